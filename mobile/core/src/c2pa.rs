@@ -0,0 +1,132 @@
+//! Detections as C2PA assertions.
+//!
+//! Provenance pipelines record where content came from in C2PA manifests.
+//! A verified detection is evidence of exactly that, so [`c2pa_assertions`]
+//! renders it as manifest assertions, in the `{"label", "data"}` layout the
+//! Python SDK's manifests use: a `c2pa.soft-binding` pointing at the
+//! watermark (its payload hash is the soft binding value the Vouch server
+//! resolves), and a `vouch.watermark` assertion with the verified signer and
+//! signing time. The host app's C2PA SDK signs them into the manifest.
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+
+use crate::{is_encrypted_covenant, upgrade_covenant, SonicError, VerificationResult, WatermarkResult};
+
+/// Soft binding algorithm name of the v3 audio watermark.
+pub const C2PA_SOFT_BINDING_ALG: &str = "com.vouch-protocol.sonic.3";
+
+/// The C2PA assertions (a JSON array of `{"label", "data"}`) for a
+/// detection and its successful verification: the soft binding, the
+/// `vouch.watermark` assertion and, when the payload has one, its covenant
+/// as `vouch.covenant` (upgraded to the current schema; an encrypted one
+/// as its envelope).
+///
+/// Only verified detections are exported: a detection without a payload
+/// hash, or a verification that is not `valid`, is an error.
+pub fn c2pa_assertions(result: WatermarkResult, verification: VerificationResult) -> Result<String, SonicError> {
+    let payload_hash = verified_payload_hash(&result, &verification)?;
+    let signed_at = result.timestamp.and_then(rfc3339);
+
+    let mut assertions = vec![
+        json!({
+            "label": "c2pa.soft-binding",
+            "data": {
+                "alg": C2PA_SOFT_BINDING_ALG,
+                "blocks": [{"scope": {}, "value": payload_hash}],
+            },
+        }),
+        json!({
+            "label": "vouch.watermark",
+            "data": {
+                "payload_hash": payload_hash,
+                "signer": verification.signer_did,
+                "key_id": verification.key_id,
+                "signature_algorithm": format!("{:?}", verification.algorithm),
+                "timestamp": result.timestamp,
+                "signed_at": signed_at,
+                "confidence": result.confidence,
+                "detection_method": result.detection_method,
+            },
+        }),
+    ];
+    if let Some(covenant) = &result.covenant_json {
+        let covenant = if is_encrypted_covenant(covenant) {
+            covenant.clone()
+        } else {
+            upgrade_covenant(covenant)?
+        };
+        let data: Value = serde_json::from_str(&covenant).map_err(|e| SonicError::InternalError(e.to_string()))?;
+        assertions.push(json!({"label": "vouch.covenant", "data": data}));
+    }
+    Ok(Value::Array(assertions).to_string())
+}
+
+/// The payload hash of a detection that `verification` verified.
+pub(crate) fn verified_payload_hash(
+    result: &WatermarkResult,
+    verification: &VerificationResult,
+) -> Result<String, SonicError> {
+    let Some(payload_hash) = result.payload_hash.clone().filter(|_| result.detected) else {
+        return Err(SonicError::InvalidConfig("no watermark was detected".into()));
+    };
+    if !verification.valid {
+        return Err(SonicError::InvalidConfig(format!(
+            "detection is not verified: {}",
+            verification.error_message.as_deref().unwrap_or_default()
+        )));
+    }
+    Ok(payload_hash)
+}
+
+/// Unix seconds as an RFC 3339 UTC instant.
+pub(crate) fn rfc3339(unix_secs: u64) -> Option<String> {
+    let instant = DateTime::from_timestamp(i64::try_from(unix_secs).ok()?, 0)?;
+    Some(instant.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exports_verified_detections_only() {
+        let result = WatermarkResult {
+            detected: true,
+            confidence: 0.9,
+            signer_did: Some("did:web:news.example".into()),
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            covenant_json: Some(r#"{"policies": {"ai_training": "DENY"}}"#.into()),
+            detection_method: "v3".into(),
+            ..Default::default()
+        };
+        let verification = VerificationResult {
+            valid: true,
+            signer_did: Some("did:web:news.example".into()),
+            key_id: Some("did:web:news.example#key-1".into()),
+            ..Default::default()
+        };
+        let assertions: Value =
+            serde_json::from_str(&c2pa_assertions(result.clone(), verification.clone()).unwrap()).unwrap();
+        assert_eq!(assertions[0]["label"], "c2pa.soft-binding");
+        assert_eq!(assertions[0]["data"]["alg"], C2PA_SOFT_BINDING_ALG);
+        assert_eq!(assertions[0]["data"]["blocks"][0]["value"], "a1b2c3d4");
+        assert_eq!(assertions[1]["data"]["signer"], "did:web:news.example");
+        assert_eq!(assertions[1]["data"]["signed_at"], "2023-11-14T22:13:20Z");
+        assert_eq!(assertions[1]["data"]["signature_algorithm"], "Ed25519");
+        assert_eq!(assertions[2]["data"]["ai_training"], false);
+
+        let forged = VerificationResult {
+            valid: false,
+            error_message: Some("Signature verification failed".into()),
+            ..verification.clone()
+        };
+        assert!(c2pa_assertions(result.clone(), forged).is_err());
+        let missed = WatermarkResult {
+            detected: false,
+            ..result
+        };
+        assert!(c2pa_assertions(missed, verification).is_err());
+    }
+}
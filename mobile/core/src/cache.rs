@@ -0,0 +1,104 @@
+//! Detection results cached by buffer content.
+//!
+//! Pipelines that retry, or fan one capture out to several consumers, hand
+//! the listener the same buffer more than once. The DSP detector is
+//! deterministic, so a repeat can be answered from a small LRU keyed by a
+//! hash of the samples instead of decoding again.
+
+use std::collections::VecDeque;
+
+use crate::WatermarkResult;
+
+/// Samples the buffer hash reads at most; longer buffers are strided down to
+/// about this many, so hashing stays cheap next to detection.
+const HASH_SAMPLES: usize = 4096;
+
+/// Least-recently-used cache of detection results, most recent last.
+#[derive(Default)]
+pub(crate) struct ResultCache {
+    entries: VecDeque<(u64, WatermarkResult)>,
+}
+
+impl ResultCache {
+    /// The result cached for `key`, which becomes the most recently used.
+    pub(crate) fn get(&mut self, key: u64) -> Option<WatermarkResult> {
+        let at = self.entries.iter().position(|(k, _)| *k == key)?;
+        let entry = self.entries.remove(at)?;
+        let result = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(result)
+    }
+
+    /// Cache `result` for `key`, evicting the least recently used entries
+    /// beyond `capacity`.
+    pub(crate) fn insert(&mut self, key: u64, result: WatermarkResult, capacity: usize) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_back((key, result));
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// FNV-1a over the length and a strided downsample of 16-bit LE PCM.
+///
+/// Identical buffers always collide, which is the point; distinct captures
+/// of the same length differ in nearly every sample, so the downsample
+/// separates them.
+pub(crate) fn buffer_hash(pcm_data: &[u8]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let samples = pcm_data.len() / 2;
+    let stride = (samples / HASH_SAMPLES).max(1);
+    let mut hash = OFFSET;
+    let mut mix = |bytes: &[u8]| {
+        for &b in bytes {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    mix(&(pcm_data.len() as u64).to_le_bytes());
+    for i in (0..samples).step_by(stride) {
+        mix(&pcm_data[i * 2..i * 2 + 2]);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(confidence: f32) -> WatermarkResult {
+        WatermarkResult {
+            confidence,
+            ..WatermarkResult::not_detected()
+        }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = ResultCache::default();
+        cache.insert(1, result(0.1), 2);
+        cache.insert(2, result(0.2), 2);
+        // Touching 1 leaves 2 as the eviction candidate.
+        assert_eq!(cache.get(1).map(|r| r.confidence), Some(0.1));
+        cache.insert(3, result(0.3), 2);
+        assert!(cache.get(2).is_none());
+        assert!(cache.get(1).is_some());
+        assert!(cache.get(3).is_some());
+    }
+
+    #[test]
+    fn test_hash_separates_content_and_length() {
+        let a: Vec<u8> = (0..20_000u32).flat_map(|i| ((i * 7) as i16).to_le_bytes()).collect();
+        let mut b = a.clone();
+        b[0] ^= 1;
+        assert_eq!(buffer_hash(&a), buffer_hash(&a.clone()));
+        assert_ne!(buffer_hash(&a), buffer_hash(&b));
+        assert_ne!(buffer_hash(&a), buffer_hash(&a[..a.len() - 2]));
+    }
+}
@@ -0,0 +1,121 @@
+//! Native audio capture for desktop hosts (feature `capture`).
+//!
+//! Mobile apps capture audio in the platform layer and push buffers in; a
+//! desktop app has no such layer. `SonicListener::start_capture` opens the
+//! default input device through `cpal` and feeds the listener itself. The
+//! device's audio callback only downmixes and queues each buffer; a capture
+//! thread, which also owns the stream, runs the streaming detector on them,
+//! so DSP work never blocks the realtime audio thread. Buffers are resampled
+//! from the device's native rate through `input_sample_rate`.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+};
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+};
+
+use crate::{dispatch::Event, SonicError, SonicListener, CONSUME_POLL_INTERVAL};
+
+/// Open the default input device and stream it into `listener` on a new
+/// capture thread, until the returned flag is set. Returns once the stream
+/// is running, or with the reason it could not be started.
+pub(crate) fn spawn(listener: Arc<SonicListener>) -> Result<Arc<AtomicBool>, SonicError> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let stopped = stop.clone();
+    thread::Builder::new()
+        .name("vouch-sonic-capture".into())
+        .spawn(move || {
+            let (tx, rx) = mpsc::channel();
+            let stream = match open_default_input(&listener, tx) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            while !stopped.load(Ordering::SeqCst) {
+                match rx.recv_timeout(CONSUME_POLL_INTERVAL) {
+                    Ok(samples) => {
+                        listener.push_samples(&samples);
+                    }
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            drop(stream);
+        })
+        .map_err(|e| capture_error(format!("cannot spawn capture thread: {e}")))?;
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(stop),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(capture_error("capture thread exited")),
+    }
+}
+
+/// Build and start an input stream on the default device that sends mono
+/// buffers to `tx`, with the listener set to resample from the device rate.
+fn open_default_input(listener: &Arc<SonicListener>, tx: Sender<Vec<f32>>) -> Result<Stream, SonicError> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| capture_error("no default input device"))?;
+    let supported = device.default_input_config().map_err(capture_error)?;
+    let config: StreamConfig = supported.config();
+
+    let mut listener_config = listener.get_config();
+    let rate = config.sample_rate.0;
+    listener_config.input_sample_rate = (rate != listener_config.sample_rate).then_some(rate);
+    listener.update_config(listener_config)?;
+
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => build::<f32>(&device, &config, listener, tx),
+        SampleFormat::I16 => build::<i16>(&device, &config, listener, tx),
+        SampleFormat::U16 => build::<u16>(&device, &config, listener, tx),
+        SampleFormat::I32 => build::<i32>(&device, &config, listener, tx),
+        format => return Err(capture_error(format!("unsupported sample format {format}"))),
+    }?;
+    stream.play().map_err(capture_error)?;
+    Ok(stream)
+}
+
+fn build<T>(
+    device: &Device,
+    config: &StreamConfig,
+    listener: &Arc<SonicListener>,
+    tx: Sender<Vec<f32>>,
+) -> Result<Stream, SonicError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    let errors = listener.clone();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                let mono = data
+                    .chunks_exact(channels)
+                    .map(|frame| frame.iter().map(|&s| s.to_sample::<f32>()).sum::<f32>() / channels as f32)
+                    .collect();
+                // The capture thread is gone once capture stops.
+                let _ = tx.send(mono);
+            },
+            move |e| errors.emit(Event::Error(format!("audio capture error: {e}"))),
+            None,
+        )
+        .map_err(capture_error)
+}
+
+fn capture_error(e: impl std::fmt::Display) -> SonicError {
+    SonicError::AudioInitFailed(e.to_string())
+}
@@ -0,0 +1,164 @@
+//! Compressed audio file detection (feature `compressed`).
+//!
+//! Decodes MP3, AAC (in MP4/M4A), FLAC, Ogg Vorbis and WAV with `symphonia`,
+//! pure Rust with no system codecs, then runs the same timed scan as
+//! [`detect_watermark_in_wav`](crate::detect_watermark_in_wav). The format is
+//! probed from the file contents, with the extension as a hint. Only the
+//! first audio track is decoded, and packets that fail to decode are skipped,
+//! as a player would.
+
+use std::{fs::File, path::Path};
+
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::{wav::scan_mono, SonicError, TimedDetection};
+
+/// Outcome of [`detect_watermark_in_file`].
+#[derive(Debug, Clone)]
+pub struct FileScan {
+    /// Codec the audio was decoded from, by symphonia's short name
+    /// (e.g. "mp3", "aac", "flac", "vorbis", "pcm_s16le")
+    pub codec: String,
+    /// Sample rate of the decoded audio, in Hz
+    pub sample_rate: u32,
+    /// Channels in the decoded audio, averaged to mono for detection
+    pub channels: u32,
+    /// Watermarks found, in file order
+    pub detections: Vec<TimedDetection>,
+}
+
+/// Decode the audio file at `path` and scan it for watermarks.
+pub fn detect_watermark_in_file(path: impl AsRef<Path>) -> Result<FileScan, SonicError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(decode_error)?;
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            MediaSourceStream::new(Box::new(file), Default::default()),
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(decode_error)?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| decode_error("no audio track"))?;
+    let track_id = track.id;
+    let codecs = symphonia::default::get_codecs();
+    let codec = codecs
+        .get_codec(track.codec_params.codec)
+        .map_or_else(|| "unknown".to_string(), |d| d.short_name.to_string());
+    let mut decoder = codecs
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(decode_error)?;
+
+    let mut mono = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    let (mut sample_rate, mut channels) = (track.codec_params.sample_rate, None);
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(decode_error(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(decode_error(e)),
+        };
+        let spec = *decoded.spec();
+        let count = spec.channels.count();
+        if count == 0 {
+            continue;
+        }
+        sample_rate = Some(spec.rate);
+        channels = Some(count);
+        let needed = decoded.capacity() * count;
+        if buffer.as_ref().is_none_or(|b| b.capacity() < needed) {
+            buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+        }
+        let buffer = buffer.as_mut().expect("sample buffer was just sized");
+        buffer.copy_interleaved_ref(decoded);
+        mono.extend(
+            buffer
+                .samples()
+                .chunks_exact(count)
+                .map(|frame| frame.iter().sum::<f32>() / count as f32),
+        );
+    }
+
+    let sample_rate = sample_rate.ok_or_else(|| decode_error("unknown sample rate"))?;
+    Ok(FileScan {
+        codec,
+        sample_rate,
+        channels: channels.unwrap_or(0) as u32,
+        detections: scan_mono(mono, sample_rate, None)?.detections,
+    })
+}
+
+fn decode_error(e: impl std::fmt::Display) -> SonicError {
+    SonicError::ProcessingFailed(format!("audio decode failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SonicConfig, Synthesizer};
+    use hound::{SampleFormat, WavSpec, WavWriter};
+
+    #[test]
+    fn test_decodes_file_and_reports_codec() {
+        let sample_rate = 48_000;
+        let marked = Synthesizer::new(SonicConfig {
+            sample_rate,
+            ..Default::default()
+        })
+        .unwrap()
+        .synthesize("did:key:z6MkFileScan", 1_700_000_000_000, 8_000)
+        .unwrap();
+
+        // No extension: the format is probed from the contents.
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("download");
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for &s in &marked.samples {
+            writer.write_sample((s.clamp(-1.0, 1.0) * 32_767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let scan = detect_watermark_in_file(&path).unwrap();
+        assert_eq!(scan.codec, "pcm_s16le");
+        assert_eq!((scan.sample_rate, scan.channels), (sample_rate, 1));
+        assert_eq!(scan.detections.len(), 1);
+        assert_eq!(scan.detections[0].result.payload_hash, Some(marked.payload_hash));
+
+        std::fs::write(dir.path().join("junk.mp3"), b"not audio at all").unwrap();
+        assert!(matches!(
+            detect_watermark_in_file(dir.path().join("junk.mp3")),
+            Err(SonicError::ProcessingFailed(_))
+        ));
+    }
+}
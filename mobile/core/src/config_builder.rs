@@ -0,0 +1,250 @@
+//! Checked construction of listener configurations.
+//!
+//! A `SonicConfig` written as a struct literal is only checked when the
+//! listener is created, and the error then describes the first bad field
+//! the check happens to reach. [`SonicConfigBuilder`] checks each setting as
+//! it is made, so a bad value fails at the call that set it, with an
+//! `InvalidConfig` naming the field and the value it was given.
+
+use std::{fmt::Debug, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::{ConfigPreset, DownmixMode, PcmFormat, SensitivityProfile, SonicConfig, SonicError, WindowFunction};
+
+/// Builds a `SonicConfig` setting by setting, starting from the defaults.
+/// The configuration it holds is valid at every step.
+///
+/// Each setter sets the `SonicConfig` field of the same name and returns
+/// the builder, so calls chain; setters for fields with a valid range throw
+/// `InvalidConfig` instead, leaving the builder as it was. Swift and Kotlin
+/// get it from its constructors, Rust from
+/// `Arc::new(SonicConfigBuilder::new())`.
+pub struct SonicConfigBuilder {
+    config: Mutex<SonicConfig>,
+}
+
+impl SonicConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(SonicConfig::default()),
+        }
+    }
+
+    /// Start from the settings of `preset`.
+    pub fn from_preset(preset: ConfigPreset) -> Self {
+        Self {
+            config: Mutex::new(SonicConfig::preset(preset)),
+        }
+    }
+
+    /// Start from `config`, e.g. a listener's `get_config`, which must be
+    /// valid itself.
+    pub fn from_config(config: SonicConfig) -> Result<Self, SonicError> {
+        config.validate()?;
+        Ok(Self {
+            config: Mutex::new(config),
+        })
+    }
+
+    fn set(self: Arc<Self>, change: impl FnOnce(&mut SonicConfig)) -> Arc<Self> {
+        change(&mut self.config.lock());
+        self
+    }
+
+    /// Apply `change`, setting `field` to `value`, if the result is valid.
+    /// The held configuration was valid before, so a failed check is about
+    /// `field`.
+    fn try_set<T: Debug>(
+        self: Arc<Self>,
+        field: &str,
+        value: T,
+        change: impl FnOnce(&mut SonicConfig, T),
+    ) -> Result<Arc<Self>, SonicError> {
+        {
+            let mut config = self.config.lock();
+            let described = format!("{value:?}");
+            let mut candidate = config.clone();
+            change(&mut candidate, value);
+            candidate.validate().map_err(|e| match e {
+                SonicError::InvalidSampleRate(_) => {
+                    SonicError::InvalidConfig(format!("{field} must be between 8000 and 96000 Hz, got {described}"))
+                }
+                SonicError::InvalidConfig(reason) => SonicError::InvalidConfig(format!("{reason}, got {described}")),
+                e => e,
+            })?;
+            *config = candidate;
+        }
+        Ok(self)
+    }
+
+    pub fn sample_rate(self: Arc<Self>, sample_rate: u32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("sample_rate", sample_rate, |c, v| c.sample_rate = v)
+    }
+
+    pub fn input_sample_rate(self: Arc<Self>, input_sample_rate: Option<u32>) -> Result<Arc<Self>, SonicError> {
+        self.try_set("input_sample_rate", input_sample_rate, |c, v| c.input_sample_rate = v)
+    }
+
+    pub fn frame_size_ms(self: Arc<Self>, frame_size_ms: u32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("frame_size_ms", frame_size_ms, |c, v| c.frame_size_ms = v)
+    }
+
+    pub fn detection_threshold(self: Arc<Self>, detection_threshold: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("detection_threshold", detection_threshold, |c, v| c.detection_threshold = v)
+    }
+
+    pub fn adaptive_threshold_sigmas(self: Arc<Self>, sigmas: Option<f32>) -> Result<Arc<Self>, SonicError> {
+        self.try_set("adaptive_threshold_sigmas", sigmas, |c, v| c.adaptive_threshold_sigmas = v)
+    }
+
+    pub fn min_detection_frames(self: Arc<Self>, min_detection_frames: u32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("min_detection_frames", min_detection_frames, |c, v| c.min_detection_frames = v)
+    }
+
+    /// Set the detection settings of `profile`, as
+    /// `SonicConfig::with_sensitivity` does.
+    pub fn sensitivity(self: Arc<Self>, profile: SensitivityProfile) -> Arc<Self> {
+        self.set(|c| *c = c.clone().with_sensitivity(profile))
+    }
+
+    pub fn spreading_factor(self: Arc<Self>, spreading_factor: u32) -> Arc<Self> {
+        self.set(|c| c.spreading_factor = spreading_factor)
+    }
+
+    pub fn enable_chirp_sync(self: Arc<Self>, enable_chirp_sync: bool) -> Arc<Self> {
+        self.set(|c| c.enable_chirp_sync = enable_chirp_sync)
+    }
+
+    pub fn max_clipping_ratio(self: Arc<Self>, max_clipping_ratio: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("max_clipping_ratio", max_clipping_ratio, |c, v| c.max_clipping_ratio = v)
+    }
+
+    pub fn min_signal_variance(self: Arc<Self>, min_signal_variance: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("min_signal_variance", min_signal_variance, |c, v| c.min_signal_variance = v)
+    }
+
+    pub fn max_level_callback_hz(self: Arc<Self>, max_level_callback_hz: u32) -> Arc<Self> {
+        self.set(|c| c.max_level_callback_hz = max_level_callback_hz)
+    }
+
+    pub fn min_path_prominence(self: Arc<Self>, min_path_prominence: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("min_path_prominence", min_path_prominence, |c, v| c.min_path_prominence = v)
+    }
+
+    pub fn collect_config_snapshot(self: Arc<Self>, collect_config_snapshot: bool) -> Arc<Self> {
+        self.set(|c| c.collect_config_snapshot = collect_config_snapshot)
+    }
+
+    pub fn level_floor_db(self: Arc<Self>, level_floor_db: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("level_floor_db", level_floor_db, |c, v| c.level_floor_db = v)
+    }
+
+    pub fn signer_confirmation_threshold(self: Arc<Self>, threshold: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("signer_confirmation_threshold", threshold, |c, v| {
+            c.signer_confirmation_threshold = v
+        })
+    }
+
+    pub fn downmix_mode(self: Arc<Self>, downmix_mode: DownmixMode) -> Arc<Self> {
+        self.set(|c| c.downmix_mode = downmix_mode)
+    }
+
+    pub fn correlation_search_step(self: Arc<Self>, step: Option<u32>) -> Result<Arc<Self>, SonicError> {
+        self.try_set("correlation_search_step", step, |c, v| c.correlation_search_step = v)
+    }
+
+    pub fn exclude_dc_and_nyquist(self: Arc<Self>, exclude_dc_and_nyquist: bool) -> Arc<Self> {
+        self.set(|c| c.exclude_dc_and_nyquist = exclude_dc_and_nyquist)
+    }
+
+    pub fn stft_window(self: Arc<Self>, stft_window: WindowFunction) -> Arc<Self> {
+        self.set(|c| c.stft_window = stft_window)
+    }
+
+    pub fn stft_overlap(self: Arc<Self>, stft_overlap: f32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("stft_overlap", stft_overlap, |c, v| c.stft_overlap = v)
+    }
+
+    pub fn stft_fft_size(self: Arc<Self>, stft_fft_size: u32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("stft_fft_size", stft_fft_size, |c, v| c.stft_fft_size = v)
+    }
+
+    pub fn max_payload_bytes(self: Arc<Self>, max_payload_bytes: Option<u32>) -> Result<Arc<Self>, SonicError> {
+        self.try_set("max_payload_bytes", max_payload_bytes, |c, v| c.max_payload_bytes = v)
+    }
+
+    pub fn result_cache_capacity(self: Arc<Self>, result_cache_capacity: u32) -> Arc<Self> {
+        self.set(|c| c.result_cache_capacity = result_cache_capacity)
+    }
+
+    pub fn pilot_gate(self: Arc<Self>, pilot_gate: bool) -> Arc<Self> {
+        self.set(|c| c.pilot_gate = pilot_gate)
+    }
+
+    pub fn channels(self: Arc<Self>, channels: u32) -> Result<Arc<Self>, SonicError> {
+        self.try_set("channels", channels, |c, v| c.channels = v)
+    }
+
+    pub fn pcm_format(self: Arc<Self>, pcm_format: Option<PcmFormat>) -> Result<Arc<Self>, SonicError> {
+        self.try_set("pcm_format", pcm_format, |c, v| c.pcm_format = v)
+    }
+
+    pub fn duplicate_window_secs(self: Arc<Self>, duplicate_window_secs: u32) -> Arc<Self> {
+        self.set(|c| c.duplicate_window_secs = duplicate_window_secs)
+    }
+
+    pub fn still_present_interval_secs(self: Arc<Self>, still_present_interval_secs: u32) -> Arc<Self> {
+        self.set(|c| c.still_present_interval_secs = still_present_interval_secs)
+    }
+
+    /// The configuration built so far, ready for `SonicListener::new`.
+    pub fn build(&self) -> SonicConfig {
+        self.config.lock().clone()
+    }
+}
+
+impl Default for SonicConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bad_settings_fail_where_they_are_made() {
+        let builder = Arc::new(SonicConfigBuilder::new())
+            .sample_rate(44_100)
+            .unwrap()
+            .pilot_gate(true)
+            .detection_threshold(0.7)
+            .unwrap();
+
+        let message = |result: Result<Arc<SonicConfigBuilder>, SonicError>| match result {
+            Err(SonicError::InvalidConfig(message)) => message,
+            other => panic!("expected InvalidConfig, got {:?}", other.map(|b| b.build())),
+        };
+        assert_eq!(
+            message(builder.clone().input_sample_rate(Some(4_000))),
+            "input_sample_rate must be between 8000 and 96000 Hz, got Some(4000)"
+        );
+        assert_eq!(
+            message(builder.clone().frame_size_ms(5)),
+            "frame_size_ms must be between 10 and 1000, got 5"
+        );
+        assert!(message(builder.clone().stft_overlap(0.95)).starts_with("stft_overlap"));
+
+        // Failed settings leave the builder as it was.
+        let config = builder.build();
+        assert_eq!((config.sample_rate, config.input_sample_rate, config.frame_size_ms), (44_100, None, 50));
+        assert!(config.pilot_gate);
+        assert_eq!(config.detection_threshold, 0.7);
+        assert!(config.validate().is_ok());
+
+        assert!(SonicConfigBuilder::from_config(config.clone()).is_ok());
+        assert!(SonicConfigBuilder::from_config(SonicConfig { channels: 0, ..config }).is_err());
+    }
+}
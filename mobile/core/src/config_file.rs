@@ -0,0 +1,79 @@
+//! Listener configurations as JSON or TOML (feature `config-files`).
+//!
+//! Server-side tooling and QA tune detection settings too, and a profile
+//! they share should load in a mobile build without recompiling. A
+//! `SonicConfig` reads from and writes to JSON or TOML with the field names
+//! of the struct; fields a profile leaves out take their defaults, and an
+//! unknown field (a misspelling, say) is rejected rather than ignored. A
+//! loaded profile is validated like any configuration.
+
+use crate::{SonicConfig, SonicError};
+
+impl SonicConfig {
+    /// The configuration in a JSON profile.
+    pub fn from_json_str(json: &str) -> Result<Self, SonicError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|e| SonicError::InvalidConfig(format!("config JSON: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The configuration as a JSON profile, every field written out.
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a SonicConfig always serializes to JSON")
+    }
+
+    /// The configuration in a TOML profile.
+    pub fn from_toml_str(toml: &str) -> Result<Self, SonicError> {
+        let config: Self = toml::from_str(toml).map_err(|e| SonicError::InvalidConfig(format!("config TOML: {e}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The configuration as a TOML profile; fields that are `None` are left
+    /// out, which reads back as `None`.
+    pub fn to_toml_string(&self) -> String {
+        // Through a `toml::Value`, which puts `pcm_format`'s table after the
+        // plain values as TOML requires.
+        toml::Value::try_from(self)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .expect("a SonicConfig always serializes to TOML")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigPreset, PcmFormat, SampleEncoding};
+
+    #[test]
+    fn test_profiles_round_trip() {
+        let config = SonicConfig {
+            pcm_format: Some(PcmFormat {
+                bits_per_sample: 24,
+                encoding: SampleEncoding::SignedInt,
+                big_endian: false,
+                interleaved: true,
+            }),
+            correlation_search_step: Some(4_410),
+            ..SonicConfig::preset(ConfigPreset::Music)
+        };
+        assert_eq!(SonicConfig::from_json_str(&config.to_json_string()).unwrap(), config);
+        assert_eq!(SonicConfig::from_toml_str(&config.to_toml_string()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_partial_profiles_take_defaults() {
+        let config = SonicConfig::from_toml_str("sample_rate = 44100\ndownmix_mode = \"MidSide\"\n").unwrap();
+        assert_eq!((config.sample_rate, config.downmix_mode), (44_100, crate::DownmixMode::MidSide));
+        assert_eq!(config.detection_threshold, SonicConfig::default().detection_threshold);
+
+        let invalid = |result: Result<SonicConfig, SonicError>| matches!(result, Err(SonicError::InvalidConfig(_)));
+        assert!(invalid(SonicConfig::from_json_str(r#"{"sample_rte": 44100}"#)));
+        assert!(invalid(SonicConfig::from_json_str(r#"{"frame_size_ms": 5}"#)));
+        assert!(matches!(
+            SonicConfig::from_json_str(r#"{"sample_rate": 4000}"#),
+            Err(SonicError::InvalidSampleRate(4000))
+        ));
+    }
+}
@@ -0,0 +1,162 @@
+//! Covenant canonicalization for signature verification.
+//!
+//! The signer serializes the covenant (the usage policy carried with a
+//! watermark) before signing it, and the verifier must reproduce those bytes
+//! exactly: `{"a":1,"b":2}` and `{"b": 2, "a": 1}` are the same covenant but
+//! different signing input. Both sides use the JSON Canonicalization Scheme
+//! (RFC 8785), following the same rules as `vouch-core`'s `jcs` module and the
+//! Python and TypeScript SDKs:
+//!   - object members are sorted by UTF-16 code units (ECMAScript sort order),
+//!   - strings are escaped as `JSON.stringify` escapes them,
+//!   - numbers use ECMAScript `ToString`, and -0 normalizes to 0.
+
+use std::cmp::Ordering;
+
+use serde_json::{Number, Value};
+
+use crate::SonicError;
+
+/// Canonicalize covenant JSON to its RFC 8785 (JCS) bytes: members sorted by
+/// UTF-16 code units, no insignificant whitespace, ECMAScript number
+/// formatting and minimal string escaping.
+///
+/// The covenant must be a JSON object.
+pub fn canonicalize_covenant(json: &str) -> Result<Vec<u8>, SonicError> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| SonicError::InvalidConfig(format!("covenant is not valid JSON: {e}")))?;
+    if !value.is_object() {
+        return Err(SonicError::InvalidConfig("covenant must be a JSON object".into()));
+    }
+    let mut out = String::new();
+    serialize(&value, &mut out);
+    Ok(out.into_bytes())
+}
+
+fn serialize(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(n)),
+        Value::String(s) => escape_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                serialize(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| utf16_cmp(a, b));
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                escape_string(key, out);
+                out.push(':');
+                serialize(&map[key], out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// ECMAScript string order: by UTF-16 code units, so astral characters
+/// (surrogate pairs) sort before U+E000..U+FFFF.
+fn utf16_cmp(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+/// serde_json escapes exactly as `JSON.stringify` does: `"` and `\`, the
+/// short forms `\b \t \n \f \r`, other control characters as `\u00xx`,
+/// everything else raw UTF-8.
+fn escape_string(s: &str, out: &mut String) {
+    out.push_str(&serde_json::to_string(s).expect("a string always serializes"));
+}
+
+/// ECMAScript `Number.prototype.toString` (RFC 8785 section 3.2.2.3).
+fn format_number(n: &Number) -> String {
+    if let Some(i) = n.as_i64() {
+        return i.to_string();
+    }
+    if let Some(u) = n.as_u64() {
+        return u.to_string();
+    }
+    let f = n.as_f64().expect("a serde_json number is i64, u64 or f64");
+    if f == 0.0 {
+        // Both 0.0 and -0.0.
+        return "0".into();
+    }
+    ryu_js::Buffer::new().format(f).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(json: &str) -> String {
+        String::from_utf8(canonicalize_covenant(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_rfc8785_sample() {
+        // RFC 8785 section 3.2.2: number formatting and string escaping.
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(input),
+            "{\"literals\":[null,true,false],\
+             \"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27],\
+             \"string\":\"\u{20ac}$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}"
+        );
+    }
+
+    #[test]
+    fn test_rfc8785_unicode_key_order() {
+        // RFC 8785 section 3.2.3: keys sort by UTF-16 code units, so the
+        // surrogate pair of U+1F600 sorts before U+FB33.
+        let input = r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+        assert_eq!(
+            canonical(input),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn test_nested_covenant_is_order_independent() {
+        let signed = r#"{"@context":"https://vouch-protocol.com/covenants/v1",
+            "permissions":{"voice_cloning":false,"ai_training":false,"commercial_use":true},
+            "custom_policies":{"retention_days":30,"regions":["EU","US"]}}"#;
+        let received = r#"{ "custom_policies": { "regions": ["EU", "US"], "retention_days": 30.0 },
+            "permissions": { "commercial_use": true, "ai_training": false, "voice_cloning": false },
+            "@context": "https://vouch-protocol.com/covenants/v1" }"#;
+        let expected = "{\"@context\":\"https://vouch-protocol.com/covenants/v1\",\
+             \"custom_policies\":{\"regions\":[\"EU\",\"US\"],\"retention_days\":30},\
+             \"permissions\":{\"ai_training\":false,\"commercial_use\":true,\"voice_cloning\":false}}";
+        assert_eq!(canonical(signed), expected);
+        assert_eq!(canonical(received), expected);
+    }
+
+    #[test]
+    fn test_rejects_non_object_covenant() {
+        assert!(canonicalize_covenant("{\"a\":").is_err());
+        assert!(canonicalize_covenant("[1,2]").is_err());
+    }
+}
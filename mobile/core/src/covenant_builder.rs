@@ -0,0 +1,167 @@
+//! Fluent construction of covenants on the embedding side.
+//!
+//! Embedding apps should not hand-write covenant JSON: a misspelled term is
+//! silently an unknown field, and a boolean written as `"false"` denies
+//! nothing. [`CovenantBuilder`] sets terms by [`UsageKind`] and produces the
+//! canonical encoding `SonicEmbedder::embed_buffer` signs.
+
+use std::sync::Arc;
+
+use chrono::DateTime;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
+
+use crate::{canonicalize_covenant, covenant_to_json, Covenant, SonicError, UsageKind, CURRENT_COVENANT_VERSION};
+
+/// Builds a covenant term by term, starting from the Python SDK's defaults
+/// (every use allowed except voice cloning, attribution required).
+///
+/// Each method returns the builder, so calls chain; Swift and Kotlin get it
+/// from its constructor, Rust from `Arc::new(CovenantBuilder::new())`.
+pub struct CovenantBuilder {
+    covenant: Mutex<Covenant>,
+    custom_policies: Mutex<Map<String, Value>>,
+}
+
+impl CovenantBuilder {
+    pub fn new() -> Self {
+        Self {
+            covenant: Mutex::new(Covenant {
+                version: CURRENT_COVENANT_VERSION.into(),
+                ai_training: true,
+                ai_inference: true,
+                voice_cloning: false,
+                derivative_works: true,
+                commercial_use: true,
+                attribution_required: true,
+                context_restrictions: Vec::new(),
+                expiration_date: None,
+                jurisdictions: Vec::new(),
+                custom_policies_json: "{}".into(),
+                unknown_fields_json: "{}".into(),
+            }),
+            custom_policies: Mutex::new(Map::new()),
+        }
+    }
+
+    fn set(self: Arc<Self>, usage: UsageKind, allowed: bool) -> Arc<Self> {
+        {
+            let mut covenant = self.covenant.lock();
+            let term = match usage {
+                UsageKind::AiTraining => &mut covenant.ai_training,
+                UsageKind::AiInference => &mut covenant.ai_inference,
+                UsageKind::VoiceCloning => &mut covenant.voice_cloning,
+                UsageKind::DerivativeWorks => &mut covenant.derivative_works,
+                UsageKind::CommercialUse => &mut covenant.commercial_use,
+            };
+            *term = allowed;
+        }
+        self
+    }
+
+    /// Permit `usage`.
+    pub fn allow(self: Arc<Self>, usage: UsageKind) -> Arc<Self> {
+        self.set(usage, true)
+    }
+
+    /// Forbid `usage`.
+    pub fn deny(self: Arc<Self>, usage: UsageKind) -> Arc<Self> {
+        self.set(usage, false)
+    }
+
+    /// Forbid training AI models on the audio.
+    pub fn deny_ai_training(self: Arc<Self>) -> Arc<Self> {
+        self.deny(UsageKind::AiTraining)
+    }
+
+    /// Permit derivative works, provided they credit the creator.
+    pub fn allow_reuse_with_attribution(self: Arc<Self>) -> Arc<Self> {
+        self.covenant.lock().attribution_required = true;
+        self.allow(UsageKind::DerivativeWorks)
+    }
+
+    /// Whether permitted uses must credit the creator.
+    pub fn require_attribution(self: Arc<Self>, required: bool) -> Arc<Self> {
+        self.covenant.lock().attribution_required = required;
+        self
+    }
+
+    /// End every permission at `timestamp` (Unix seconds).
+    pub fn expires_at(self: Arc<Self>, timestamp: u64) -> Arc<Self> {
+        let expiration = i64::try_from(timestamp)
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|instant| instant.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        self.covenant.lock().expiration_date = expiration;
+        self
+    }
+
+    /// Limit the permissions to these ISO 3166-1 alpha-2 jurisdictions.
+    pub fn limit_to_jurisdictions(self: Arc<Self>, jurisdictions: Vec<String>) -> Arc<Self> {
+        self.covenant.lock().jurisdictions = jurisdictions.iter().map(|j| j.to_ascii_uppercase()).collect();
+        self
+    }
+
+    /// Limit use to these contexts (e.g. `news`, `education`).
+    pub fn limit_to_contexts(self: Arc<Self>, contexts: Vec<String>) -> Arc<Self> {
+        self.covenant.lock().context_restrictions = contexts;
+        self
+    }
+
+    /// Add one of the creator's own terms.
+    pub fn custom_policy(self: Arc<Self>, name: String, allowed: bool) -> Arc<Self> {
+        self.custom_policies.lock().insert(name, Value::Bool(allowed));
+        self
+    }
+
+    /// The covenant built so far.
+    pub fn build(&self) -> Covenant {
+        Covenant {
+            custom_policies_json: Value::Object(self.custom_policies.lock().clone()).to_string(),
+            ..self.covenant.lock().clone()
+        }
+    }
+
+    /// The covenant's canonical (RFC 8785) JSON, ready to pass as
+    /// `covenant_json` to the embedder.
+    pub fn to_json(&self) -> Result<String, SonicError> {
+        let canonical = canonicalize_covenant(&covenant_to_json(self.build())?)?;
+        String::from_utf8(canonical).map_err(|e| SonicError::InternalError(e.to_string()))
+    }
+}
+
+impl Default for CovenantBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_permitted, parse_covenant};
+
+    #[test]
+    fn test_builds_canonical_covenant() {
+        let json = Arc::new(CovenantBuilder::new())
+            .deny_ai_training()
+            .allow_reuse_with_attribution()
+            .allow(UsageKind::VoiceCloning)
+            .expires_at(4_102_444_800)
+            .limit_to_jurisdictions(vec!["de".into()])
+            .custom_policy("remixing".into(), false)
+            .to_json()
+            .unwrap();
+        assert_eq!(canonicalize_covenant(&json).unwrap(), json.as_bytes());
+
+        let covenant = parse_covenant(&json).unwrap();
+        assert!(!covenant.ai_training && covenant.voice_cloning && covenant.derivative_works);
+        assert_eq!(covenant.expiration_date.as_deref(), Some("2100-01-01T00:00:00Z"));
+        assert_eq!(covenant.jurisdictions, ["DE"]);
+        assert_eq!(covenant.custom_policies_json, r#"{"remixing":false}"#);
+
+        let decide = |usage| is_permitted(Some(json.clone()), usage, Some("DE".into())).unwrap();
+        assert!(!decide(UsageKind::AiTraining).permitted);
+        assert!(decide(UsageKind::DerivativeWorks).attribution_required);
+    }
+}
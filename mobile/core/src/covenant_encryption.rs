@@ -0,0 +1,231 @@
+//! Recipient-bound covenant encryption.
+//!
+//! A covenant is public by default: anyone who detects the watermark and
+//! fetches its record can read the usage terms. Creators who license terms
+//! to specific verifiers encrypt the covenant to those verifiers' X25519
+//! keys instead. The covenant is sealed once under a random content key
+//! with ChaCha20-Poly1305, and the content key is wrapped for each
+//! recipient under a key derived (HKDF-SHA256) from an ephemeral X25519
+//! exchange with that recipient.
+//!
+//! The result is itself a JSON object, so it takes the covenant's place in
+//! the payload unchanged: the signer signs the envelope, and a verifier
+//! without a key can still check the signature.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{canonicalize_covenant, SonicError};
+
+/// `enc` of an encrypted covenant envelope.
+pub const COVENANT_ENCRYPTION: &str = "X25519-ChaCha20Poly1305";
+
+/// HKDF `info` of a recipient's key-wrapping key.
+const KEY_WRAP_INFO: &[u8] = b"vouch covenant key wrap v1";
+
+/// Associated data of the covenant ciphertext.
+const CONTENT_AAD: &[u8] = b"vouch covenant v1";
+
+/// Every wrapping key encrypts exactly one content key, so a fixed nonce is
+/// never reused under the same key.
+const KEY_WRAP_NONCE: [u8; 12] = [0; 12];
+
+/// Supplies a verifier's X25519 private keys for covenant decryption.
+/// Implemented by the host app, which keeps them in the keychain or
+/// keystore.
+pub trait CovenantKeyProvider: Send + Sync {
+    /// The 32-byte private key whose public key is `recipient_public_key`,
+    /// or `None` if this verifier does not hold it.
+    fn x25519_private_key(&self, recipient_public_key: Vec<u8>) -> Option<Vec<u8>>;
+}
+
+/// An encrypted covenant, with binary fields base64url-encoded.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    enc: String,
+    /// Ephemeral X25519 public key
+    epk: String,
+    nonce: String,
+    ciphertext: String,
+    recipients: Vec<Recipient>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Recipient {
+    /// The recipient's X25519 public key
+    rpk: String,
+    /// The content key, encrypted under the recipient's wrapping key
+    wrapped_key: String,
+}
+
+fn x25519_key(bytes: &[u8], what: &str) -> Result<[u8; 32], SonicError> {
+    bytes
+        .try_into()
+        .map_err(|_| SonicError::InvalidKey(format!("X25519 {what} must be 32 bytes, got {}", bytes.len())))
+}
+
+/// Cipher for the content key of the recipient `rpk`, from the X25519
+/// `shared` secret with ephemeral key `epk`.
+fn key_wrap_cipher(shared: &[u8; 32], epk: &[u8; 32], rpk: &[u8; 32]) -> ChaCha20Poly1305 {
+    let mut salt = [0u8; 64];
+    salt[..32].copy_from_slice(epk);
+    salt[32..].copy_from_slice(rpk);
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(KEY_WRAP_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+/// The X25519 public key of `private_key`, to hand to creators encrypting
+/// covenants for this verifier.
+pub fn x25519_public_key(private_key: &[u8]) -> Result<Vec<u8>, SonicError> {
+    let secret = StaticSecret::from(x25519_key(private_key, "private key")?);
+    Ok(PublicKey::from(&secret).as_bytes().to_vec())
+}
+
+/// Encrypt `covenant_json` so that only holders of one of the X25519
+/// `recipient_public_keys` can read it, returning the envelope JSON to use
+/// as the payload's covenant. The covenant is canonicalized (RFC 8785)
+/// before encryption, so it decrypts to the bytes a verifier would sign.
+pub fn encrypt_covenant(covenant_json: &str, recipient_public_keys: Vec<Vec<u8>>) -> Result<String, SonicError> {
+    if recipient_public_keys.is_empty() {
+        return Err(SonicError::InvalidConfig("an encrypted covenant needs at least one recipient".into()));
+    }
+    let plaintext = canonicalize_covenant(covenant_json)?;
+
+    let mut content_key = [0u8; 32];
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut content_key);
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&content_key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: &plaintext, aad: CONTENT_AAD })
+        .map_err(|_| SonicError::InternalError("covenant encryption failed".into()))?;
+
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let epk = PublicKey::from(&ephemeral).to_bytes();
+    let recipients = recipient_public_keys
+        .iter()
+        .map(|rpk| {
+            let rpk = x25519_key(rpk, "public key")?;
+            let shared = ephemeral.diffie_hellman(&PublicKey::from(rpk));
+            if !shared.was_contributory() {
+                return Err(SonicError::InvalidKey("X25519 public key is a low-order point".into()));
+            }
+            let wrapped_key = key_wrap_cipher(shared.as_bytes(), &epk, &rpk)
+                .encrypt(Nonce::from_slice(&KEY_WRAP_NONCE), content_key.as_ref())
+                .map_err(|_| SonicError::InternalError("content key wrapping failed".into()))?;
+            Ok(Recipient {
+                rpk: URL_SAFE_NO_PAD.encode(rpk),
+                wrapped_key: URL_SAFE_NO_PAD.encode(wrapped_key),
+            })
+        })
+        .collect::<Result<Vec<_>, SonicError>>()?;
+
+    let envelope = Envelope {
+        enc: COVENANT_ENCRYPTION.into(),
+        epk: URL_SAFE_NO_PAD.encode(epk),
+        nonce: URL_SAFE_NO_PAD.encode(nonce),
+        ciphertext: URL_SAFE_NO_PAD.encode(ciphertext),
+        recipients,
+    };
+    serde_json::to_string(&envelope).map_err(|e| SonicError::InternalError(e.to_string()))
+}
+
+/// Whether `covenant_json` is an envelope from [`encrypt_covenant`].
+pub fn is_encrypted_covenant(covenant_json: &str) -> bool {
+    serde_json::from_str::<Envelope>(covenant_json).is_ok_and(|envelope| envelope.enc == COVENANT_ENCRYPTION)
+}
+
+/// Decrypt an encrypted covenant with whichever recipient key
+/// `key_provider` holds, returning the covenant's canonical JSON. Fails
+/// with `DecryptionFailed` when the provider holds none of the recipients'
+/// keys or the envelope has been tampered with.
+pub fn decrypt_covenant(
+    covenant_json: &str,
+    key_provider: Box<dyn CovenantKeyProvider>,
+) -> Result<String, SonicError> {
+    let malformed = |what: &str| SonicError::DecryptionFailed(format!("malformed envelope: {what}"));
+    let envelope: Envelope = serde_json::from_str(covenant_json).map_err(|_| malformed("not an encrypted covenant"))?;
+    if envelope.enc != COVENANT_ENCRYPTION {
+        return Err(SonicError::DecryptionFailed(format!("unsupported encryption {}", envelope.enc)));
+    }
+    let decode = |field: &str, what: &str| URL_SAFE_NO_PAD.decode(field).map_err(|_| malformed(what));
+    let epk: [u8; 32] = decode(&envelope.epk, "epk")?.try_into().map_err(|_| malformed("epk"))?;
+    let nonce: [u8; 12] = decode(&envelope.nonce, "nonce")?.try_into().map_err(|_| malformed("nonce"))?;
+    let ciphertext = decode(&envelope.ciphertext, "ciphertext")?;
+
+    for recipient in &envelope.recipients {
+        let Ok(rpk) = decode(&recipient.rpk, "rpk").and_then(|rpk| x25519_key(&rpk, "public key")) else {
+            continue;
+        };
+        let Some(private_key) = key_provider.x25519_private_key(rpk.to_vec()) else {
+            continue;
+        };
+        let secret = StaticSecret::from(x25519_key(&private_key, "private key")?);
+        if PublicKey::from(&secret).as_bytes() != &rpk {
+            return Err(SonicError::InvalidKey("private key does not match the recipient public key".into()));
+        }
+        let shared = secret.diffie_hellman(&PublicKey::from(epk));
+        let content_key = key_wrap_cipher(shared.as_bytes(), &epk, &rpk)
+            .decrypt(Nonce::from_slice(&KEY_WRAP_NONCE), decode(&recipient.wrapped_key, "wrapped_key")?.as_ref())
+            .map_err(|_| SonicError::DecryptionFailed("content key failed authentication".into()))?;
+        let plaintext = ChaCha20Poly1305::new_from_slice(&content_key)
+            .map_err(|_| malformed("content key length"))?
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: CONTENT_AAD })
+            .map_err(|_| SonicError::DecryptionFailed("covenant failed authentication".into()))?;
+        return String::from_utf8(plaintext).map_err(|_| malformed("covenant is not UTF-8"));
+    }
+    Err(SonicError::DecryptionFailed("not a recipient of this covenant".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Holds one private key.
+    struct Keychain(Vec<u8>);
+
+    impl CovenantKeyProvider for Keychain {
+        fn x25519_private_key(&self, recipient_public_key: Vec<u8>) -> Option<Vec<u8>> {
+            (x25519_public_key(&self.0).ok()? == recipient_public_key).then(|| self.0.clone())
+        }
+    }
+
+    #[test]
+    fn test_covenant_decrypts_for_recipients_only() {
+        let (alice, bob, eve) = (vec![1u8; 32], vec![2u8; 32], vec![3u8; 32]);
+        let recipients = vec![x25519_public_key(&alice).unwrap(), x25519_public_key(&bob).unwrap()];
+        let sealed = encrypt_covenant(r#"{"train": false, "ai": "deny"}"#, recipients).unwrap();
+        assert!(is_encrypted_covenant(&sealed));
+        assert!(!is_encrypted_covenant(r#"{"ai":"deny"}"#));
+        assert!(!sealed.contains("deny"));
+        // The envelope is a covenant in its own right, so it can be signed.
+        assert!(canonicalize_covenant(&sealed).is_ok());
+
+        for key in [alice, bob] {
+            assert_eq!(decrypt_covenant(&sealed, Box::new(Keychain(key))).unwrap(), r#"{"ai":"deny","train":false}"#);
+        }
+        assert!(matches!(decrypt_covenant(&sealed, Box::new(Keychain(eve))), Err(SonicError::DecryptionFailed(_))));
+
+        let mut envelope: serde_json::Value = serde_json::from_str(&sealed).unwrap();
+        let mut ciphertext = URL_SAFE_NO_PAD.decode(envelope["ciphertext"].as_str().unwrap()).unwrap();
+        ciphertext[0] ^= 1;
+        envelope["ciphertext"] = URL_SAFE_NO_PAD.encode(ciphertext).into();
+        assert!(matches!(
+            decrypt_covenant(&envelope.to_string(), Box::new(Keychain(vec![1u8; 32]))),
+            Err(SonicError::DecryptionFailed(_))
+        ));
+
+        assert!(encrypt_covenant("{}", vec![]).is_err());
+        assert!(matches!(encrypt_covenant("{}", vec![vec![0u8; 32]]), Err(SonicError::InvalidKey(_))));
+    }
+}
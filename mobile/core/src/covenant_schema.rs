@@ -0,0 +1,301 @@
+//! Covenant schema versions and migration.
+//!
+//! Covenants carry a `version`, and each SDK release may add terms. Older
+//! covenants stay in circulation for as long as the audio does, so the core
+//! upgrades them step by step to the current layout before reading them
+//! into a [`Covenant`]. Fields the schema does not know, from a newer SDK or
+//! a custom extension, are carried through untouched rather than dropped.
+//!
+//! Versions:
+//!   - `1.0`: the Python SDK's `VouchCovenant`, either flat or as the
+//!     `vouch.covenant` C2PA assertion (`policies` as `"ALLOW"`/`"DENY"`,
+//!     `requirements`, `restrictions`, `custom`)
+//!   - `1.1`: flat booleans only, adding `jurisdictions`
+
+use serde_json::{Map, Value};
+
+use crate::SonicError;
+
+/// Version [`upgrade_covenant`] produces.
+pub const CURRENT_COVENANT_VERSION: &str = "1.1";
+
+/// Version of a covenant without a `version` field.
+const UNVERSIONED: &str = "1.0";
+
+/// One upgrade step: the version it applies to, and the migration to the
+/// next version in the list (the last one migrates to the current version).
+type Migration = (&'static str, fn(&mut Map<String, Value>));
+
+/// Every known version but the current one, oldest first.
+const MIGRATIONS: &[Migration] = &[("1.0", upgrade_1_0)];
+
+/// Top-level fields of the current schema; everything else is preserved as
+/// an unknown field.
+const KNOWN_FIELDS: &[&str] = &[
+    "version",
+    "ai_training",
+    "ai_inference",
+    "voice_cloning",
+    "derivative_works",
+    "commercial_use",
+    "attribution_required",
+    "context_restrictions",
+    "expiration_date",
+    "jurisdictions",
+    "custom_policies",
+];
+
+/// A covenant in the current schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Covenant {
+    /// Always [`CURRENT_COVENANT_VERSION`] once parsed
+    pub version: String,
+    pub ai_training: bool,
+    pub ai_inference: bool,
+    pub voice_cloning: bool,
+    pub derivative_works: bool,
+    pub commercial_use: bool,
+    pub attribution_required: bool,
+    /// Contexts the content may be used in; empty for any
+    pub context_restrictions: Vec<String>,
+    /// RFC 3339 instant or date after which the permissions lapse
+    pub expiration_date: Option<String>,
+    /// ISO 3166-1 alpha-2 codes the permissions are limited to; empty for
+    /// everywhere
+    pub jurisdictions: Vec<String>,
+    /// The creator's own terms, as a JSON object
+    pub custom_policies_json: String,
+    /// Fields this schema does not know, as a JSON object, written back
+    /// as they were by [`covenant_to_json`]
+    pub unknown_fields_json: String,
+}
+
+/// Versions [`upgrade_covenant`] reads, oldest first.
+pub fn supported_covenant_versions() -> Vec<String> {
+    MIGRATIONS
+        .iter()
+        .map(|(version, _)| *version)
+        .chain([CURRENT_COVENANT_VERSION])
+        .map(String::from)
+        .collect()
+}
+
+fn invalid(reason: String) -> SonicError {
+    SonicError::InvalidConfig(format!("covenant: {reason}"))
+}
+
+/// `1.0` -> `1.1`: unwrap the C2PA assertion form into flat booleans.
+fn upgrade_1_0(covenant: &mut Map<String, Value>) {
+    if covenant.get("label").and_then(Value::as_str) == Some("vouch.covenant") {
+        if let Some(Value::Object(data)) = covenant.remove("data") {
+            covenant.remove("label");
+            covenant.extend(data);
+        }
+    }
+    if let Some(Value::Object(policies)) = covenant.remove("policies") {
+        for (term, value) in policies {
+            let value = match value.as_str() {
+                Some(s) if s.eq_ignore_ascii_case("allow") => Value::Bool(true),
+                Some(s) if s.eq_ignore_ascii_case("deny") => Value::Bool(false),
+                _ => value,
+            };
+            covenant.insert(term, value);
+        }
+    }
+    // Move `section.from` to the top-level `to`, dropping `section` once
+    // nothing else is left in it.
+    let mut hoist = |section: &str, moves: &[(&str, &str)]| {
+        let Some(Value::Object(mut fields)) = covenant.remove(section) else {
+            return;
+        };
+        for (from, to) in moves {
+            if let Some(value) = fields.remove(*from) {
+                covenant.insert((*to).into(), value);
+            }
+        }
+        if !fields.is_empty() {
+            covenant.insert(section.into(), Value::Object(fields));
+        }
+    };
+    hoist("requirements", &[("attribution", "attribution_required")]);
+    hoist(
+        "restrictions",
+        &[
+            ("context", "context_restrictions"),
+            ("expiration", "expiration_date"),
+            ("jurisdictions", "jurisdictions"),
+        ],
+    );
+    if let Some(custom) = covenant.remove("custom") {
+        covenant.insert("custom_policies".into(), custom);
+    }
+}
+
+/// Parse `covenant_json` and migrate it to the current version.
+pub(crate) fn upgrade(covenant_json: &str) -> Result<Map<String, Value>, SonicError> {
+    let value: Value = serde_json::from_str(covenant_json).map_err(|e| invalid(format!("not valid JSON: {e}")))?;
+    let Value::Object(mut covenant) = value else {
+        return Err(invalid("must be a JSON object".into()));
+    };
+    let version = match covenant.get("version") {
+        None | Some(Value::Null) => UNVERSIONED.to_string(),
+        Some(Value::String(version)) => version.clone(),
+        Some(Value::Number(version)) => version.to_string(),
+        Some(other) => return Err(invalid(format!("invalid version {other}"))),
+    };
+    if version != CURRENT_COVENANT_VERSION {
+        let first = MIGRATIONS
+            .iter()
+            .position(|(from, _)| *from == version)
+            .ok_or_else(|| invalid(format!("unsupported version {version}")))?;
+        for (_, migrate) in &MIGRATIONS[first..] {
+            migrate(&mut covenant);
+        }
+    }
+    covenant.insert("version".into(), CURRENT_COVENANT_VERSION.into());
+    Ok(covenant)
+}
+
+/// `covenant_json`, of any supported version, as current-version JSON.
+/// Unknown fields are kept.
+pub fn upgrade_covenant(covenant_json: &str) -> Result<String, SonicError> {
+    Ok(Value::Object(upgrade(covenant_json)?).to_string())
+}
+
+/// Read `covenant_json`, of any supported version, into the current typed
+/// representation. Terms it leaves out take the Python SDK's defaults.
+pub fn parse_covenant(covenant_json: &str) -> Result<Covenant, SonicError> {
+    let mut covenant = upgrade(covenant_json)?;
+    let mut flag = |name: &str, default: bool| match covenant.remove(name) {
+        None | Some(Value::Null) => Ok(default),
+        Some(Value::Bool(value)) => Ok(value),
+        Some(other) => Err(invalid(format!("{name} must be a boolean, got {other}"))),
+    };
+    let ai_training = flag("ai_training", true)?;
+    let ai_inference = flag("ai_inference", true)?;
+    let voice_cloning = flag("voice_cloning", false)?;
+    let derivative_works = flag("derivative_works", true)?;
+    let commercial_use = flag("commercial_use", true)?;
+    let attribution_required = flag("attribution_required", true)?;
+    let mut strings = |name: &str| match covenant.remove(name) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s),
+                other => Err(invalid(format!("{name} must hold strings, got {other}"))),
+            })
+            .collect(),
+        Some(other) => Err(invalid(format!("{name} must be a list, got {other}"))),
+    };
+    let context_restrictions = strings("context_restrictions")?;
+    let jurisdictions = strings("jurisdictions")?;
+    let expiration_date = match covenant.remove("expiration_date") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(date)) => Some(date),
+        Some(other) => return Err(invalid(format!("expiration_date must be a string, got {other}"))),
+    };
+    let custom_policies = match covenant.remove("custom_policies") {
+        None | Some(Value::Null) => Value::Object(Map::new()),
+        Some(custom @ Value::Object(_)) => custom,
+        Some(other) => return Err(invalid(format!("custom_policies must be an object, got {other}"))),
+    };
+    covenant.remove("version");
+    Ok(Covenant {
+        version: CURRENT_COVENANT_VERSION.into(),
+        ai_training,
+        ai_inference,
+        voice_cloning,
+        derivative_works,
+        commercial_use,
+        attribution_required,
+        context_restrictions,
+        expiration_date,
+        jurisdictions,
+        custom_policies_json: custom_policies.to_string(),
+        unknown_fields_json: Value::Object(covenant).to_string(),
+    })
+}
+
+/// `covenant` as current-version JSON, with its unknown fields restored.
+pub fn covenant_to_json(covenant: Covenant) -> Result<String, SonicError> {
+    let object = |json: &str, name: &str| match serde_json::from_str(json) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        _ => Err(invalid(format!("{name} must be a JSON object"))),
+    };
+    let mut fields = object(&covenant.unknown_fields_json, "unknown_fields_json")?;
+    if let Some(clash) = fields.keys().find(|key| KNOWN_FIELDS.contains(&key.as_str())) {
+        return Err(invalid(format!("unknown_fields_json redefines {clash}")));
+    }
+    let known = serde_json::json!({
+        "version": CURRENT_COVENANT_VERSION,
+        "ai_training": covenant.ai_training,
+        "ai_inference": covenant.ai_inference,
+        "voice_cloning": covenant.voice_cloning,
+        "derivative_works": covenant.derivative_works,
+        "commercial_use": covenant.commercial_use,
+        "attribution_required": covenant.attribution_required,
+        "context_restrictions": covenant.context_restrictions,
+        "expiration_date": covenant.expiration_date,
+        "jurisdictions": covenant.jurisdictions,
+        "custom_policies": object(&covenant.custom_policies_json, "custom_policies_json")?,
+    });
+    if let Value::Object(known) = known {
+        fields.extend(known);
+    }
+    Ok(Value::Object(fields).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upgrades_assertion_form() {
+        let assertion = r#"{
+            "label": "vouch.covenant",
+            "data": {
+                "@context": "https://vouch-protocol.com/covenants/v1",
+                "version": "1.0",
+                "policies": {"ai_training": "DENY", "voice_cloning": "ALLOW", "remixing": "DENY"},
+                "requirements": {"attribution": false, "royalty": 0.05},
+                "restrictions": {"context": ["news"], "expiration": "2030-01-01"},
+                "custom": {"region_lock": true}
+            }
+        }"#;
+        let covenant = parse_covenant(assertion).unwrap();
+        assert_eq!(covenant.version, CURRENT_COVENANT_VERSION);
+        assert!(!covenant.ai_training && covenant.voice_cloning && covenant.ai_inference);
+        assert!(!covenant.attribution_required);
+        assert_eq!(covenant.context_restrictions, ["news"]);
+        assert_eq!(covenant.expiration_date.as_deref(), Some("2030-01-01"));
+        assert_eq!(covenant.custom_policies_json, r#"{"region_lock":true}"#);
+        let unknown: Value = serde_json::from_str(&covenant.unknown_fields_json).unwrap();
+        assert_eq!(unknown["@context"], "https://vouch-protocol.com/covenants/v1");
+        assert_eq!(unknown["remixing"], false);
+        assert_eq!(unknown["requirements"]["royalty"], 0.05);
+
+        // Writing it back keeps the unknown fields, and reads back the same.
+        let json = covenant_to_json(covenant.clone()).unwrap();
+        assert_eq!(parse_covenant(&json).unwrap(), covenant);
+        assert_eq!(parse_covenant(&upgrade_covenant(assertion).unwrap()).unwrap(), covenant);
+    }
+
+    #[test]
+    fn test_versions() {
+        assert_eq!(supported_covenant_versions(), ["1.0", "1.1"]);
+        let flat = parse_covenant(r#"{"ai_training": false}"#).unwrap();
+        assert!(!flat.ai_training && !flat.voice_cloning && flat.attribution_required);
+        assert_eq!(flat.unknown_fields_json, "{}");
+        // The current version is read as is: no ALLOW/DENY strings.
+        assert!(parse_covenant(r#"{"version": "1.1", "ai_training": "DENY"}"#).is_err());
+        assert!(matches!(parse_covenant(r#"{"version": "9.0"}"#), Err(SonicError::InvalidConfig(_))));
+        assert!(parse_covenant(r#"{"version": "1.1", "jurisdictions": "DE"}"#).is_err());
+
+        let clash = Covenant {
+            unknown_fields_json: r#"{"ai_training": true}"#.into(),
+            ..flat
+        };
+        assert!(covenant_to_json(clash).is_err());
+    }
+}
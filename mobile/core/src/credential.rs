@@ -0,0 +1,93 @@
+//! Detections as W3C Verifiable Credentials.
+//!
+//! Services that consume provenance claims through standard VC tooling get
+//! a detection as a credential (VC Data Model 2.0) in which the verifying
+//! device, identified by its DID, attests that it heard a watermark signed
+//! by a given signer. The credential is returned without a proof: the host
+//! app secures it with the verifier's key through its VC library (a Data
+//! Integrity proof or a JOSE envelope), which the core does not hold.
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::c2pa::{rfc3339, verified_payload_hash};
+use crate::{SonicError, VerificationResult, WatermarkResult, C2PA_SOFT_BINDING_ALG};
+
+/// JSON-LD context of the `WatermarkDetection` credential subject.
+pub const VOUCH_CREDENTIALS_CONTEXT: &str = "https://vouch-protocol.com/credentials/v1";
+
+/// An unsecured Verifiable Credential, issued by `verifier_did`, stating
+/// that the watermark in `result` was detected now and verified as
+/// `verification` reports. Only verified detections are exported.
+pub fn to_verifiable_credential(
+    result: WatermarkResult,
+    verification: VerificationResult,
+    verifier_did: String,
+) -> Result<String, SonicError> {
+    credential(result, verification, verifier_did, Utc::now())
+}
+
+fn credential(
+    result: WatermarkResult,
+    verification: VerificationResult,
+    verifier_did: String,
+    now: DateTime<Utc>,
+) -> Result<String, SonicError> {
+    if !verifier_did.starts_with("did:") {
+        return Err(SonicError::InvalidKey(format!("{verifier_did}: not a DID")));
+    }
+    let payload_hash = verified_payload_hash(&result, &verification)?;
+    let credential = json!({
+        "@context": ["https://www.w3.org/ns/credentials/v2", VOUCH_CREDENTIALS_CONTEXT],
+        "type": ["VerifiableCredential", "WatermarkDetectionCredential"],
+        "issuer": verifier_did,
+        "validFrom": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "credentialSubject": {
+            "type": "WatermarkDetection",
+            "payloadHash": payload_hash,
+            "softBindingAlgorithm": C2PA_SOFT_BINDING_ALG,
+            "signer": verification.signer_did,
+            "keyId": verification.key_id,
+            "signatureAlgorithm": format!("{:?}", verification.algorithm),
+            "signedAt": result.timestamp.and_then(rfc3339),
+            "confidence": result.confidence,
+            "detectionMethod": result.detection_method,
+        },
+    });
+    Ok(credential.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_credential_describes_detection() {
+        let result = WatermarkResult {
+            detected: true,
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            ..Default::default()
+        };
+        let verification = VerificationResult {
+            valid: true,
+            signer_did: Some("did:web:news.example".into()),
+            ..Default::default()
+        };
+        let now = DateTime::from_timestamp(1_800_000_000, 0).unwrap();
+        let vc = credential(result.clone(), verification.clone(), "did:key:z6Mkverifier".into(), now).unwrap();
+        let vc: Value = serde_json::from_str(&vc).unwrap();
+        assert_eq!(vc["type"][0], "VerifiableCredential");
+        assert_eq!(vc["issuer"], "did:key:z6Mkverifier");
+        assert_eq!(vc["validFrom"], "2027-01-15T08:00:00Z");
+        assert_eq!(vc["credentialSubject"]["payloadHash"], "a1b2c3d4");
+        assert_eq!(vc["credentialSubject"]["signer"], "did:web:news.example");
+        assert_eq!(vc["credentialSubject"]["signedAt"], "2023-11-14T22:13:20Z");
+        assert!(vc.get("proof").is_none());
+
+        assert!(credential(result.clone(), verification.clone(), "verifier".into(), now).is_err());
+        let unverified = VerificationResult::default();
+        assert!(credential(result, unverified, "did:key:z6Mkverifier".into(), now).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Repeated detections of the same watermark.
+//!
+//! A watermarked stream that keeps playing decodes again in every window,
+//! and each decode would reach the host as a new detection. With
+//! `duplicate_window_secs` set, detections of a payload hash already
+//! reported collapse into that first report for as long as they keep coming
+//! less than the window apart; `still_present_interval_secs` adds a periodic
+//! "still present" update while they do.
+
+use std::time::{Duration, Instant};
+
+/// How a detection is reported to the callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Report {
+    /// The first detection of a run: `on_watermark_detected`
+    New,
+    /// A repeat that is due a "still present" update, with how long the
+    /// watermark has been detected
+    StillPresent(Duration),
+    /// A repeat that is not reported
+    Suppressed,
+}
+
+/// A run of detections of one payload.
+struct Run {
+    payload_hash: String,
+    first_seen: Instant,
+    last_seen: Instant,
+    last_reported: Instant,
+}
+
+/// The runs of detections still within their duplicate window.
+#[derive(Default)]
+pub(crate) struct Deduplicator {
+    runs: Vec<Run>,
+}
+
+impl Deduplicator {
+    /// Record a detection of `payload_hash` at `now`, and decide how to
+    /// report it: repeats less than `window` after the previous detection of
+    /// the payload are suppressed, except once every `interval` (when not
+    /// zero) since the run was last reported.
+    pub(crate) fn observe(&mut self, payload_hash: &str, now: Instant, window: Duration, interval: Duration) -> Report {
+        self.runs.retain(|run| now.duration_since(run.last_seen) <= window);
+        let Some(run) = self.runs.iter_mut().find(|run| run.payload_hash == payload_hash) else {
+            self.runs.push(Run {
+                payload_hash: payload_hash.to_owned(),
+                first_seen: now,
+                last_seen: now,
+                last_reported: now,
+            });
+            return Report::New;
+        };
+        run.last_seen = now;
+        if !interval.is_zero() && now.duration_since(run.last_reported) >= interval {
+            run.last_reported = now;
+            Report::StillPresent(now.duration_since(run.first_seen))
+        } else {
+            Report::Suppressed
+        }
+    }
+
+    /// Forget every run, so the next detection of any payload is new.
+    pub(crate) fn clear(&mut self) {
+        self.runs.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_collapse_until_the_window_lapses() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let window = Duration::from_secs(10);
+        let interval = Duration::from_secs(30);
+        let mut dedup = Deduplicator::default();
+
+        assert_eq!(dedup.observe("a1b2", at(0), window, interval), Report::New);
+        assert_eq!(dedup.observe("c3d4", at(5), window, interval), Report::New);
+        // Each repeat extends the run, so a steady stream stays one report.
+        for secs in (10..30).step_by(5) {
+            assert_eq!(dedup.observe("a1b2", at(secs), window, interval), Report::Suppressed);
+        }
+        assert_eq!(
+            dedup.observe("a1b2", at(30), window, interval),
+            Report::StillPresent(Duration::from_secs(30))
+        );
+        assert_eq!(dedup.observe("a1b2", at(35), window, interval), Report::Suppressed);
+
+        // A gap longer than the window starts a new run.
+        assert_eq!(dedup.observe("c3d4", at(35), window, interval), Report::New);
+        assert_eq!(dedup.observe("a1b2", at(50), window, Duration::ZERO), Report::New);
+        assert_eq!(dedup.observe("a1b2", at(95), window, Duration::ZERO), Report::New);
+        assert_eq!(dedup.observe("a1b2", at(100), window, Duration::ZERO), Report::Suppressed);
+
+        dedup.clear();
+        assert_eq!(dedup.observe("a1b2", at(101), window, interval), Report::New);
+    }
+}
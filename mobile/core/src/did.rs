@@ -0,0 +1,450 @@
+//! Mapping between signer public keys and DIDs.
+//!
+//! `SignatureVerifier` reports the signer as a DID. Deployments differ in the
+//! DID method they use for signers (`did:key`, `did:web`, `did:vouch`, ...), so
+//! the mapping is pluggable through [`DidResolver`]. The default,
+//! [`DidKeyResolver`], emits `did:key` identifiers.
+//!
+//! A `did:key` carries its key and resolves locally. Methods such as
+//! `did:web` and `did:ion` name a DID document that has to be fetched; the
+//! core has no network stack, so a host app implements [`DidResolver`] over
+//! its own (the trait is foreign-implementable over UniFFI), using
+//! [`did_web_url`] to locate a `did:web` document and
+//! [`public_key_from_did_document`] to read the signer key out of it.
+//!
+//! A signer that rotates keys lists several in its document. Payloads signed
+//! before a rotation are verified against all of them, as [`SignerKey`]s
+//! from [`signer_keys_from_did_document`], optionally bounded by the
+//! validity windows the host knows for each.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+
+use crate::{signature::compress_public_key, SignatureAlgorithm, SonicError};
+
+/// Two-way mapping between an Ed25519 public key and the signer's DID.
+///
+/// Host implementations are called synchronously on the verifying thread,
+/// so a resolver may block on a network fetch as long as verification is
+/// kept off the UI thread.
+pub trait DidResolver: Send + Sync {
+    /// DID to report for a verified signer key.
+    fn public_key_to_did(&self, public_key: Vec<u8>) -> String;
+
+    /// Inverse of [`public_key_to_did`](Self::public_key_to_did): the raw
+    /// public key for `did`, or `None` if this resolver cannot map it.
+    fn did_to_public_key(&self, did: String) -> Option<Vec<u8>>;
+}
+
+/// Prefix of every `did:key` identifier, before the multibase-encoded key.
+const DID_KEY_PREFIX: &str = "did:key:";
+
+/// Multibase code for base58btc, the encoding `did:key` uses.
+const MULTIBASE_BASE58BTC: char = 'z';
+
+/// Multicodec prefix of an Ed25519 public key: `0xed` as an unsigned varint.
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Multicodec prefixes of compressed secp256k1 (`0xe7`) and P-256
+/// (`0x1200`) public keys.
+const SECP256K1_PUB_MULTICODEC: [u8; 2] = [0xe7, 0x01];
+const P256_PUB_MULTICODEC: [u8; 2] = [0x80, 0x24];
+
+/// Length of a raw Ed25519 public key.
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+/// Length of a compressed SEC1 point.
+const COMPRESSED_POINT_LEN: usize = 33;
+
+fn multicodec(algorithm: SignatureAlgorithm) -> [u8; 2] {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => ED25519_PUB_MULTICODEC,
+        SignatureAlgorithm::Es256k => SECP256K1_PUB_MULTICODEC,
+        SignatureAlgorithm::Es256 => P256_PUB_MULTICODEC,
+    }
+}
+
+/// The `did:key` identifier of an Ed25519 public key: the key behind its
+/// multicodec prefix, base58btc-encoded with the multibase `z` prefix, so
+/// every such DID starts with `did:key:z6Mk`.
+pub fn did_from_public_key(public_key: &[u8]) -> Result<String, SonicError> {
+    did_key_for(SignatureAlgorithm::Ed25519, public_key)
+}
+
+/// [`did_from_public_key`] for a key of `algorithm`. ECDSA keys are
+/// encoded compressed, so both SEC1 forms of a key give the same DID.
+pub(crate) fn did_key_for(algorithm: SignatureAlgorithm, public_key: &[u8]) -> Result<String, SonicError> {
+    if algorithm == SignatureAlgorithm::Ed25519 && public_key.len() != ED25519_PUBLIC_KEY_LEN {
+        return Err(SonicError::InvalidKey(format!(
+            "Ed25519 public key must be {ED25519_PUBLIC_KEY_LEN} bytes, got {}",
+            public_key.len()
+        )));
+    }
+    let key = compress_public_key(algorithm, public_key)
+        .ok_or_else(|| SonicError::InvalidKey(format!("not a {algorithm:?} public key")))?;
+    let mut multicodec = multicodec(algorithm).to_vec();
+    multicodec.extend_from_slice(&key);
+    Ok(format!(
+        "{DID_KEY_PREFIX}{MULTIBASE_BASE58BTC}{}",
+        bs58::encode(multicodec).into_string()
+    ))
+}
+
+/// Inverse of [`did_from_public_key`]: the raw Ed25519 public key in a
+/// `did:key` identifier. Other DID methods, other multibase encodings and
+/// other key types are rejected.
+pub fn public_key_from_did(did: &str) -> Result<Vec<u8>, SonicError> {
+    match decode_did_key(did)? {
+        (SignatureAlgorithm::Ed25519, key) => Ok(key),
+        _ => Err(SonicError::InvalidKey(format!("{did}: not an Ed25519 public key"))),
+    }
+}
+
+/// The key type and public key (compressed, for ECDSA) in a `did:key`
+/// identifier of any key type [`SignatureAlgorithm`] covers.
+pub(crate) fn decode_did_key(did: &str) -> Result<(SignatureAlgorithm, Vec<u8>), SonicError> {
+    let invalid = |reason: &str| SonicError::InvalidKey(format!("{did}: {reason}"));
+    let encoded = did
+        .strip_prefix(DID_KEY_PREFIX)
+        .ok_or_else(|| invalid("not a did:key"))?
+        .strip_prefix(MULTIBASE_BASE58BTC)
+        .ok_or_else(|| invalid("not base58btc multibase"))?;
+    let bytes = bs58::decode(encoded).into_vec().map_err(|_| invalid("invalid base58"))?;
+    decode_multikey(&bytes).ok_or_else(|| invalid("unsupported key type or length"))
+}
+
+/// A multicodec-prefixed public key, as `did:key` and `Multikey`
+/// verification methods carry it.
+fn decode_multikey(bytes: &[u8]) -> Option<(SignatureAlgorithm, Vec<u8>)> {
+    let (algorithm, len) = match bytes.get(..2)? {
+        p if p == ED25519_PUB_MULTICODEC => (SignatureAlgorithm::Ed25519, ED25519_PUBLIC_KEY_LEN),
+        p if p == SECP256K1_PUB_MULTICODEC => (SignatureAlgorithm::Es256k, COMPRESSED_POINT_LEN),
+        p if p == P256_PUB_MULTICODEC => (SignatureAlgorithm::Es256, COMPRESSED_POINT_LEN),
+        _ => return None,
+    };
+    let key = &bytes[2..];
+    (key.len() == len).then(|| (algorithm, key.to_vec()))
+}
+
+/// The verification method ID of a `did:key`: the DID with its own
+/// multibase key as the fragment.
+pub(crate) fn did_key_id(did: &str) -> Option<String> {
+    let fragment = did.strip_prefix(DID_KEY_PREFIX)?;
+    Some(format!("{did}#{fragment}"))
+}
+
+/// Prefix of every `did:web` identifier.
+const DID_WEB_PREFIX: &str = "did:web:";
+
+/// The HTTPS URL of a `did:web` identifier's DID document, per the did:web
+/// method: the domain (with a `%3A`-encoded port) and any further
+/// `:`-separated path segments, ending in `/did.json`, or
+/// `/.well-known/did.json` for a bare domain.
+pub fn did_web_url(did: &str) -> Result<String, SonicError> {
+    let invalid = |reason: &str| SonicError::InvalidKey(format!("{did}: {reason}"));
+    let id = did
+        .strip_prefix(DID_WEB_PREFIX)
+        .ok_or_else(|| invalid("not a did:web"))?
+        .split('#')
+        .next()
+        .unwrap_or_default();
+    let mut segments = id.split(':');
+    let domain = segments.next().unwrap_or_default().replace("%3A", ":").replace("%3a", ":");
+    if domain.is_empty() || domain.contains('/') {
+        return Err(invalid("invalid domain"));
+    }
+    let path: Vec<&str> = segments.collect();
+    if path.iter().any(|segment| segment.is_empty()) {
+        return Err(invalid("empty path segment"));
+    }
+    Ok(if path.is_empty() {
+        format!("https://{domain}/.well-known/did.json")
+    } else {
+        format!("https://{domain}/{}/did.json", path.join("/"))
+    })
+}
+
+/// A DID document, as far as signer keys go.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DidDocument {
+    id: String,
+    #[serde(default)]
+    verification_method: Vec<VerificationMethod>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMethod {
+    id: String,
+    public_key_multibase: Option<String>,
+    public_key_base58: Option<String>,
+    public_key_jwk: Option<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+    y: Option<String>,
+}
+
+impl VerificationMethod {
+    /// The method's key and its type, in whichever encoding it is
+    /// published: `publicKeyMultibase` (Multikey,
+    /// Ed25519VerificationKey2020), `publicKeyBase58`
+    /// (Ed25519VerificationKey2018) or `publicKeyJwk` (JsonWebKey2020: OKP
+    /// Ed25519, EC secp256k1 or P-256). `None` for other key types.
+    fn signer_key(&self) -> Option<(SignatureAlgorithm, Vec<u8>)> {
+        if let Some(multibase) = &self.public_key_multibase {
+            let multicodec = bs58::decode(multibase.strip_prefix(MULTIBASE_BASE58BTC)?).into_vec().ok()?;
+            return decode_multikey(&multicodec);
+        }
+        if let Some(base58) = &self.public_key_base58 {
+            let key = bs58::decode(base58).into_vec().ok()?;
+            return (key.len() == ED25519_PUBLIC_KEY_LEN).then_some((SignatureAlgorithm::Ed25519, key));
+        }
+        let jwk = self.public_key_jwk.as_ref()?;
+        let algorithm = match (jwk.kty.as_str(), jwk.crv.as_str()) {
+            ("OKP", "Ed25519") => SignatureAlgorithm::Ed25519,
+            ("EC", "secp256k1") => SignatureAlgorithm::Es256k,
+            ("EC", "P-256") => SignatureAlgorithm::Es256,
+            _ => return None,
+        };
+        let x = URL_SAFE_NO_PAD.decode(&jwk.x).ok()?;
+        if algorithm == SignatureAlgorithm::Ed25519 {
+            return (x.len() == ED25519_PUBLIC_KEY_LEN).then_some((algorithm, x));
+        }
+        // An uncompressed SEC1 point, `0x04 || x || y`
+        let mut point = vec![0x04];
+        point.extend(x);
+        point.extend(URL_SAFE_NO_PAD.decode(jwk.y.as_ref()?).ok()?);
+        Some((algorithm, compress_public_key(algorithm, &point)?))
+    }
+
+    /// The method's key if it is Ed25519.
+    fn ed25519_key(&self) -> Option<Vec<u8>> {
+        match self.signer_key()? {
+            (SignatureAlgorithm::Ed25519, key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+/// One of a signer's keys, as a candidate for
+/// `SignatureVerifier::verify_watermark_payload_with_keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerKey {
+    /// Verification method ID reported when this key verifies, e.g.
+    /// `did:web:example.com#key-2`
+    pub key_id: String,
+    /// Raw Ed25519 key or SEC1 point
+    pub public_key: Vec<u8>,
+    pub algorithm: SignatureAlgorithm,
+    /// Unix seconds from which the key may sign; `None` for no lower bound
+    pub valid_from: Option<u64>,
+    /// Unix seconds after which the key no longer signs (it was rotated
+    /// out); `None` for no upper bound
+    pub valid_until: Option<u64>,
+}
+
+impl SignerKey {
+    /// Whether a payload signed at `timestamp` may have been signed with
+    /// this key. A payload without a timestamp only matches keys without a
+    /// validity window.
+    pub(crate) fn valid_at(&self, timestamp: Option<u64>) -> bool {
+        match timestamp {
+            Some(t) => self.valid_from.is_none_or(|from| t >= from) && self.valid_until.is_none_or(|until| t <= until),
+            None => self.valid_from.is_none() && self.valid_until.is_none(),
+        }
+    }
+}
+
+/// Parse `document_json` as `did`'s own DID document.
+fn parse_did_document(did: &str, document_json: &str) -> Result<DidDocument, SonicError> {
+    let invalid = |reason: String| SonicError::InvalidKey(format!("{did}: {reason}"));
+    let document: DidDocument =
+        serde_json::from_str(document_json).map_err(|e| invalid(format!("invalid DID document: {e}")))?;
+    let subject = did.split('#').next().unwrap_or_default();
+    if document.id != subject {
+        return Err(invalid(format!("document is for {}", document.id)));
+    }
+    Ok(document)
+}
+
+/// The Ed25519 signer key for `did` in its fetched DID document
+/// `document_json`. The document must be `did`'s own; a DID URL with a
+/// fragment (`did:web:example.com#key-1`) selects that verification
+/// method, otherwise the first Ed25519 method is used.
+pub fn public_key_from_did_document(did: &str, document_json: &str) -> Result<Vec<u8>, SonicError> {
+    let document = parse_did_document(did, document_json)?;
+    let fragment = did.split_once('#').map(|(_, fragment)| fragment);
+    document
+        .verification_method
+        .iter()
+        .filter(|method| {
+            fragment.is_none_or(|fragment| method.id.rsplit_once('#').is_some_and(|(_, f)| f == fragment))
+        })
+        .find_map(VerificationMethod::ed25519_key)
+        .ok_or_else(|| SonicError::InvalidKey(format!("{did}: no Ed25519 verification method")))
+}
+
+/// Every signer key in `did`'s DID document `document_json`, of any
+/// supported type, in document order: the candidates to verify a payload
+/// against when the signer has rotated keys. Relative method IDs
+/// (`#key-1`) are made absolute. DID documents carry no validity windows,
+/// so the keys have none; a host that tracks key history sets them.
+pub fn signer_keys_from_did_document(did: &str, document_json: &str) -> Result<Vec<SignerKey>, SonicError> {
+    let document = parse_did_document(did, document_json)?;
+    Ok(document
+        .verification_method
+        .iter()
+        .filter_map(|method| {
+            let (algorithm, public_key) = method.signer_key()?;
+            let key_id = if method.id.starts_with('#') {
+                format!("{}{}", document.id, method.id)
+            } else {
+                method.id.clone()
+            };
+            Some(SignerKey {
+                key_id,
+                public_key,
+                algorithm,
+                valid_from: None,
+                valid_until: None,
+            })
+        })
+        .collect())
+}
+
+/// Default resolver: `did:key` identifiers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DidKeyResolver;
+
+impl DidResolver for DidKeyResolver {
+    fn public_key_to_did(&self, public_key: Vec<u8>) -> String {
+        // `verify_signature` only hands over keys that parsed as Ed25519.
+        did_from_public_key(&public_key).unwrap_or_default()
+    }
+
+    fn did_to_public_key(&self, did: String) -> Option<Vec<u8>> {
+        public_key_from_did(&did).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_did_key_round_trip() {
+        let key = [7u8; 32];
+        let did = DidKeyResolver.public_key_to_did(key.to_vec());
+        assert!(did.starts_with("did:key:z6Mk"));
+        assert_eq!(DidKeyResolver.did_to_public_key(did), Some(key.to_vec()));
+        assert_eq!(DidKeyResolver.did_to_public_key("did:web:example.com".into()), None);
+    }
+
+    #[test]
+    fn test_did_key_matches_spec_vector() {
+        // did:key spec, Ed25519 test vector.
+        let did = "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp";
+        let key = public_key_from_did(did).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(did_from_public_key(&key).unwrap(), did);
+
+        assert!(matches!(did_from_public_key(&[0u8; 31]), Err(SonicError::InvalidKey(_))));
+        // The old encoding: raw key bytes behind a literal "z6Mk".
+        let legacy = format!("did:key:z6Mk{}", bs58::encode([7u8; 32]).into_string());
+        assert!(public_key_from_did(&legacy).is_err());
+        assert!(public_key_from_did("did:key:f6Mk00").is_err());
+        assert!(public_key_from_did("did:web:example.com").is_err());
+    }
+
+    #[test]
+    fn test_did_key_ecdsa_multicodecs() {
+        // did:key spec test vectors for secp256k1 and P-256.
+        for (did, algorithm) in [
+            ("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme", SignatureAlgorithm::Es256k),
+            ("did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169", SignatureAlgorithm::Es256),
+        ] {
+            let (decoded, key) = decode_did_key(did).unwrap();
+            assert_eq!((decoded, key.len()), (algorithm, 33));
+            assert_eq!(did_key_for(algorithm, &key).unwrap(), did);
+            assert!(public_key_from_did(did).is_err(), "not Ed25519");
+        }
+        assert!(did_key_for(SignatureAlgorithm::Es256k, &[7u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_did_web_url() {
+        assert_eq!(did_web_url("did:web:example.com").unwrap(), "https://example.com/.well-known/did.json");
+        assert_eq!(
+            did_web_url("did:web:example.com%3A8443:user:alice").unwrap(),
+            "https://example.com:8443/user/alice/did.json"
+        );
+        assert!(did_web_url("did:key:z6Mk").is_err());
+        assert!(did_web_url("did:web:example.com::alice").is_err());
+    }
+
+    #[test]
+    fn test_public_key_from_did_document() {
+        let key = [5u8; 32];
+        let multibase = did_from_public_key(&key).unwrap().replace(DID_KEY_PREFIX, "");
+        let jwk = URL_SAFE_NO_PAD.encode([6u8; 32]);
+        let document = format!(
+            r#"{{
+                "id": "did:web:example.com",
+                "verificationMethod": [
+                    {{"id": "did:web:example.com#p256", "type": "JsonWebKey2020",
+                      "publicKeyJwk": {{"kty": "EC", "crv": "P-256", "x": "AA", "y": "AA"}}}},
+                    {{"id": "did:web:example.com#key-1", "type": "Multikey", "publicKeyMultibase": "{multibase}"}},
+                    {{"id": "did:web:example.com#key-2", "type": "JsonWebKey2020",
+                      "publicKeyJwk": {{"kty": "OKP", "crv": "Ed25519", "x": "{jwk}"}}}}
+                ]
+            }}"#
+        );
+        let from = |did| public_key_from_did_document(did, &document);
+        assert_eq!(from("did:web:example.com").unwrap(), key.to_vec());
+        assert_eq!(from("did:web:example.com#key-2").unwrap(), vec![6u8; 32]);
+        assert!(from("did:web:example.com#p256").is_err());
+        assert!(matches!(from("did:web:attacker.example"), Err(SonicError::InvalidKey(_))));
+        assert!(public_key_from_did_document("did:web:example.com", "not json").is_err());
+    }
+
+    #[test]
+    fn test_signer_keys_from_did_document() {
+        let p256 = p256::ecdsa::SigningKey::from_slice(&[4u8; 32]).unwrap();
+        let point = p256.verifying_key().to_encoded_point(false);
+        let (x, y) = (URL_SAFE_NO_PAD.encode(point.x().unwrap()), URL_SAFE_NO_PAD.encode(point.y().unwrap()));
+        let old = did_from_public_key(&[1u8; 32]).unwrap().replace(DID_KEY_PREFIX, "");
+        let document = format!(
+            r##"{{
+                "id": "did:web:example.com",
+                "verificationMethod": [
+                    {{"id": "#key-1", "type": "Multikey", "publicKeyMultibase": "{old}"}},
+                    {{"id": "did:web:example.com#key-2", "type": "JsonWebKey2020",
+                      "publicKeyJwk": {{"kty": "EC", "crv": "P-256", "x": "{x}", "y": "{y}"}}}},
+                    {{"id": "#rsa", "type": "JsonWebKey2020", "publicKeyJwk": {{"kty": "RSA", "crv": "", "x": ""}}}}
+                ]
+            }}"##
+        );
+        let keys = signer_keys_from_did_document("did:web:example.com", &document).unwrap();
+        let ids: Vec<&str> = keys.iter().map(|k| k.key_id.as_str()).collect();
+        assert_eq!(ids, ["did:web:example.com#key-1", "did:web:example.com#key-2"]);
+        assert_eq!((keys[0].algorithm, keys[0].public_key.clone()), (SignatureAlgorithm::Ed25519, vec![1u8; 32]));
+        assert_eq!(keys[1].algorithm, SignatureAlgorithm::Es256);
+        assert_eq!(keys[1].public_key, p256.verifying_key().to_encoded_point(true).as_bytes());
+        assert!(signer_keys_from_did_document("did:web:attacker.example", &document).is_err());
+
+        let rotated = SignerKey {
+            valid_from: Some(100),
+            valid_until: Some(200),
+            ..keys[0].clone()
+        };
+        assert!(rotated.valid_at(Some(100)) && rotated.valid_at(Some(200)));
+        assert!(!rotated.valid_at(Some(201)) && !rotated.valid_at(Some(99)) && !rotated.valid_at(None));
+        assert!(keys[0].valid_at(None));
+    }
+}
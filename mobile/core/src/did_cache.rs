@@ -0,0 +1,239 @@
+//! Offline cache of resolved signer keys.
+//!
+//! Resolving a `did:web` or `did:ion` signer means fetching its DID document,
+//! which a phone cannot do offline and should not repeat for every detection
+//! of the same signer. [`CachingDidResolver`] wraps the host's resolver and
+//! remembers each key it resolves for a fixed time, in an LRU of bounded
+//! size, handing the cache to a [`DidCacheStorage`] whenever it changes so
+//! it survives app restarts.
+
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::DidResolver;
+
+/// Where a [`CachingDidResolver`] persists its entries (a file, the
+/// keychain, `SharedPreferences`, ...). Implemented by the host app.
+pub trait DidCacheStorage: Send + Sync {
+    /// The snapshot last passed to [`save`](Self::save), if any.
+    fn load(&self) -> Option<String>;
+
+    /// Persist `snapshot` (JSON), replacing the previous one.
+    fn save(&self, snapshot: String);
+}
+
+/// One resolved signer key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    did: String,
+    /// base58btc
+    public_key: String,
+    /// Unix seconds
+    resolved_at: u64,
+}
+
+/// Resolved keys, least recently used first.
+#[derive(Default)]
+struct KeyCache {
+    entries: VecDeque<Entry>,
+}
+
+impl KeyCache {
+    /// The key cached for `did`, which becomes the most recently used,
+    /// unless it is more than `ttl_secs` old at `now`.
+    fn get(&mut self, did: &str, now: u64, ttl_secs: u64) -> Option<Vec<u8>> {
+        let at = self.entries.iter().position(|e| e.did == did)?;
+        let entry = self.entries.remove(at)?;
+        if now.saturating_sub(entry.resolved_at) > ttl_secs {
+            return None;
+        }
+        let key = bs58::decode(&entry.public_key).into_vec().ok();
+        self.entries.push_back(entry);
+        key
+    }
+
+    /// Cache `public_key` for `did` as resolved at `now`, evicting the
+    /// least recently used entries beyond `capacity`.
+    fn insert(&mut self, did: String, public_key: &[u8], now: u64, capacity: usize) {
+        self.entries.retain(|e| e.did != did);
+        self.entries.push_back(Entry {
+            did,
+            public_key: bs58::encode(public_key).into_string(),
+            resolved_at: now,
+        });
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// A [`DidResolver`] that answers repeated lookups of a signer from a
+/// persistent cache instead of its inner resolver.
+///
+/// A key is served from the cache for `ttl_secs` after it was resolved;
+/// after that the inner resolver is asked again, so choose the TTL as the
+/// longest a rotated-out key may still verify. Only successful lookups are
+/// cached, so a lookup that fails while offline is retried next time.
+pub struct CachingDidResolver {
+    inner: Arc<dyn DidResolver>,
+    storage: Option<Box<dyn DidCacheStorage>>,
+    ttl_secs: u64,
+    capacity: usize,
+    cache: Mutex<KeyCache>,
+}
+
+impl CachingDidResolver {
+    /// Cache up to `capacity` keys resolved by `inner` for `ttl_secs` each,
+    /// starting from the snapshot in `storage`. A snapshot that cannot be
+    /// read starts the cache empty.
+    pub fn new(
+        inner: Arc<dyn DidResolver>,
+        storage: Option<Box<dyn DidCacheStorage>>,
+        ttl_secs: u64,
+        capacity: u32,
+    ) -> Self {
+        let capacity = capacity as usize;
+        let mut entries: VecDeque<Entry> = storage
+            .as_ref()
+            .and_then(|s| s.load())
+            .and_then(|snapshot| serde_json::from_str(&snapshot).ok())
+            .unwrap_or_default();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+        Self {
+            inner,
+            storage,
+            ttl_secs,
+            capacity,
+            cache: Mutex::new(KeyCache { entries }),
+        }
+    }
+
+    fn resolve(&self, did: String, now: u64) -> Option<Vec<u8>> {
+        if let Some(key) = self.cache.lock().get(&did, now, self.ttl_secs) {
+            return Some(key);
+        }
+        // Not under the lock: the inner resolver may block on the network.
+        let key = self.inner.did_to_public_key(did.clone())?;
+        let snapshot = {
+            let mut cache = self.cache.lock();
+            cache.insert(did, &key, now, self.capacity);
+            serde_json::to_string(&cache.entries).ok()
+        };
+        if let (Some(storage), Some(snapshot)) = (&self.storage, snapshot) {
+            storage.save(snapshot);
+        }
+        Some(key)
+    }
+}
+
+impl DidResolver for CachingDidResolver {
+    fn public_key_to_did(&self, public_key: Vec<u8>) -> String {
+        self.inner.public_key_to_did(public_key)
+    }
+
+    fn did_to_public_key(&self, did: String) -> Option<Vec<u8>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.resolve(did, now)
+    }
+}
+
+/// [`CachingDidResolver::new`] as a `DidResolver` for
+/// `SignatureVerifier::with_resolver`.
+pub fn caching_did_resolver(
+    inner: Arc<dyn DidResolver>,
+    storage: Option<Box<dyn DidCacheStorage>>,
+    ttl_secs: u64,
+    capacity: u32,
+) -> Arc<dyn DidResolver> {
+    Arc::new(CachingDidResolver::new(inner, storage, ttl_secs, capacity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    /// Resolves `did:web:<n>` to `[n; 32]` while online, counting lookups.
+    #[derive(Default)]
+    struct Network {
+        offline: AtomicBool,
+        lookups: AtomicU32,
+    }
+
+    impl DidResolver for Network {
+        fn public_key_to_did(&self, public_key: Vec<u8>) -> String {
+            format!("did:web:{}", public_key[0])
+        }
+
+        fn did_to_public_key(&self, did: String) -> Option<Vec<u8>> {
+            if self.offline.load(Ordering::SeqCst) {
+                return None;
+            }
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            Some(vec![did.strip_prefix("did:web:")?.parse().ok()?; 32])
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Memory(Arc<Mutex<Option<String>>>);
+
+    impl DidCacheStorage for Memory {
+        fn load(&self) -> Option<String> {
+            self.0.lock().clone()
+        }
+
+        fn save(&self, snapshot: String) {
+            *self.0.lock() = Some(snapshot);
+        }
+    }
+
+    #[test]
+    fn test_cache_expires_and_evicts() {
+        let network = Arc::new(Network::default());
+        let resolver = CachingDidResolver::new(network.clone(), None, 60, 2);
+        assert_eq!(resolver.resolve("did:web:1".into(), 1_000), Some(vec![1; 32]));
+        assert_eq!(resolver.resolve("did:web:1".into(), 1_060), Some(vec![1; 32]));
+        assert_eq!(network.lookups.load(Ordering::SeqCst), 1);
+        // Past the TTL the signer is resolved again.
+        resolver.resolve("did:web:1".into(), 1_061);
+        assert_eq!(network.lookups.load(Ordering::SeqCst), 2);
+
+        // Touching 1 leaves 2 as the eviction candidate.
+        resolver.resolve("did:web:2".into(), 1_100);
+        resolver.resolve("did:web:1".into(), 1_100);
+        resolver.resolve("did:web:3".into(), 1_100);
+        network.offline.store(true, Ordering::SeqCst);
+        assert!(resolver.resolve("did:web:1".into(), 1_100).is_some());
+        assert!(resolver.resolve("did:web:3".into(), 1_100).is_some());
+        assert!(resolver.resolve("did:web:2".into(), 1_100).is_none());
+    }
+
+    #[test]
+    fn test_cache_persists_through_storage() {
+        let storage = Memory::default();
+        let network = Arc::new(Network::default());
+        let online = CachingDidResolver::new(network.clone(), Some(Box::new(storage.clone())), 3_600, 8);
+        assert_eq!(online.did_to_public_key("did:web:7".into()), Some(vec![7; 32]));
+
+        // After a restart, offline: the signer still resolves.
+        network.offline.store(true, Ordering::SeqCst);
+        let restarted = caching_did_resolver(network.clone(), Some(Box::new(storage.clone())), 3_600, 8);
+        assert_eq!(restarted.did_to_public_key("did:web:7".into()), Some(vec![7; 32]));
+        assert_eq!(restarted.did_to_public_key("did:web:8".into()), None);
+        assert_eq!(restarted.public_key_to_did(vec![7; 32]), "did:web:7");
+
+        *storage.0.lock() = Some("not a snapshot".into());
+        let corrupt = CachingDidResolver::new(network, Some(Box::new(storage)), 3_600, 8);
+        assert_eq!(corrupt.did_to_public_key("did:web:7".into()), None);
+    }
+}
@@ -0,0 +1,238 @@
+//! Callback dispatch off the audio path.
+//!
+//! A host's callbacks cross the FFI into Kotlin or Swift, and a slow one
+//! would stall whichever thread pushed the audio. A listener's events are
+//! instead queued, with the subscribers their filters passed, on a bounded
+//! queue that a dispatcher thread drains, calling each subscriber in order.
+//! The thread starts with the first event that has a subscriber, so a
+//! listener nobody subscribes to, like the temporary one behind
+//! `detect_watermark`, never starts one.
+//!
+//! When the queue is full, audio level events are dropped (the meter
+//! catches up with the next one) and counted; every other event waits for
+//! room, so no detection, error or state change is lost.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, OnceLock,
+    },
+    thread::{self, ThreadId},
+};
+
+use crate::{ListenerEvent, ListenerState, SonicConfig, SonicError, WatermarkCallback, WatermarkResult};
+
+/// Events a listener's queue holds before audio level events are dropped
+/// and other events wait for the dispatcher
+pub(crate) const EVENT_QUEUE_CAPACITY: usize = 256;
+
+/// A listener event, with what its `WatermarkCallback` method is passed.
+pub(crate) enum Event {
+    WatermarkDetected(WatermarkResult),
+    WatermarkStillPresent(WatermarkResult, u64),
+    AudioLevel(f32),
+    Error(String),
+    StateChanged(ListenerState),
+    SignerConfirmed(String, f32),
+    ConfigChanged(SonicConfig, SonicConfig),
+}
+
+impl Event {
+    pub(crate) fn kind(&self) -> ListenerEvent {
+        match self {
+            Event::WatermarkDetected(_) => ListenerEvent::WatermarkDetected,
+            Event::WatermarkStillPresent(..) => ListenerEvent::WatermarkStillPresent,
+            Event::AudioLevel(_) => ListenerEvent::AudioLevel,
+            Event::Error(_) => ListenerEvent::Error,
+            Event::StateChanged(_) => ListenerEvent::StateChanged,
+            Event::SignerConfirmed(..) => ListenerEvent::SignerConfirmed,
+            Event::ConfigChanged(..) => ListenerEvent::ConfigChanged,
+        }
+    }
+}
+
+enum Message {
+    Deliver(Vec<Arc<dyn WatermarkCallback>>, Box<Event>),
+    /// Answered once every earlier message is delivered
+    Flush(SyncSender<()>),
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Dispatcher threads started from this thread
+    pub(crate) static SPAWNED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// A listener's event queue and the thread that drains it, started on
+/// first use. The thread exits once the dispatcher is dropped and the
+/// queue is drained.
+#[derive(Default)]
+pub(crate) struct Dispatcher {
+    /// `None` once starting the thread has failed: events are then
+    /// delivered on the thread that emits them
+    worker: OnceLock<Option<Worker>>,
+    dropped_levels: AtomicU64,
+}
+
+struct Worker {
+    queue: SyncSender<Message>,
+    thread: ThreadId,
+}
+
+impl Worker {
+    fn spawn() -> Result<Self, SonicError> {
+        let (queue, messages) = mpsc::sync_channel(EVENT_QUEUE_CAPACITY);
+        let handle = thread::Builder::new()
+            .name("vouch-sonic-events".into())
+            .spawn(move || {
+                for message in messages {
+                    match message {
+                        Message::Deliver(callbacks, event) => deliver(&callbacks, &event),
+                        Message::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+            .map_err(|e| SonicError::InternalError(format!("cannot spawn event dispatcher: {e}")))?;
+        #[cfg(test)]
+        SPAWNED.with(|spawned| spawned.set(spawned.get() + 1));
+        Ok(Self {
+            queue,
+            thread: handle.thread().id(),
+        })
+    }
+}
+
+impl Dispatcher {
+    /// The running worker, started if this is its first use.
+    fn worker(&self) -> Option<&Worker> {
+        self.worker
+            .get_or_init(|| {
+                Worker::spawn()
+                    .inspect_err(|e| log::warn!("{e}; delivering events inline"))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Queue `event` for `callbacks`. A callback that calls back into the
+    /// listener gets the events that causes inline, as the dispatcher
+    /// cannot wait on its own queue.
+    pub(crate) fn dispatch(&self, callbacks: Vec<Arc<dyn WatermarkCallback>>, event: Event) {
+        if callbacks.is_empty() {
+            return;
+        }
+        let Some(worker) = self
+            .worker()
+            .filter(|worker| thread::current().id() != worker.thread)
+        else {
+            deliver(&callbacks, &event);
+            return;
+        };
+        let is_level = matches!(event, Event::AudioLevel(_));
+        let message = Message::Deliver(callbacks, Box::new(event));
+        let queued = if is_level {
+            match worker.queue.try_send(message) {
+                Err(TrySendError::Full(_)) => {
+                    self.dropped_levels.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                }
+                Err(TrySendError::Disconnected(_)) => Err(()),
+                Ok(()) => Ok(()),
+            }
+        } else {
+            worker.queue.send(message).map_err(|_| ())
+        };
+        if queued.is_err() {
+            log::warn!("event dispatcher has exited; event dropped");
+        }
+    }
+
+    /// Wait until every event queued so far has been delivered.
+    pub(crate) fn flush(&self) {
+        let Some(Some(worker)) = self.worker.get() else {
+            return;
+        };
+        if thread::current().id() == worker.thread {
+            return;
+        }
+        let (done, delivered) = mpsc::sync_channel(1);
+        if worker.queue.send(Message::Flush(done)).is_ok() {
+            let _ = delivered.recv();
+        }
+    }
+
+    /// Audio level events dropped because the queue was full
+    pub(crate) fn dropped_levels(&self) -> u64 {
+        self.dropped_levels.load(Ordering::Relaxed)
+    }
+}
+
+fn deliver(callbacks: &[Arc<dyn WatermarkCallback>], event: &Event) {
+    for callback in callbacks {
+        match event {
+            Event::WatermarkDetected(result) => callback.on_watermark_detected(result.clone()),
+            Event::WatermarkStillPresent(result, present_for_ms) => {
+                callback.on_watermark_still_present(result.clone(), *present_for_ms)
+            }
+            Event::AudioLevel(level_db) => callback.on_audio_level_changed(*level_db),
+            Event::Error(message) => callback.on_error(message.clone()),
+            Event::StateChanged(state) => callback.on_state_changed(*state),
+            Event::SignerConfirmed(signer, confidence) => callback.on_signer_confirmed(signer.clone(), *confidence),
+            Event::ConfigChanged(old, new) => callback.on_config_changed(old.clone(), new.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::mpsc::Receiver;
+
+    /// Blocks in `on_state_changed` until released, counting level events.
+    struct SlowCallback {
+        release: Mutex<Receiver<()>>,
+        levels: AtomicU64,
+    }
+
+    impl WatermarkCallback for SlowCallback {
+        fn on_watermark_detected(&self, _result: WatermarkResult) {}
+        fn on_audio_level_changed(&self, _level_db: f32) {
+            self.levels.fetch_add(1, Ordering::SeqCst);
+        }
+        fn on_error(&self, _message: String) {}
+        fn on_state_changed(&self, _state: ListenerState) {
+            let _ = self.release.lock().recv();
+        }
+    }
+
+    #[test]
+    fn test_full_queue_drops_levels_only() {
+        let (release, blocked) = mpsc::channel();
+        let callback = Arc::new(SlowCallback {
+            release: Mutex::new(blocked),
+            levels: AtomicU64::new(0),
+        });
+        let callbacks = || vec![callback.clone() as Arc<dyn WatermarkCallback>];
+        let dispatcher = Dispatcher::default();
+
+        // The dispatcher is stuck in the callback; the queue fills behind it
+        // without blocking the sender.
+        dispatcher.dispatch(callbacks(), Event::StateChanged(ListenerState::Listening));
+        let levels = EVENT_QUEUE_CAPACITY as u64 + 10;
+        for _ in 0..levels {
+            dispatcher.dispatch(callbacks(), Event::AudioLevel(-20.0));
+        }
+        assert!(dispatcher.dropped_levels() > 0);
+
+        release.send(()).unwrap();
+        dispatcher.flush();
+        assert_eq!(
+            callback.levels.load(Ordering::SeqCst) + dispatcher.dropped_levels(),
+            levels
+        );
+    }
+}
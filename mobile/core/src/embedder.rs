@@ -0,0 +1,124 @@
+//! Watermark embedding for capture apps.
+//!
+//! Embeds the v3 watermark through the same `vouch-sonic-dsp` path a browser
+//! or server embed uses, and signs the payload record the detector's
+//! `payload_hash` resolves to. The audio carries only a compact ID; the
+//! signer, timestamp and covenant travel in that signed record, which the app
+//! registers so a verifier can check it with
+//! `SignatureVerifier::verify_watermark_payload`.
+
+use ed25519_dalek::{Signer, SigningKey};
+use vouch_sonic_dsp as dsp;
+
+use crate::{
+    canonicalize_covenant, encode_frame, pcm_le16_to_samples, public_key_from_did, samples_to_pcm_le16,
+    signing_input, SonicConfig, SonicError,
+};
+
+/// Lowest sample rate the v3 embedder accepts (its top layer sits near 19 kHz).
+const MIN_EMBED_SAMPLE_RATE: u32 = 44_100;
+
+/// Watermarked audio and the signed payload record it points to.
+#[derive(Debug, Clone)]
+pub struct EmbeddedWatermark {
+    /// Watermarked float samples
+    pub samples: Vec<f32>,
+    /// The same audio as 16-bit LE PCM
+    pub pcm: Vec<u8>,
+    /// Payload hash a detector reports for this audio (the server lookup key)
+    pub payload_hash: String,
+    /// Watermark ID derived from the signer DID and timestamp
+    pub watermark_id: String,
+    /// Signer DID, as signed
+    pub signer_did: String,
+    /// Unix timestamp, as signed
+    pub timestamp: u64,
+    /// Covenant JSON, as signed
+    pub covenant_json: Option<String>,
+    /// Ed25519 signature over the payload's signing input
+    pub signature: Vec<u8>,
+    /// The signing input and signature as a version 1 watermark frame, ready
+    /// to register with the server or hand to another SDK
+    pub frame: Vec<u8>,
+}
+
+/// Embeds signed watermarks into captured audio.
+pub struct SonicEmbedder {
+    sample_rate: u32,
+    signing_key: SigningKey,
+}
+
+impl SonicEmbedder {
+    /// Embedder at `config.sample_rate` (at least 44.1 kHz) signing with the
+    /// 32-byte Ed25519 secret key `signing_key`.
+    pub fn new(config: SonicConfig, signing_key: Vec<u8>) -> Result<Self, SonicError> {
+        config.validate()?;
+        if config.sample_rate < MIN_EMBED_SAMPLE_RATE {
+            return Err(SonicError::InvalidSampleRate(config.sample_rate));
+        }
+        let key: [u8; 32] = signing_key
+            .try_into()
+            .map_err(|_| SonicError::InvalidConfig("signing key must be 32 bytes".into()))?;
+        Ok(Self {
+            sample_rate: config.sample_rate,
+            signing_key: SigningKey::from_bytes(&key),
+        })
+    }
+
+    /// Watermark 16-bit LE PCM for `signer_did` at `timestamp` (Unix
+    /// seconds) and sign the payload record with its `covenant_json`.
+    ///
+    /// The clip must fit the sync preamble and one payload repetition (about
+    /// 5 s). A `did:key` signer must be this embedder's key; other DID
+    /// methods are signed as given, and verify only where they resolve to it.
+    pub fn embed_buffer(
+        &self,
+        pcm_data: &[u8],
+        signer_did: &str,
+        covenant_json: Option<String>,
+        timestamp: u64,
+    ) -> Result<EmbeddedWatermark, SonicError> {
+        let public_key = self.signing_key.verifying_key().to_bytes();
+        if public_key_from_did(signer_did).is_ok_and(|key| key != public_key) {
+            return Err(SonicError::InvalidConfig(
+                "signer DID does not match the signing key".into(),
+            ));
+        }
+        if let Some(covenant) = &covenant_json {
+            canonicalize_covenant(covenant)?;
+        }
+
+        let embedded = dsp::embed(pcm_data, self.sample_rate, signer_did, timestamp * 1000)
+            .map_err(|e| SonicError::from_dsp(e, self.sample_rate))?;
+        let message = signing_input(
+            Some(signer_did),
+            Some(timestamp),
+            Some(&embedded.payload_hash),
+            covenant_json.as_deref(),
+        )?;
+        let signature = self.signing_key.sign(&message).to_bytes().to_vec();
+        let frame = encode_frame(&message, Some(signature.clone()))?;
+        Ok(EmbeddedWatermark {
+            samples: pcm_le16_to_samples(&embedded.watermarked_audio),
+            pcm: embedded.watermarked_audio,
+            payload_hash: embedded.payload_hash,
+            watermark_id: embedded.watermark_id,
+            signer_did: signer_did.into(),
+            timestamp,
+            covenant_json,
+            signature,
+            frame,
+        })
+    }
+
+    /// [`embed_buffer`](Self::embed_buffer) for float samples.
+    pub fn embed_samples(
+        &self,
+        samples: &[f32],
+        signer_did: &str,
+        covenant_json: Option<String>,
+        timestamp: u64,
+    ) -> Result<EmbeddedWatermark, SonicError> {
+        self.embed_buffer(&samples_to_pcm_le16(samples), signer_did, covenant_json, timestamp)
+    }
+}
@@ -0,0 +1,210 @@
+//! Content fingerprints for "is this the same recording" checks.
+//!
+//! Complementary to watermark detection: a watermark proves who published
+//! audio, a fingerprint recognizes the audio itself, even when it carries no
+//! watermark. The scheme is the classic spectral-landmark hash: pick
+//! the prominent peaks of the spectrogram, pair each with a few peaks shortly
+//! after it, and hash each pair's (frequency, frequency, time gap). Pairs
+//! survive noise, level changes and cropping, and matching them with a
+//! consistent time offset is strong evidence of shared content.
+//!
+//! Frequencies are in FFT bins, so compare audio at the same sample rate.
+
+use std::collections::HashMap;
+
+use crate::spectrum::{compute_fft, hann_window};
+
+/// Spectrogram frame length.
+const FRAME_SIZE: usize = 1024;
+
+/// Spectrogram hop.
+const HOP_SIZE: usize = FRAME_SIZE / 2;
+
+/// Peaks kept per frame, strongest first.
+const PEAKS_PER_FRAME: usize = 5;
+
+/// A peak must be the maximum within this many bins either side.
+const PEAK_NEIGHBORHOOD_BINS: usize = 3;
+
+/// Peaks below this magnitude (silence, dither) are not landmarks.
+const MIN_PEAK_MAGNITUDE: f32 = 1e-3;
+
+/// Later peaks paired with each anchor.
+const FAN_OUT: usize = 5;
+
+/// Target zone: frames after the anchor a paired peak may sit in. At most
+/// 63 so the gap fits its 6 hash bits.
+const TARGET_ZONE_FRAMES: usize = 32;
+
+/// Sub-hop shifts of the query tried when comparing. A clip rarely starts on
+/// the reference's frame grid, and off-grid frames see different peaks.
+const ALIGNMENT_SHIFTS: usize = 4;
+
+/// Compact landmark fingerprint of a recording.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Landmark hashes: anchor bin (9 bits), paired bin (9 bits), frame gap
+    /// (6 bits)
+    pub hashes: Vec<u32>,
+    /// Anchor frame of each hash, same order as `hashes`
+    pub frames: Vec<u32>,
+}
+
+/// Fingerprint `samples`.
+pub fn fingerprint(samples: &[f32]) -> Fingerprint {
+    let peaks = spectral_peaks(samples);
+    let mut fp = Fingerprint::default();
+    for (frame, anchors) in peaks.iter().enumerate() {
+        for &anchor in anchors {
+            let targets = peaks
+                .iter()
+                .enumerate()
+                .skip(frame + 1)
+                .take(TARGET_ZONE_FRAMES)
+                .flat_map(|(target_frame, bins)| bins.iter().map(move |&b| (target_frame - frame, b)))
+                .take(FAN_OUT);
+            for (gap, target) in targets {
+                fp.hashes.push(landmark_hash(anchor, target, gap));
+                fp.frames.push(frame as u32);
+            }
+        }
+    }
+    fp
+}
+
+/// Similarity (0.0 - 1.0) of `samples` to the recording behind `fp`: the
+/// share of the sample's landmarks that recur in `fp` at one consistent time
+/// offset, best over a few sub-hop alignments. A clip cut from the
+/// fingerprinted recording scores near 1.0; unrelated audio near 0.0.
+pub fn compare_to_fingerprint(samples: &[f32], fp: &Fingerprint) -> f32 {
+    let mut index: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&hash, &frame) in fp.hashes.iter().zip(&fp.frames) {
+        index.entry(hash).or_default().push(frame);
+    }
+    (0..ALIGNMENT_SHIFTS)
+        .map(|s| s * HOP_SIZE / ALIGNMENT_SHIFTS)
+        .filter(|&shift| shift < samples.len())
+        .map(|shift| offset_vote_score(&fingerprint(&samples[shift..]), &index))
+        .fold(0.0, f32::max)
+}
+
+/// Share of `query`'s landmarks found in `index` at the most popular time
+/// offset: true matches agree on the offset between the two recordings.
+fn offset_vote_score(query: &Fingerprint, index: &HashMap<u32, Vec<u32>>) -> f32 {
+    if query.hashes.is_empty() {
+        return 0.0;
+    }
+    let mut votes: HashMap<i64, u32> = HashMap::new();
+    for (hash, &frame) in query.hashes.iter().zip(&query.frames) {
+        for &reference_frame in index.get(hash).into_iter().flatten() {
+            *votes.entry(reference_frame as i64 - frame as i64).or_default() += 1;
+        }
+    }
+    let best = votes.values().copied().max().unwrap_or(0);
+    (best as f32 / query.hashes.len() as f32).min(1.0)
+}
+
+fn landmark_hash(anchor: usize, target: usize, gap: usize) -> u32 {
+    ((anchor as u32 & 0x1FF) << 15) | ((target as u32 & 0x1FF) << 6) | (gap as u32 & 0x3F)
+}
+
+/// Per frame, the bins of the strongest local spectral maxima.
+fn spectral_peaks(samples: &[f32]) -> Vec<Vec<usize>> {
+    if samples.len() < FRAME_SIZE {
+        return Vec::new();
+    }
+    let window = hann_window(FRAME_SIZE);
+    let bins = FRAME_SIZE / 2;
+    (0..=(samples.len() - FRAME_SIZE) / HOP_SIZE)
+        .map(|f| {
+            let frame: Vec<f32> = samples[f * HOP_SIZE..f * HOP_SIZE + FRAME_SIZE]
+                .iter()
+                .zip(&window)
+                .map(|(x, w)| x * w)
+                .collect();
+            let Ok(spectrum) = compute_fft(&frame, FRAME_SIZE) else {
+                return Vec::new();
+            };
+            let magnitude: Vec<f32> = spectrum[..bins].iter().map(|c| c.norm()).collect();
+            // Skip DC: it's offset, not content.
+            let mut peaks: Vec<usize> = (1..bins)
+                .filter(|&k| {
+                    let lo = k.saturating_sub(PEAK_NEIGHBORHOOD_BINS).max(1);
+                    let hi = (k + PEAK_NEIGHBORHOOD_BINS).min(bins - 1);
+                    magnitude[k] >= MIN_PEAK_MAGNITUDE
+                        && (lo..=hi).all(|j| j == k || magnitude[j] < magnitude[k])
+                })
+                .collect();
+            peaks.sort_by(|&a, &b| magnitude[b].total_cmp(&magnitude[a]));
+            peaks.truncate(PEAKS_PER_FRAME);
+            peaks
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: f32 = 16_000.0;
+
+    // A seeded "melody": a new pair of tones every 100 ms over light noise,
+    // so the spectrogram has distinct landmarks that move in time.
+    fn melody(seconds: f32, seed: u64) -> Vec<f32> {
+        let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        let mut unit = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32
+        };
+        let note = (SR * 0.1) as usize;
+        let n = (SR * seconds) as usize;
+        let mut out = Vec::with_capacity(n);
+        let (mut f1, mut f2) = (0.0, 0.0);
+        for i in 0..n {
+            if i % note == 0 {
+                f1 = 200.0 + unit() * 3000.0;
+                f2 = 200.0 + unit() * 3000.0;
+            }
+            let t = i as f32 / SR;
+            let tone = (std::f32::consts::TAU * f1 * t).sin() + 0.6 * (std::f32::consts::TAU * f2 * t).sin();
+            out.push(0.3 * tone + 0.02 * (unit() * 2.0 - 1.0));
+        }
+        out
+    }
+
+    #[test]
+    fn test_fingerprint_matches_itself_and_its_clips() {
+        let original = melody(6.0, 1);
+        let fp = fingerprint(&original);
+        assert!(!fp.hashes.is_empty());
+        assert_eq!(fp.hashes.len(), fp.frames.len());
+
+        assert!(compare_to_fingerprint(&original, &fp) > 0.95);
+
+        // A quieter, noisier excerpt from the middle still matches.
+        let mut noise = melody(2.0, 99);
+        let clip: Vec<f32> = original[(SR * 2.0) as usize..(SR * 4.0) as usize]
+            .iter()
+            .zip(noise.iter_mut())
+            .map(|(x, n)| 0.5 * x + 0.05 * *n)
+            .collect();
+        let score = compare_to_fingerprint(&clip, &fp);
+        assert!(score > 0.3, "excerpt score {score}");
+    }
+
+    #[test]
+    fn test_unrelated_audio_scores_low() {
+        let fp = fingerprint(&melody(6.0, 1));
+        let score = compare_to_fingerprint(&melody(6.0, 2), &fp);
+        assert!(score < 0.05, "unrelated score {score}");
+    }
+
+    #[test]
+    fn test_short_or_silent_input_has_no_landmarks() {
+        assert!(fingerprint(&[0.1; 100]).hashes.is_empty());
+        assert!(fingerprint(&[0.0; 8000]).hashes.is_empty());
+        assert_eq!(compare_to_fingerprint(&[0.0; 8000], &Fingerprint::default()), 0.0);
+    }
+}
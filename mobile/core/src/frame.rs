@@ -0,0 +1,142 @@
+//! Binary frame for a signed watermark payload record.
+//!
+//! The audio carries only a compact ID; the record it resolves to (signer,
+//! timestamp, covenant) travels out of band. This frame is the wire form of
+//! that record, shared with the other Vouch SDKs:
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     magic "VSNC"
+//! 4       1     version (1)
+//! 5       1     flags (bit 0: signed)
+//! 6       2     payload length, big-endian
+//! 8       n     payload (the record's RFC 8785 signing input)
+//! 8+n     64    Ed25519 signature over the payload, when signed
+//! ```
+//!
+//! The magic and version lead every version, so a parser can always tell a
+//! newer frame from a corrupt one; a version it does not know is reported as
+//! `UnsupportedFormat`.
+
+use crate::SonicError;
+
+/// Leading bytes of every frame.
+const MAGIC: &[u8; 4] = b"VSNC";
+
+/// The version [`encode_frame`] writes.
+pub const FRAME_VERSION: u8 = 1;
+
+/// Flag bit: an Ed25519 signature follows the payload.
+pub const FRAME_FLAG_SIGNED: u8 = 0x01;
+
+/// Magic, version, flags and payload length.
+const HEADER_LEN: usize = 8;
+
+/// Ed25519 signature length.
+const SIGNATURE_LEN: usize = 64;
+
+/// A parsed watermark frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatermarkFrame {
+    /// Frame format version
+    pub version: u8,
+    /// Flag bits as read (see `FRAME_FLAG_SIGNED`)
+    pub flags: u8,
+    /// The payload record's signing input
+    pub payload: Vec<u8>,
+    /// Ed25519 signature over `payload`, when the frame is signed
+    pub signature: Option<Vec<u8>>,
+}
+
+/// Encode a version 1 frame around `payload` (at most 65535 bytes) and its
+/// optional 64-byte signature.
+pub fn encode_frame(payload: &[u8], signature: Option<Vec<u8>>) -> Result<Vec<u8>, SonicError> {
+    let len = u16::try_from(payload.len())
+        .map_err(|_| SonicError::InvalidConfig("frame payload exceeds 65535 bytes".into()))?;
+    if signature.as_ref().is_some_and(|s| s.len() != SIGNATURE_LEN) {
+        return Err(SonicError::InvalidConfig("frame signature must be 64 bytes".into()));
+    }
+    let flags = if signature.is_some() { FRAME_FLAG_SIGNED } else { 0 };
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len() + SIGNATURE_LEN);
+    out.extend_from_slice(MAGIC);
+    out.push(FRAME_VERSION);
+    out.push(flags);
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(payload);
+    out.extend(signature.into_iter().flatten());
+    Ok(out)
+}
+
+/// Parse a frame, dispatching on its version.
+pub fn parse_frame(bytes: &[u8]) -> Result<WatermarkFrame, SonicError> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+        return Err(frame_error("missing VSNC header"));
+    }
+    match bytes[4] {
+        1 => parse_v1(bytes),
+        version => Err(SonicError::UnsupportedFormat(version)),
+    }
+}
+
+fn parse_v1(bytes: &[u8]) -> Result<WatermarkFrame, SonicError> {
+    let flags = bytes[5];
+    if flags & !FRAME_FLAG_SIGNED != 0 {
+        return Err(frame_error(format!("reserved flag bits set: {flags:#04x}")));
+    }
+    let len = u16::from_be_bytes([bytes[6], bytes[7]]) as usize;
+    let signature_len = if flags & FRAME_FLAG_SIGNED != 0 { SIGNATURE_LEN } else { 0 };
+    let body = &bytes[HEADER_LEN..];
+    if body.len() != len + signature_len {
+        return Err(frame_error(format!(
+            "expected {} bytes after the header, found {}",
+            len + signature_len,
+            body.len()
+        )));
+    }
+    Ok(WatermarkFrame {
+        version: 1,
+        flags,
+        payload: body[..len].to_vec(),
+        signature: (signature_len > 0).then(|| body[len..].to_vec()),
+    })
+}
+
+fn frame_error(message: impl std::fmt::Display) -> SonicError {
+    SonicError::ProcessingFailed(format!("invalid watermark frame: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let signed = encode_frame(b"{\"did\":\"x\"}", Some(vec![7; 64])).unwrap();
+        assert_eq!(&signed[..8], b"VSNC\x01\x01\x00\x0b");
+        let frame = parse_frame(&signed).unwrap();
+        assert_eq!(frame.payload, b"{\"did\":\"x\"}");
+        assert_eq!(frame.signature, Some(vec![7; 64]));
+
+        let unsigned = parse_frame(&encode_frame(b"{}", None).unwrap()).unwrap();
+        assert_eq!((unsigned.flags, unsigned.signature), (0, None));
+    }
+
+    #[test]
+    fn test_rejects_malformed_and_unknown_frames() {
+        let frame = encode_frame(b"{}", Some(vec![1; 64])).unwrap();
+        assert!(parse_frame(&frame[..frame.len() - 1]).is_err());
+        assert!(parse_frame(b"VSN").is_err());
+        assert!(parse_frame(b"RIFF\x01\x00\x00\x00").is_err());
+
+        let mut reserved = frame.clone();
+        reserved[5] |= 0x80;
+        assert!(parse_frame(&reserved).is_err());
+
+        let mut future = frame;
+        future[4] = 2;
+        assert!(matches!(parse_frame(&future), Err(SonicError::UnsupportedFormat(2))));
+
+        assert!(encode_frame(b"{}", Some(vec![0; 10])).is_err());
+        assert!(encode_frame(&vec![0; 70_000], None).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Timestamp freshness and replay detection.
+//!
+//! A valid signature says who signed a watermark and when, not that it is
+//! being heard as it was made: a recording played back years later, or a
+//! clip looped on air, verifies just the same. The verifier compares the
+//! signed timestamp with its clock, and remembers the payloads it has
+//! accepted so that hearing one again can be flagged.
+
+use std::collections::{HashSet, VecDeque};
+
+/// Payloads a verifier remembers for replay detection.
+const REPLAY_CACHE_CAPACITY: usize = 4096;
+
+/// How a payload's signed timestamp compares with the verifier's clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampStatus {
+    /// Within the configured age and clock skew
+    Fresh,
+    /// Older than `VerifierConfig::max_timestamp_age_secs`
+    Stale,
+    /// Later than the verifier's clock plus `VerifierConfig::max_clock_skew_secs`
+    Future,
+}
+
+/// Status of `timestamp` at `now` (both Unix seconds).
+pub(crate) fn timestamp_status(
+    timestamp: u64,
+    now: u64,
+    max_age_secs: Option<u64>,
+    max_clock_skew_secs: u64,
+) -> TimestampStatus {
+    if timestamp > now.saturating_add(max_clock_skew_secs) {
+        TimestampStatus::Future
+    } else if max_age_secs.is_some_and(|age| now.saturating_sub(timestamp) > age) {
+        TimestampStatus::Stale
+    } else {
+        TimestampStatus::Fresh
+    }
+}
+
+/// Payload hashes already accepted, oldest first, forgetting the oldest
+/// beyond [`REPLAY_CACHE_CAPACITY`].
+#[derive(Debug, Default)]
+pub(crate) struct ReplayCache {
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+}
+
+impl ReplayCache {
+    /// Record `payload_hash`, returning whether it was already seen.
+    pub(crate) fn replayed(&mut self, payload_hash: &str) -> bool {
+        if self.seen.contains(payload_hash) {
+            return true;
+        }
+        self.seen.insert(payload_hash.to_string());
+        self.order.push_back(payload_hash.to_string());
+        if self.order.len() > REPLAY_CACHE_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        false
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.seen.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_status() {
+        let now = 1_700_000_000;
+        assert_eq!(timestamp_status(now - 10, now, Some(60), 300), TimestampStatus::Fresh);
+        assert_eq!(timestamp_status(now - 61, now, Some(60), 300), TimestampStatus::Stale);
+        assert_eq!(timestamp_status(0, now, None, 300), TimestampStatus::Fresh);
+        assert_eq!(timestamp_status(now + 300, now, None, 300), TimestampStatus::Fresh);
+        assert_eq!(timestamp_status(now + 301, now, None, 300), TimestampStatus::Future);
+        assert_eq!(timestamp_status(u64::MAX, u64::MAX, Some(0), u64::MAX), TimestampStatus::Fresh);
+    }
+
+    #[test]
+    fn test_replay_cache_forgets_oldest() {
+        let mut cache = ReplayCache::default();
+        assert!(!cache.replayed("a"));
+        assert!(cache.replayed("a"));
+        for i in 0..REPLAY_CACHE_CAPACITY {
+            cache.replayed(&i.to_string());
+        }
+        assert!(!cache.replayed("a"));
+        cache.clear();
+        assert!(!cache.replayed("0"));
+    }
+}
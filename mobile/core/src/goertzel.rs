@@ -0,0 +1,113 @@
+//! Narrowband check for the v3 sync chirp.
+//!
+//! A continuously listening app hands the detector a fresh window every few
+//! seconds, and nearly all of them hold no watermark. Every v3 watermark
+//! opens with the same 600 ms linear chirp, so a window without that chirp
+//! where the detector searches for it cannot decode. This module tracks the
+//! chirp with Goertzel filters: the search region is cut into short blocks,
+//! each block is measured at the one frequency the chirp passes through
+//! during each block-length slice of its sweep, and a chirp shows up as a
+//! diagonal of blocks that are each loud at their slice's frequency. That
+//! is a few dozen single-bin filters per block instead of the matched
+//! filter and layer decoding of the full detector.
+
+use vouch_sonic_dsp as dsp;
+
+use crate::spectrum::hann_window;
+
+/// Length of one Goertzel block. Over 20 ms the chirp sweeps 67 Hz, within
+/// a block's frequency resolution, while a chirp still spans 30 blocks.
+const PILOT_BLOCK_MS: u32 = 20;
+
+/// Score at or above which the sync chirp counts as present. Watermarked
+/// audio scores in the hundreds; unmarked speech, music and noise stay below
+/// about 4, since no host sweeps the chirp band at the chirp's rate.
+pub(crate) const PILOT_SCORE_THRESHOLD: f32 = 10.0;
+
+/// Power of `samples` at `freq` Hz (one Goertzel bin).
+pub(crate) fn goertzel_power(samples: &[f32], freq: f32, sample_rate: u32) -> f64 {
+    let coeff = 2.0 * (std::f64::consts::TAU * freq as f64 / sample_rate as f64).cos();
+    let (mut s1, mut s2) = (0.0f64, 0.0f64);
+    for &x in samples {
+        let s0 = x as f64 + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    s1 * s1 + s2 * s2 - coeff * s1 * s2
+}
+
+/// How strongly `samples` hold the sync chirp: the best, over start blocks,
+/// of the mean power of each chirp slice's frequency in its block, relative
+/// to that frequency's mean power over all of `samples`. Near 1 without a
+/// chirp; 0 when `samples` are shorter than a chirp.
+pub(crate) fn sync_pilot_score(samples: &[f32], sample_rate: u32) -> f32 {
+    let block = (sample_rate * PILOT_BLOCK_MS / 1000) as usize;
+    let steps = (dsp::CHIRP_DURATION_MS as u32 / PILOT_BLOCK_MS) as usize;
+    if block == 0 || samples.len() < block * steps {
+        return 0.0;
+    }
+    let freqs: Vec<f32> = (0..steps)
+        .map(|k| dsp::CHIRP_F0 + (dsp::CHIRP_F1 - dsp::CHIRP_F0) * (k as f32 + 0.5) / steps as f32)
+        .collect();
+    let window = hann_window(block);
+    let mut frame = vec![0.0f32; block];
+    let powers: Vec<Vec<f64>> = samples
+        .chunks_exact(block)
+        .map(|chunk| {
+            for ((f, x), w) in frame.iter_mut().zip(chunk).zip(&window) {
+                *f = x * w;
+            }
+            freqs.iter().map(|&freq| goertzel_power(&frame, freq, sample_rate)).collect()
+        })
+        .collect();
+    let mean: Vec<f64> = (0..steps)
+        .map(|k| powers.iter().map(|p| p[k]).sum::<f64>() / powers.len() as f64)
+        .collect();
+    let best = (0..=powers.len() - steps)
+        .map(|t| (0..steps).map(|k| powers[t + k][k] / mean[k].max(1e-20)).sum::<f64>() / steps as f64)
+        .fold(0.0f64, f64::max);
+    best as f32
+}
+
+/// Whether the sync chirp appears where the detector searches a window of
+/// `samples` for it (the first 4 s, or three chirp lengths if longer).
+pub(crate) fn sync_pilot_present(samples: &[f32], sample_rate: u32) -> bool {
+    let chirp = (dsp::CHIRP_DURATION_MS as u64 * sample_rate as u64 / 1000) as usize;
+    let search = (sample_rate as usize * 4).max(chirp * 3).min(samples.len());
+    sync_pilot_score(&samples[..search], sample_rate) >= PILOT_SCORE_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{synth, SonicConfig, Synthesizer};
+
+    #[test]
+    fn test_sync_pilot_separates_marked_from_unmarked_audio() {
+        let sample_rate = 44_100;
+        let config = SonicConfig {
+            sample_rate,
+            ..Default::default()
+        };
+        let marked = Synthesizer::new(config)
+            .unwrap()
+            .with_seed(3)
+            .synthesize("did:key:z6MkPilot", 1_700_000_000_000, 10_000)
+            .unwrap()
+            .samples;
+        let cover = synth::broadband_cover(sample_rate as usize * 10, sample_rate, 3);
+        // A watermark starting 3 s into the window is still inside the search.
+        let mut late = cover[..sample_rate as usize * 3].to_vec();
+        late.extend_from_slice(&marked[..sample_rate as usize * 7]);
+
+        assert!(sync_pilot_present(&marked, sample_rate));
+        assert!(sync_pilot_present(&late, sample_rate));
+        assert!(!sync_pilot_present(&cover, sample_rate));
+        assert!(!sync_pilot_present(&vec![0.0; sample_rate as usize * 10], sample_rate));
+        assert_eq!(sync_pilot_score(&marked[..1000], sample_rate), 0.0);
+
+        // One block of a steady tone in the band is a Goertzel peak.
+        let tone: Vec<f32> = (0..882).map(|n| (std::f32::consts::TAU * 2_000.0 * n as f32 / 44_100.0).sin()).collect();
+        assert!(goertzel_power(&tone, 2_000.0, sample_rate) > 100.0 * goertzel_power(&tone, 3_000.0, sample_rate));
+    }
+}
@@ -0,0 +1,113 @@
+//! Detection history.
+//!
+//! A listener reports detections as they happen; apps that show "what was
+//! heard today" or sync detections to a server later need them kept. A
+//! [`DetectionStore`] set on the listener receives every detection it
+//! emits. [`InMemoryDetectionStore`] keeps them for the session; apps that
+//! need them across restarts implement the trait over their own database.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::WatermarkResult;
+
+/// A detection and when it was heard.
+#[derive(Debug, Clone)]
+pub struct DetectionRecord {
+    /// Device clock when the listener emitted the detection (Unix seconds)
+    pub detected_at: u64,
+    pub result: WatermarkResult,
+}
+
+/// Where a listener keeps its detections. Implement it in the host app to
+/// persist them (SQLite, Core Data, Room, ...).
+pub trait DetectionStore: Send + Sync {
+    /// Add a detection.
+    fn insert(&self, record: DetectionRecord);
+
+    /// Detections heard from `since` up to `until` (inclusive, Unix
+    /// seconds; `None` is unbounded) and, when `signer_did` is given,
+    /// signed by it; oldest first.
+    fn query(&self, since: Option<u64>, until: Option<u64>, signer_did: Option<String>) -> Vec<DetectionRecord>;
+
+    /// Remove detections heard before `before` (Unix seconds), returning
+    /// how many were removed.
+    fn purge(&self, before: u64) -> u32;
+}
+
+/// Detections kept in memory, for the lifetime of the store.
+#[derive(Debug, Default)]
+pub struct InMemoryDetectionStore {
+    records: Mutex<Vec<DetectionRecord>>,
+}
+
+impl InMemoryDetectionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DetectionStore for InMemoryDetectionStore {
+    fn insert(&self, record: DetectionRecord) {
+        let mut records = self.records.lock();
+        // Keep heard order even if the device clock steps back.
+        let at = records.partition_point(|r| r.detected_at <= record.detected_at);
+        records.insert(at, record);
+    }
+
+    fn query(&self, since: Option<u64>, until: Option<u64>, signer_did: Option<String>) -> Vec<DetectionRecord> {
+        self.records
+            .lock()
+            .iter()
+            .filter(|r| since.is_none_or(|since| r.detected_at >= since))
+            .filter(|r| until.is_none_or(|until| r.detected_at <= until))
+            .filter(|r| signer_did.is_none() || r.result.signer_did == signer_did)
+            .cloned()
+            .collect()
+    }
+
+    fn purge(&self, before: u64) -> u32 {
+        let mut records = self.records.lock();
+        let count = records.len();
+        records.retain(|r| r.detected_at >= before);
+        (count - records.len()) as u32
+    }
+}
+
+/// An empty [`InMemoryDetectionStore`], for `SonicListener::set_detection_store`.
+pub fn in_memory_detection_store() -> Arc<dyn DetectionStore> {
+    Arc::new(InMemoryDetectionStore::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(detected_at: u64, signer_did: &str) -> DetectionRecord {
+        DetectionRecord {
+            detected_at,
+            result: WatermarkResult {
+                detected: true,
+                signer_did: Some(signer_did.into()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_in_memory_store_queries_and_purges() {
+        let store = InMemoryDetectionStore::new();
+        store.insert(record(300, "did:web:a.example"));
+        store.insert(record(100, "did:web:a.example"));
+        store.insert(record(200, "did:web:b.example"));
+
+        let times = |records: Vec<DetectionRecord>| records.iter().map(|r| r.detected_at).collect::<Vec<_>>();
+        assert_eq!(times(store.query(None, None, None)), [100, 200, 300]);
+        assert_eq!(times(store.query(Some(150), Some(300), None)), [200, 300]);
+        assert_eq!(times(store.query(None, None, Some("did:web:a.example".into()))), [100, 300]);
+
+        assert_eq!(store.purge(200), 1);
+        assert_eq!(times(store.query(None, None, None)), [200, 300]);
+    }
+}
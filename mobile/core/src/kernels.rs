@@ -0,0 +1,89 @@
+//! Reduction kernels over sample buffers.
+//!
+//! Every buffer the listener sees is summed for its level and its variance
+//! guard, and timed scans sum the energy of every tracked repetition. With
+//! the `simd` feature these reductions run four f64 lanes at a time through
+//! `wide`, which uses NEON on arm64 and SSE/AVX on x86 and falls back to
+//! scalar code on other targets; without it they are plain loops. Lane-wise
+//! accumulation changes the order of the additions, so the two paths agree to
+//! rounding rather than bit for bit. Sums are kept in f64 either way.
+
+/// Sum of the samples.
+pub(crate) fn sum(samples: &[f32]) -> f64 {
+    reduce(samples, 0.0, false)
+}
+
+/// Sum of the squared samples (the buffer's energy).
+pub(crate) fn sum_squares(samples: &[f32]) -> f64 {
+    reduce(samples, 0.0, true)
+}
+
+/// Sum of the squared deviations of the samples from `mean`.
+pub(crate) fn centered_sum_squares(samples: &[f32], mean: f64) -> f64 {
+    reduce(samples, mean, true)
+}
+
+#[cfg(feature = "simd")]
+fn reduce(samples: &[f32], offset: f64, square: bool) -> f64 {
+    simd::reduce(samples, offset, square)
+}
+
+#[cfg(not(feature = "simd"))]
+fn reduce(samples: &[f32], offset: f64, square: bool) -> f64 {
+    scalar::reduce(samples, offset, square)
+}
+
+mod scalar {
+    /// Sum of `s - offset`, or of its square, over `samples`.
+    pub(super) fn reduce(samples: &[f32], offset: f64, square: bool) -> f64 {
+        if square {
+            samples.iter().map(|&s| (s as f64 - offset) * (s as f64 - offset)).sum()
+        } else {
+            samples.iter().map(|&s| s as f64 - offset).sum()
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use wide::f64x4;
+
+    const LANES: usize = 4;
+
+    /// `scalar::reduce`, four lanes at a time.
+    pub(super) fn reduce(samples: &[f32], offset: f64, square: bool) -> f64 {
+        let offsets = f64x4::splat(offset);
+        let mut acc = f64x4::ZERO;
+        let mut chunks = samples.chunks_exact(LANES);
+        for chunk in &mut chunks {
+            let x = f64x4::from([chunk[0] as f64, chunk[1] as f64, chunk[2] as f64, chunk[3] as f64]) - offsets;
+            acc = if square { x.mul_add(x, acc) } else { acc + x };
+        }
+        acc.reduce_add() + super::scalar::reduce(chunks.remainder(), offset, square)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-12 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn test_kernels_match_scalar_loops() {
+        // Odd length, so the SIMD path also exercises its scalar remainder.
+        let samples: Vec<f32> = (0..10_007).map(|i| ((i * 7919) % 2001) as f32 / 1000.0 - 1.0).collect();
+        let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+
+        assert!(close(sum(&samples), samples.iter().map(|&s| s as f64).sum()));
+        assert!(close(sum_squares(&samples), samples.iter().map(|&s| s as f64 * s as f64).sum()));
+        assert!(close(
+            centered_sum_squares(&samples, mean),
+            samples.iter().map(|&s| (s as f64 - mean).powi(2)).sum()
+        ));
+        assert_eq!(sum_squares(&[]), 0.0);
+        assert_eq!(sum(&[0.5, 0.25, 0.125]), 0.875);
+    }
+}
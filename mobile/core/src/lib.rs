@@ -30,18 +30,113 @@
 //! }
 //! ```
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use thiserror::Error;
 use vouch_sonic_dsp as dsp;
 
+mod c2pa;
+mod cache;
+#[cfg(feature = "capture")]
+mod capture;
+#[cfg(feature = "compressed")]
+mod compressed;
+mod config_builder;
+#[cfg(feature = "config-files")]
+mod config_file;
+mod covenant;
+mod covenant_builder;
+mod covenant_encryption;
+mod covenant_schema;
+mod credential;
+mod dedup;
+mod did;
+mod did_cache;
+mod dispatch;
+mod embedder;
+mod fingerprint;
+mod freshness;
+mod frame;
+mod goertzel;
+mod history;
+mod kernels;
+mod noise_floor;
+#[cfg(feature = "opus")]
+mod opus;
+mod policy;
+mod receipt;
+mod resample;
+mod session;
+mod signature;
+mod spectrum;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod status;
+mod synth;
+mod timed;
+mod timing;
+mod trust;
+#[cfg(feature = "files")]
+mod wav;
+
+pub use c2pa::{c2pa_assertions, C2PA_SOFT_BINDING_ALG};
+#[cfg(feature = "compressed")]
+pub use compressed::{detect_watermark_in_file, FileScan};
+pub use config_builder::SonicConfigBuilder;
+pub use covenant::canonicalize_covenant;
+pub use covenant_builder::CovenantBuilder;
+pub use covenant_encryption::{
+    decrypt_covenant, encrypt_covenant, is_encrypted_covenant, x25519_public_key, CovenantKeyProvider,
+    COVENANT_ENCRYPTION,
+};
+pub use covenant_schema::{
+    covenant_to_json, parse_covenant, supported_covenant_versions, upgrade_covenant, Covenant, CURRENT_COVENANT_VERSION,
+};
+pub use credential::{to_verifiable_credential, VOUCH_CREDENTIALS_CONTEXT};
+pub use did::{
+    did_from_public_key, did_web_url, public_key_from_did, public_key_from_did_document, signer_keys_from_did_document,
+    DidKeyResolver, DidResolver, SignerKey,
+};
+pub use did_cache::{caching_did_resolver, CachingDidResolver, DidCacheStorage};
+pub use embedder::{EmbeddedWatermark, SonicEmbedder};
+pub use fingerprint::{compare_to_fingerprint, fingerprint, Fingerprint};
+pub use freshness::TimestampStatus;
+pub use frame::{encode_frame, parse_frame, WatermarkFrame, FRAME_FLAG_SIGNED, FRAME_VERSION};
+pub use history::{in_memory_detection_store, DetectionRecord, DetectionStore, InMemoryDetectionStore};
+#[cfg(feature = "opus")]
+pub use opus::{decode_opus_to_samples, OpusPacketDecoder};
+pub use policy::{is_permitted, DenialReason, PolicyDecision, UsageKind};
+pub use receipt::{verify_receipt, DetectionReceipt, DeviceKeyProvider, ReceiptSigner, ReceiptVerification};
+pub use rustfft::num_complex::Complex;
+pub use session::SessionSummary;
+pub use signature::SignatureAlgorithm;
+pub use spectrum::{watermark_energy_map, QualityEstimator, SpectralBalanceEstimator};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteDetectionStore;
+pub use status::{status_list_revoked, SignerStatus, StatusListProvider};
+pub use synth::{SynthesizedAudio, Synthesizer};
+pub use trust::TrustStore;
+pub use timed::{TimedDetection, TimedScan};
+#[cfg(feature = "files")]
+pub use wav::{detect_watermark_in_wav, detect_watermark_in_wav_with_progress};
+
 // =============================================================================
 // UniFFI Scaffolding
 // =============================================================================
 
-uniffi::include_scaffolding!("vouch_sonic_core");
+// The generated scaffolding has doc comments followed by blank lines, which
+// clippy flags; it is generated code we cannot edit.
+#[allow(clippy::empty_line_after_doc_comments)]
+mod scaffolding {
+    use super::*;
+    uniffi::include_scaffolding!("vouch_sonic_core");
+}
+use scaffolding::UniFfiTag;
 
 // =============================================================================
 // Constants
@@ -59,9 +154,74 @@ const DEFAULT_FRAME_SIZE_MS: u32 = 50;
 /// Default detection threshold
 const DEFAULT_THRESHOLD: f32 = 0.5;
 
+/// Hamming(7,4) codewords in a v3 frame: the ID and its CRC-16, two per byte
+const CODEWORDS_PER_FRAME: u32 = ((dsp::V3_ID_BYTES + 2) * 2) as u32;
+
 /// Default spreading factor
 const DEFAULT_SPREADING_FACTOR: u32 = 100;
 
+/// Analysis window accumulated by the streaming API before the detector runs.
+/// The v3 watermark is a 600 ms chirp followed by an ID that repeats every
+/// ~4.2 s, so a window must span several seconds to decode.
+const STREAM_WINDOW_MS: u32 = 10_000;
+
+/// How far consecutive streaming windows advance. The detector looks for
+/// the sync preamble in the first 4 s of a window, so a 600 ms chirp is found
+/// only when it starts in the first 3.4 s. Advancing by exactly that span
+/// gives every preamble one window that can decode it: a watermark straddling
+/// a window boundary is picked up by the next window, and none is reported
+/// twice.
+const STREAM_HOP_MS: u32 = 3_400;
+
+/// Default fraction of full-scale samples above which a detection is suppressed
+const DEFAULT_MAX_CLIPPING_RATIO: f32 = 0.05;
+
+/// Default variance floor below which a buffer is treated as degenerate
+/// (constant / DC or digital silence); roughly a -90 dBFS RMS signal
+const DEFAULT_MIN_SIGNAL_VARIANCE: f32 = 1e-9;
+
+/// Default sync peak height, relative to the strongest, that counts as a
+/// separate propagation path (about -10 dB)
+const DEFAULT_MIN_PATH_PROMINENCE: f32 = 0.3;
+
+/// Default bottom of the reported audio level scale, in dBFS
+const DEFAULT_LEVEL_FLOOR_DB: f32 = -90.0;
+
+/// Default accumulated confidence at which a signer is confirmed: the
+/// equivalent of three solid detections, or more marginal ones
+const DEFAULT_SIGNER_CONFIRMATION_THRESHOLD: f32 = 2.0;
+
+/// Default overlap of consecutive quality STFT frames: half a frame, where a
+/// Hann window's overlapping frames weight every sample equally
+const DEFAULT_STFT_OVERLAP: f32 = 0.5;
+
+/// Default quality STFT frame length, in samples
+const DEFAULT_STFT_FFT_SIZE: u32 = spectrum::QUALITY_FFT_SIZE as u32;
+
+/// Coarsest quality STFT bin spacing accepted, in Hz: the v3 tones sit at
+/// least 300 Hz apart, and coarser bins blur neighbouring tones together
+const MAX_STFT_BIN_HZ: u32 = 300;
+
+/// Largest accepted `stft_overlap`; beyond it the hop shrinks toward a
+/// single sample and the frame count explodes
+const MAX_STFT_OVERLAP: f32 = 0.9;
+
+/// How often `consume` wakes while its channel is idle to check whether the
+/// listener was stopped
+const CONSUME_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Normalized correlation with the sync chirp at which streamed audio counts
+/// as sync-locked (`ListenerState::Acquiring`). Unmarked broadband audio peaks
+/// around 0.02; an embedded preamble reaches well over 0.1.
+const SYNC_LOCK_CORRELATION: f64 = 0.08;
+
+/// Chips one payload byte occupies: two nibbles, Hamming(7,4) coded, one code
+/// bit per chip
+const CHIPS_PER_PAYLOAD_BYTE: usize = 14;
+
+/// Absolute sample level counted as clipped (16-bit full scale is 32767/32768)
+const CLIP_LEVEL: f32 = 0.999;
+
 // =============================================================================
 // Errors
 // =============================================================================
@@ -89,8 +249,52 @@ pub enum SonicError {
     #[error("Listener not running")]
     ListenerNotRunning,
 
+    #[error("Listener is shut down")]
+    ListenerClosed,
+
     #[error("Internal error: {0}")]
     InternalError(String),
+
+    #[error("Sync preamble not found")]
+    SyncNotFound,
+
+    #[error("Payload CRC mismatch")]
+    CrcMismatch,
+
+    #[error("Payload errors exceed what error correction can recover")]
+    ErrorCorrectionExceeded,
+
+    #[error("Signature is invalid")]
+    SignatureInvalid,
+
+    #[error("Unsupported format: {0}")]
+    UnsupportedFormat(u8),
+
+    #[error("Invalid key or DID: {0}")]
+    InvalidKey(String),
+
+    #[error("Signer is not pinned in the trust store: {0}")]
+    UntrustedSigner(String),
+
+    #[error("Signer has been revoked: {0}")]
+    SignerRevoked(String),
+
+    #[error("Covenant decryption failed: {0}")]
+    DecryptionFailed(String),
+}
+
+impl SonicError {
+    /// Map a `vouch-sonic-dsp` error for audio at `sample_rate`.
+    fn from_dsp(e: dsp::DspError, sample_rate: u32) -> Self {
+        match e {
+            dsp::DspError::SampleRateTooLow => SonicError::InvalidSampleRate(sample_rate),
+            dsp::DspError::AudioTooShort => SonicError::BufferTooShort(dsp::MIN_DETECTION_SAMPLES),
+            dsp::DspError::SyncNotFound => SonicError::SyncNotFound,
+            dsp::DspError::CrcMismatch => SonicError::CrcMismatch,
+            dsp::DspError::ErrorCorrectionExceeded => SonicError::ErrorCorrectionExceeded,
+            e @ dsp::DspError::FftFailed(_) => SonicError::ProcessingFailed(e.to_string()),
+        }
+    }
 }
 
 // Implement uniffi compatible error conversion
@@ -104,8 +308,91 @@ impl From<SonicError> for uniffi::UnexpectedUniFFICallbackError {
 // Configuration
 // =============================================================================
 
+/// How `process_interleaved` reduces multi-channel audio to the mono signal
+/// the detector runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config-files", derive(serde::Serialize, serde::Deserialize))]
+pub enum DownmixMode {
+    /// Average all channels. A watermark carried only in the stereo
+    /// difference (L-R) cancels out.
+    #[default]
+    Average,
+    /// First channel only
+    Left,
+    /// Second channel only (the first for mono input)
+    Right,
+    /// Detect on the mid (average) signal, then, if nothing is found, on the
+    /// side signal `(L - R) / 2`, which recovers watermarks embedded in the
+    /// stereo difference
+    MidSide,
+    /// Detect on every channel separately and report the most confident
+    /// result, with its `channel_index`. Finds a watermark carried by one
+    /// channel of a multi-track recording, at one detector run per channel.
+    BestChannel,
+}
+
+/// Taper applied to each STFT frame before its FFT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config-files", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowFunction {
+    /// No taper. Narrowest main lobe, but a tone between bins leaks into
+    /// the whole spectrum through -13 dB sidelobes.
+    Rectangular,
+    /// Raised cosine, sidelobes falling off fast from -31 dB
+    #[default]
+    Hann,
+    /// Raised cosine on a pedestal, flatter -43 dB sidelobes
+    Hamming,
+    /// Three-term cosine, -58 dB sidelobes at the cost of a wider main lobe
+    Blackman,
+}
+
+/// How the samples of a PCM buffer are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "config-files", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleEncoding {
+    /// Two's complement integers
+    #[default]
+    SignedInt,
+    /// Offset-binary integers, zero at half scale
+    UnsignedInt,
+    /// IEEE 754 floats in -1.0..1.0 (32-bit only)
+    Float,
+}
+
+/// Layout of the raw bytes passed to `process_buffer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config-files", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config-files", serde(deny_unknown_fields))]
+pub struct PcmFormat {
+    /// Bits per sample: 16, 24 (packed, 3 bytes) or 32
+    pub bits_per_sample: u32,
+    /// Integer or float samples
+    pub encoding: SampleEncoding,
+    /// Most significant byte first
+    pub big_endian: bool,
+    /// Multi-channel samples alternate frame by frame (`true`) rather than
+    /// each channel filling a contiguous block in turn (planar). Only
+    /// matters with `SonicConfig::channels` above 1.
+    pub interleaved: bool,
+}
+
+impl Default for PcmFormat {
+    /// 16-bit signed little-endian, interleaved
+    fn default() -> Self {
+        Self {
+            bits_per_sample: 16,
+            encoding: SampleEncoding::SignedInt,
+            big_endian: false,
+            interleaved: true,
+        }
+    }
+}
+
 /// Configuration for the Sonic Listener
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "config-files", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config-files", serde(default, deny_unknown_fields))]
 pub struct SonicConfig {
     /// Target sample rate in Hz (default: 16000)
     pub sample_rate: u32,
@@ -115,12 +402,157 @@ pub struct SonicConfig {
     
     /// Detection confidence threshold (default: 0.5)
     pub detection_threshold: f32,
+
+    /// Hold detections to the room's noise floor as well (default: `None`,
+    /// off). The listener measures how strongly each buffer that decodes
+    /// nothing correlates with the sync chirp; once 8 have been measured, a
+    /// detection whose buffer does not correlate this many standard
+    /// deviations above their mean is dropped. The floor covers the last 64
+    /// such buffers and is reset by `stop_listening` and by sample rate
+    /// changes. Must be in (0.0, 10.0] when set.
+    pub adaptive_threshold_sigmas: Option<f32>,
+
+    /// Buffers or streamed windows in a row a watermark must be detected in
+    /// before it is reported (default: 1, the first). Until then its
+    /// results are reported as not detected, so a one-off decode never
+    /// reaches the callbacks. Must be at least 1.
+    pub min_detection_frames: u32,
     
-    /// Spread spectrum spreading factor (default: 100)
+    /// Spread spectrum spreading factor (default: 100). Kept for config
+    /// compatibility: the v3 codec is FSK and its data rate is fixed by the
+    /// format (50 ms chips, see the README's "Data Rate"), not configurable.
     pub spreading_factor: u32,
     
     /// Enable chirp synchronization markers (default: true)
     pub enable_chirp_sync: bool,
+
+    /// Fraction of clipped samples above which detections are suppressed
+    /// (default: 0.05). Hard clipping sprays broadband energy that the
+    /// detector can mistake for watermark tones, and destroys a real watermark
+    /// anyway, so severely clipped buffers are not trusted.
+    pub max_clipping_ratio: f32,
+
+    /// Variance floor below which a buffer is rejected as degenerate without
+    /// running detection (default: 1e-9). Catches digital silence and
+    /// constant/DC buffers; 0.0 disables the check.
+    pub min_signal_variance: f32,
+
+    /// Upper bound on `on_audio_level_changed` calls per second (default: 0,
+    /// every buffer). Each level callback crosses the FFI boundary; level
+    /// updates arriving sooner than `1000 / hz` ms after the last one are
+    /// dropped. Detection callbacks are never throttled.
+    pub max_level_callback_hz: u32,
+
+    /// Sync matched-filter peak height, relative to the strongest peak, for
+    /// an arrival to count as a propagation path in timed scans (default:
+    /// 0.3). Offsets are reported for the earliest such path (the direct
+    /// sound); later ones are counted as echoes. Lower it to pick up weaker
+    /// reflections.
+    pub min_path_prominence: f32,
+
+    /// Attach a copy of the configuration in effect to every result the
+    /// listener produces (default: false), so logged detections record the
+    /// settings behind them even when setters changed them mid-session
+    pub collect_config_snapshot: bool,
+
+    /// Bottom of the level meter scale in dBFS (default: -90.0). Levels
+    /// reported to `on_audio_level_changed` are clamped to it, so digital
+    /// silence reads as the floor rather than the -200 dBFS of the raw RMS.
+    pub level_floor_db: f32,
+
+    /// Accumulated confidence at which `on_signer_confirmed` fires for a
+    /// signer (default: 2.0). Every decoded frame adds its confidence to its
+    /// signer's total, including frames below `detection_threshold`, so a run
+    /// of individually marginal frames can still confirm a signer.
+    pub signer_confirmation_threshold: f32,
+
+    /// Downmix applied by `process_interleaved` to multi-channel buffers
+    /// (default: `Average`)
+    pub downmix_mode: DownmixMode,
+
+    /// Samples a timed scan advances between analysis windows (default:
+    /// `None`, a quarter window). The detector only searches the first few
+    /// seconds of each window for the sync preamble, so a step longer than
+    /// that search can jump past a watermark: coarser steps scan fewer windows
+    /// (faster, less battery) at the risk of misses, finer steps catch every
+    /// preamble at the cost of more windows. Must be at least 1 when set.
+    pub correlation_search_step: Option<u32>,
+
+    /// Leave the DC and Nyquist bins out of the `audio_quality` estimate
+    /// (default: false). An uncorrected microphone DC offset concentrates in
+    /// the DC bin and can dominate the band energies the estimate compares.
+    pub exclude_dc_and_nyquist: bool,
+
+    /// Window applied to each frame of the `audio_quality` STFT (default:
+    /// `Hann`). An untapered frame smears a strong low tone across the
+    /// upper band the estimate compares it with.
+    pub stft_window: WindowFunction,
+
+    /// Fraction of each STFT frame shared with the next (default: 0.5), in
+    /// 0.0 - 0.9. The hop is the frame length times `1 - stft_overlap`; more
+    /// overlap averages more frames per buffer at proportionally more FFTs.
+    pub stft_overlap: f32,
+
+    /// Length of each `audio_quality` STFT frame, in samples (default: 512).
+    /// Longer frames resolve frequency more finely at the cost of time
+    /// resolution and work per frame; no power of two is needed. Must be
+    /// even, fine enough for bins at most 300 Hz wide at `sample_rate`, and
+    /// no longer than one second.
+    pub stft_fft_size: u32,
+
+    /// Longest payload, in bytes, the streaming decoder assembles after a
+    /// sync lock (default: `None`, bounded only by the 10 s stream window).
+    /// Once the audio since the preamble exceeds one repetition of a payload
+    /// this long, it is decoded immediately and the assembly is reset, so a
+    /// stream that locks but never completes cannot hold the buffer. Must be
+    /// at least 1 when set; a v3 payload (ID and CRC) is 6 bytes.
+    pub max_payload_bytes: Option<u32>,
+
+    /// Detection results the listener keeps, keyed by a hash of the buffer,
+    /// to answer a repeated identical buffer without decoding it again
+    /// (default: 0, caching disabled). Least recently used results are
+    /// evicted first; the cache is dropped whenever the configuration changes.
+    pub result_cache_capacity: u32,
+
+    /// Rate the caller's audio is captured at, when it differs from
+    /// `sample_rate` (default: `None`, already at `sample_rate`). Buffers
+    /// passed to `process_buffer`, `process_samples`, `process_interleaved`
+    /// and `push_samples` are then resampled to `sample_rate` before
+    /// detection, so a 44.1 or 48 kHz microphone can be fed as is.
+    pub input_sample_rate: Option<u32>,
+
+    /// Skip streamed windows without the sync chirp (default: false). Before
+    /// decoding a window, `push_samples` checks the part the detector
+    /// searches for the sync preamble with a handful of narrowband filters;
+    /// a window where the chirp is absent could not decode, so it is
+    /// answered "not detected" without running the detector or the quality
+    /// stage. Cuts the idle CPU of an always-on listener to a fraction.
+    pub pilot_gate: bool,
+
+    /// Channels interleaved in the buffers passed to `process_buffer`,
+    /// `process_samples` and `process_samples_i16` (default: 1, mono).
+    /// Multi-channel buffers are reduced per `downmix_mode`, as
+    /// `process_interleaved` does. Streamed samples are always mono.
+    pub channels: u32,
+
+    /// Encoding of the bytes passed to `process_buffer` (default: `None`,
+    /// 16-bit signed little-endian). Lets 24-bit, 32-bit, float, unsigned
+    /// or big-endian platform buffers, interleaved or planar, be passed as
+    /// they come.
+    pub pcm_format: Option<PcmFormat>,
+
+    /// Collapse repeated detections of one watermark (default: 0, every
+    /// detection is reported). A detection whose payload hash was detected
+    /// less than this many seconds earlier is not reported again, so a
+    /// watermarked stream that keeps playing fires `on_watermark_detected`
+    /// once rather than on every window. Only reported detections are
+    /// recorded in the detection store.
+    pub duplicate_window_secs: u32,
+
+    /// While `duplicate_window_secs` is collapsing a watermark's repeats,
+    /// report it through `on_watermark_still_present` every this many
+    /// seconds (default: 0, never)
+    pub still_present_interval_secs: u32,
 }
 
 impl Default for SonicConfig {
@@ -129,18 +561,149 @@ impl Default for SonicConfig {
             sample_rate: DEFAULT_SAMPLE_RATE,
             frame_size_ms: DEFAULT_FRAME_SIZE_MS,
             detection_threshold: DEFAULT_THRESHOLD,
+            adaptive_threshold_sigmas: None,
+            min_detection_frames: 1,
             spreading_factor: DEFAULT_SPREADING_FACTOR,
             enable_chirp_sync: true,
+            max_clipping_ratio: DEFAULT_MAX_CLIPPING_RATIO,
+            min_signal_variance: DEFAULT_MIN_SIGNAL_VARIANCE,
+            max_level_callback_hz: 0,
+            min_path_prominence: DEFAULT_MIN_PATH_PROMINENCE,
+            collect_config_snapshot: false,
+            level_floor_db: DEFAULT_LEVEL_FLOOR_DB,
+            signer_confirmation_threshold: DEFAULT_SIGNER_CONFIRMATION_THRESHOLD,
+            downmix_mode: DownmixMode::Average,
+            correlation_search_step: None,
+            exclude_dc_and_nyquist: false,
+            stft_window: WindowFunction::Hann,
+            stft_overlap: DEFAULT_STFT_OVERLAP,
+            stft_fft_size: DEFAULT_STFT_FFT_SIZE,
+            max_payload_bytes: None,
+            result_cache_capacity: 0,
+            input_sample_rate: None,
+            pilot_gate: false,
+            channels: 1,
+            pcm_format: None,
+            duplicate_window_secs: 0,
+            still_present_interval_secs: 0,
         }
     }
 }
 
+/// Listening scenarios `SonicConfig::preset` has tuned settings for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigPreset {
+    /// Voice through a phone mic or a call: 16 kHz, which keeps the three
+    /// v3 layers below 6.6 kHz, with the mic's DC offset left out of the
+    /// quality estimate
+    Speech,
+    /// Music, often stereo: 44.1 kHz for all four layers, the side signal
+    /// searched too, a stricter threshold that drops decodes tonal passages
+    /// resembling the FSK tones pushed into heavy correction, and room for
+    /// loudly mastered tracks' clipping
+    Music,
+    /// Always-on monitoring of a radio or TV stream: 48 kHz, windows without
+    /// the sync chirp skipped, a station's repeats collapsed into periodic
+    /// "still present" updates, and a slow level meter
+    Broadcast,
+    /// Full-band 48 kHz capture for setups that carry the watermark high, in
+    /// the v3 8.5 - 12.5 kHz layer or the legacy 17.5 - 19.5 kHz band: the
+    /// high layer is kept and nothing above it is lost to resampling
+    Ultrasonic,
+    /// Battery first: 16 kHz, windows without the sync chirp skipped, a
+    /// single-pass quality estimate, repeats collapsed and the level meter
+    /// slowed, at some cost in sensitivity to the weakest watermarks
+    LowPower,
+}
+
+/// How readily a listener reports a watermark, trading false alarms
+/// against missed watermarks; `SonicConfig::with_sensitivity` sets the
+/// detection settings for each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensitivityProfile {
+    /// Fewer false alarms: a higher threshold, held to the room's noise
+    /// floor, two detections in a row before a watermark is reported, and
+    /// more evidence before a signer is confirmed
+    Strict,
+    /// The defaults
+    Balanced,
+    /// Fewer misses: a lower threshold, taking decodes that needed most of
+    /// their codewords corrected, and signers confirmed on less evidence,
+    /// for faint or distant sources
+    Aggressive,
+}
+
 impl SonicConfig {
+    /// Settings tuned for `preset`, on top of the defaults; adjust further
+    /// fields as needed.
+    pub fn preset(preset: ConfigPreset) -> Self {
+        let defaults = Self::default();
+        match preset {
+            ConfigPreset::Speech => Self {
+                sample_rate: 16_000,
+                exclude_dc_and_nyquist: true,
+                ..defaults
+            },
+            ConfigPreset::Music => Self {
+                sample_rate: 44_100,
+                detection_threshold: 0.6,
+                max_clipping_ratio: 0.1,
+                downmix_mode: DownmixMode::MidSide,
+                ..defaults
+            },
+            ConfigPreset::Broadcast => Self {
+                sample_rate: 48_000,
+                pilot_gate: true,
+                duplicate_window_secs: 30,
+                still_present_interval_secs: 60,
+                max_level_callback_hz: 10,
+                ..defaults
+            },
+            ConfigPreset::Ultrasonic => Self {
+                sample_rate: 48_000,
+                exclude_dc_and_nyquist: true,
+                ..defaults
+            },
+            ConfigPreset::LowPower => Self {
+                sample_rate: 16_000,
+                pilot_gate: true,
+                stft_overlap: 0.0,
+                duplicate_window_secs: 30,
+                max_level_callback_hz: 4,
+                ..defaults
+            },
+        }
+    }
+
+    /// These settings with the threshold, noise floor, minimum run and
+    /// signer confirmation of `profile`; nothing else changes, so it
+    /// combines with any preset.
+    pub fn with_sensitivity(self, profile: SensitivityProfile) -> Self {
+        let (detection_threshold, adaptive_threshold_sigmas, min_detection_frames, signer_confirmation_threshold) =
+            match profile {
+                SensitivityProfile::Strict => (0.7, Some(6.0), 2, 3.0),
+                SensitivityProfile::Balanced => (DEFAULT_THRESHOLD, None, 1, DEFAULT_SIGNER_CONFIRMATION_THRESHOLD),
+                SensitivityProfile::Aggressive => (0.3, None, 1, 1.0),
+            };
+        Self {
+            detection_threshold,
+            adaptive_threshold_sigmas,
+            min_detection_frames,
+            signer_confirmation_threshold,
+            ..self
+        }
+    }
+
     /// Validate the configuration
+    // `is_multiple_of` needs Rust 1.87, which the crate does not require.
+    #[allow(clippy::manual_is_multiple_of)]
     fn validate(&self) -> Result<(), SonicError> {
         if self.sample_rate < 8000 || self.sample_rate > 96000 {
             return Err(SonicError::InvalidSampleRate(self.sample_rate));
         }
+        if let Some(rate) = self.input_sample_rate.filter(|r| !(8000..=96000).contains(r)) {
+            return Err(SonicError::InvalidSampleRate(rate));
+        }
         if self.frame_size_ms < 10 || self.frame_size_ms > 1000 {
             return Err(SonicError::InvalidConfig(
                 "frame_size_ms must be between 10 and 1000".into(),
@@ -151,6 +714,76 @@ impl SonicConfig {
                 "detection_threshold must be between 0.0 and 1.0".into(),
             ));
         }
+        if self.adaptive_threshold_sigmas.is_some_and(|k| !(k > 0.0 && k <= 10.0)) {
+            return Err(SonicError::InvalidConfig(
+                "adaptive_threshold_sigmas must be in (0.0, 10.0]".into(),
+            ));
+        }
+        if self.min_detection_frames == 0 {
+            return Err(SonicError::InvalidConfig(
+                "min_detection_frames must be at least 1".into(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.max_clipping_ratio) {
+            return Err(SonicError::InvalidConfig(
+                "max_clipping_ratio must be between 0.0 and 1.0".into(),
+            ));
+        }
+        if self.min_signal_variance.is_nan() || self.min_signal_variance < 0.0 {
+            return Err(SonicError::InvalidConfig(
+                "min_signal_variance must be non-negative".into(),
+            ));
+        }
+        if !(self.min_path_prominence > 0.0 && self.min_path_prominence <= 1.0) {
+            return Err(SonicError::InvalidConfig(
+                "min_path_prominence must be in (0.0, 1.0]".into(),
+            ));
+        }
+        if !(self.level_floor_db.is_finite() && self.level_floor_db <= 0.0) {
+            return Err(SonicError::InvalidConfig(
+                "level_floor_db must be a finite dBFS value <= 0.0".into(),
+            ));
+        }
+        if !(self.signer_confirmation_threshold.is_finite() && self.signer_confirmation_threshold > 0.0) {
+            return Err(SonicError::InvalidConfig(
+                "signer_confirmation_threshold must be positive".into(),
+            ));
+        }
+        if self.correlation_search_step == Some(0) {
+            return Err(SonicError::InvalidConfig(
+                "correlation_search_step must be at least 1 sample".into(),
+            ));
+        }
+        if !(0.0..=MAX_STFT_OVERLAP).contains(&self.stft_overlap) {
+            return Err(SonicError::InvalidConfig(format!(
+                "stft_overlap must be between 0.0 and {MAX_STFT_OVERLAP}"
+            )));
+        }
+        let min_fft_size = self.sample_rate.div_ceil(MAX_STFT_BIN_HZ).max(16);
+        if self.stft_fft_size % 2 != 0 || !(min_fft_size..=self.sample_rate).contains(&self.stft_fft_size) {
+            return Err(SonicError::InvalidConfig(format!(
+                "stft_fft_size must be even and between {min_fft_size} and {} at {} Hz",
+                self.sample_rate, self.sample_rate
+            )));
+        }
+        if self.max_payload_bytes == Some(0) {
+            return Err(SonicError::InvalidConfig(
+                "max_payload_bytes must be at least 1".into(),
+            ));
+        }
+        if self.channels == 0 {
+            return Err(SonicError::InvalidConfig("channels must be at least 1".into()));
+        }
+        if let Some(format) = self.pcm_format {
+            if !matches!(format.bits_per_sample, 16 | 24 | 32) {
+                return Err(SonicError::InvalidConfig(
+                    "pcm_format bits_per_sample must be 16, 24 or 32".into(),
+                ));
+            }
+            if format.encoding == SampleEncoding::Float && format.bits_per_sample != 32 {
+                return Err(SonicError::InvalidConfig("float PCM must be 32-bit".into()));
+            }
+        }
         Ok(())
     }
 }
@@ -159,13 +792,28 @@ impl SonicConfig {
 // Watermark Result
 // =============================================================================
 
+/// Why a buffer was rejected without (or despite) a detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// Digital silence: no signal energy to analyze
+    InsufficientSignal,
+    /// Constant (DC) buffer: energy but no variation
+    ConstantSignal,
+    /// Too many samples at full scale to trust a detection
+    Clipped,
+    /// A streamed payload outgrew `max_payload_bytes` after its sync lock
+    /// without decoding
+    PayloadOverflow,
+}
+
 /// Result of watermark detection
 #[derive(Debug, Clone, Default)]
 pub struct WatermarkResult {
     /// Whether a watermark was detected
     pub detected: bool,
     
-    /// Detection confidence (0.0 - 1.0)
+    /// Detection confidence (0.0 - 1.0), lower the more codewords the
+    /// decode had to correct
     pub confidence: f32,
     
     /// Signer's DID if extracted
@@ -179,12 +827,62 @@ pub struct WatermarkResult {
     
     /// Covenant data as JSON string
     pub covenant_json: Option<String>,
+
+    /// Signature over the payload's signing input (see
+    /// `SignatureVerifier::verify_watermark_payload`), when the payload is
+    /// signed
+    pub signature: Option<Vec<u8>>,
     
     /// Estimated audio quality (0.0 - 1.0)
     pub audio_quality: f32,
     
     /// Detection method used
     pub detection_method: String,
+
+    /// Whether a payload frame was recovered and passed its CRC-16. Stays set
+    /// when a guard or the threshold later suppresses the detection, so
+    /// "intact payload, rejected" is distinguishable from "nothing decoded"
+    pub payload_integrity: bool,
+
+    /// Hamming codewords the payload decode had to correct: 0 on a clean
+    /// channel, rising as noise or compression erode the margin
+    pub corrected_symbols: u32,
+
+    /// True when an FFT-dependent stage could not run (e.g. an unsupported
+    /// FFT size) and the result was produced without it
+    pub degraded: bool,
+
+    /// Set when the buffer was rejected by a signal guard
+    pub rejection_reason: Option<RejectionReason>,
+
+    /// Configuration that produced this result, when
+    /// `SonicConfig::collect_config_snapshot` is set
+    pub config_snapshot: Option<SonicConfig>,
+
+    /// Input channel the result was detected on, when a multi-channel
+    /// buffer was reduced to one channel (`Left`, `Right`, `BestChannel`)
+    pub channel_index: Option<u32>,
+
+    /// The buffer's spectrum looks like audio captured at a higher rate than
+    /// `SonicConfig::sample_rate`: its spectral rolloff sits below every
+    /// watermark band. Detection cannot succeed until the rate is corrected
+    pub sample_rate_mismatch: bool,
+
+    /// Algorithm `signature` was made with; Ed25519 unless the payload says
+    /// otherwise
+    pub signature_algorithm: SignatureAlgorithm,
+
+    /// Listening session the result was produced in (see `start_listening`);
+    /// `None` outside a session
+    pub session_id: Option<String>,
+
+    /// Where the buffer or streamed window the result comes from starts,
+    /// in ms of audio since the session started; `None` outside a session
+    pub stream_offset_ms: Option<u64>,
+
+    /// Index of the buffer or streamed window the result comes from among
+    /// those processed in the session, from 0; `None` outside a session
+    pub frame_index: Option<u64>,
 }
 
 impl WatermarkResult {
@@ -205,48 +903,170 @@ impl WatermarkResult {
     /// = SHA-256 of that ID), which is the server lookup key. The signer DID,
     /// signing timestamp, and covenant are resolved server-side from that hash,
     /// so they remain `None` here.
+    ///
+    /// The detector gives every CRC-valid decode the same confidence; it is
+    /// scaled by the share of codewords that decoded without correction, so a
+    /// decode that used up its error margin ranks below a clean one.
     fn from_dsp(d: dsp::DetectResult) -> Self {
+        let corrected = d.corrected_symbols.min(CODEWORDS_PER_FRAME) as f32;
         Self {
             detected: d.detected,
-            confidence: d.confidence,
+            confidence: d.confidence * (1.0 - corrected / CODEWORDS_PER_FRAME as f32),
             signer_did: None,
             timestamp: None,
+            payload_integrity: d.payload_hash.is_some(),
             payload_hash: d.payload_hash,
             covenant_json: None,
+            signature: None,
             audio_quality: d.audio_quality,
             detection_method: d.detection_method,
+            corrected_symbols: d.corrected_symbols,
+            degraded: false,
+            rejection_reason: None,
+            config_snapshot: None,
+            channel_index: None,
+            sample_rate_mismatch: false,
+            signature_algorithm: SignatureAlgorithm::Ed25519,
+            session_id: None,
+            stream_offset_ms: None,
+            frame_index: None,
+        }
+    }
+
+    /// Bytes the signer signs: the canonical (RFC 8785) JSON of the payload's
+    /// signer, timestamp, hash and covenant.
+    fn signing_input(&self) -> Result<Vec<u8>, SonicError> {
+        signing_input(
+            self.signer_did.as_deref(),
+            self.timestamp,
+            self.payload_hash.as_deref(),
+            self.covenant_json.as_deref(),
+        )
+    }
+
+    /// Create a "not detected" result for a buffer rejected before detection
+    fn rejected(reason: RejectionReason) -> Self {
+        Self {
+            audio_quality: 0.0,
+            rejection_reason: Some(reason),
+            ..Self::not_detected()
+        }
+    }
+
+    /// Suppress a detection on severely clipped audio.
+    ///
+    /// Once more than `max_ratio` of the buffer sits at full scale, the
+    /// confidence is down-weighted by the clipped fraction and the result is
+    /// reported as not detected (method `"clipped"`).
+    fn apply_clipping_guard(&mut self, ratio: f32, max_ratio: f32) {
+        if ratio <= max_ratio {
+            return;
+        }
+        self.confidence *= 1.0 - ratio;
+        self.rejection_reason = Some(RejectionReason::Clipped);
+        if self.detected {
+            self.detected = false;
+            self.payload_hash = None;
+            self.detection_method = "clipped".into();
+        }
+    }
+
+    /// Drop a detection whose confidence falls below the configured threshold.
+    fn apply_threshold(&mut self, threshold: f32) {
+        if self.detected && self.confidence < threshold {
+            self.detected = false;
+            self.payload_hash = None;
         }
     }
 }
 
+/// Confidence accumulated for one signer over a listening session
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignerEvidence {
+    /// The signer: its DID when the result carries one, otherwise the payload
+    /// hash that resolves to it (v3 watermarks carry only the hash)
+    pub signer: String,
+    /// Sum of the confidences of every frame decoded for this signer
+    pub accumulated_confidence: f32,
+    /// Number of frames that contributed
+    pub frames: u32,
+    /// Whether the total has reached `signer_confirmation_threshold`
+    pub confirmed: bool,
+}
+
+/// Detector work counters and timings for a listener
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListenerStats {
+    /// Buffers and streamed windows processed, including those skipped by
+    /// `pilot_gate`
+    pub buffers_processed: u64,
+    /// Samples in those buffers, at the detection rate
+    pub samples_processed: u64,
+    /// `on_watermark_detected` events emitted (repeats collapsed by
+    /// `duplicate_window_secs` are not counted)
+    pub detections_emitted: u64,
+    /// Mean time to process a buffer, over the most recent 256, in ms
+    pub mean_processing_ms: f32,
+    /// 95th percentile of the same processing times, in ms
+    pub p95_processing_ms: f32,
+    /// Streamed samples buffered towards the next analysis window
+    pub pending_samples: u64,
+    /// Buffers decoded by the DSP detector
+    pub dsp_calls: u64,
+    /// Buffers answered from the result cache, skipping the detector
+    pub cache_hits: u64,
+    /// Streamed windows skipped by `pilot_gate` for lacking the sync chirp
+    pub pilot_skips: u64,
+    /// `on_audio_level_changed` events dropped because the callbacks fell
+    /// behind and the event queue was full
+    pub dropped_level_events: u64,
+    /// Sync correlation detections must currently reach under
+    /// `adaptive_threshold_sigmas` (`None` when off or before the noise
+    /// floor is measured)
+    pub adaptive_threshold: Option<f32>,
+}
+
 // =============================================================================
 // Listener State
 // =============================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ListenerState {
+    #[default]
     Idle,
     Listening,
+    /// Streaming: a sync preamble has arrived and the listener is gathering
+    /// the rest of its window before decoding
+    Acquiring,
     Processing,
+    /// Paused with `pause`: streamed samples are ignored, and the stream
+    /// buffer, sync lock and callback are kept for `resume`
+    Paused,
     Error,
-}
-
-impl Default for ListenerState {
-    fn default() -> Self {
-        Self::Idle
-    }
+    /// Shut down with `shutdown` (or dropped); terminal
+    Closed,
 }
 
 // =============================================================================
 // Callback Interface
 // =============================================================================
 
+/// Progress reports from a long-running scan
+pub trait ProgressCallback: Send + Sync {
+    /// Called as the scan advances, with the share of the recording scanned
+    /// (0.0 - 100.0), the watermarks found so far and the position reached,
+    /// in ms from the start. Return false to cancel the scan.
+    fn on_progress(&self, percent_done: f32, detections: u32, position_ms: u64) -> bool;
+}
+
 /// Callback interface for watermark detection events
 pub trait WatermarkCallback: Send + Sync {
     /// Called when a watermark is detected
     fn on_watermark_detected(&self, result: WatermarkResult);
     
-    /// Called when audio level changes (for UI meter)
+    /// Called when audio level changes (for UI meter). `level_db` is the
+    /// buffer's RMS in dBFS: 0.0 is a full-scale square wave, and the value
+    /// never drops below the configured `level_floor_db`.
     fn on_audio_level_changed(&self, level_db: f32);
     
     /// Called on error
@@ -254,6 +1074,72 @@ pub trait WatermarkCallback: Send + Sync {
     
     /// Called when listener state changes
     fn on_state_changed(&self, state: ListenerState);
+
+    /// Called while a watermark's repeated detections are collapsed by
+    /// `duplicate_window_secs`, every `still_present_interval_secs`, with
+    /// the latest detection and how long the watermark has been detected
+    fn on_watermark_still_present(&self, result: WatermarkResult, present_for_ms: u64) {
+        let _ = (result, present_for_ms);
+    }
+
+    /// Called once per signer when its accumulated confidence first reaches
+    /// `signer_confirmation_threshold` (see `SignerEvidence::signer`)
+    fn on_signer_confirmed(&self, signer: String, accumulated_confidence: f32) {
+        let _ = (signer, accumulated_confidence);
+    }
+
+    /// Called when a setter or `update_config` changes the listener's
+    /// configuration, with the settings before and after
+    fn on_config_changed(&self, old: SonicConfig, new: SonicConfig) {
+        let _ = (old, new);
+    }
+}
+
+/// The `WatermarkCallback` methods, as event kinds an `EventFilter` selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListenerEvent {
+    WatermarkDetected,
+    WatermarkStillPresent,
+    AudioLevel,
+    Error,
+    StateChanged,
+    SignerConfirmed,
+    ConfigChanged,
+}
+
+/// Which events a subscriber receives (`SonicListener::add_callback_with_filter`).
+/// The default passes everything
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EventFilter {
+    /// Detections below this confidence are not delivered
+    pub min_confidence: f32,
+    /// Event kinds delivered; empty for all
+    pub events: Vec<ListenerEvent>,
+    /// Signers whose detections and confirmations are delivered; empty for
+    /// any. A confirmation by payload hash (unresolved signer) matches none
+    pub signer_dids: Vec<String>,
+}
+
+impl EventFilter {
+    fn wants(&self, event: ListenerEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    fn accepts_signer(&self, signer: Option<&str>) -> bool {
+        self.signer_dids.is_empty() || signer.is_some_and(|s| self.signer_dids.iter().any(|d| d == s))
+    }
+
+    /// Whether a `WatermarkDetected` or `WatermarkStillPresent` event for
+    /// `result` passes.
+    fn accepts_detection(&self, event: ListenerEvent, result: &WatermarkResult) -> bool {
+        self.wants(event)
+            && result.confidence >= self.min_confidence
+            && self.accepts_signer(result.signer_did.as_deref())
+    }
+
+    fn accepts_signer_confirmation(&self, signer: &str) -> bool {
+        self.wants(ListenerEvent::SignerConfirmed) && self.accepts_signer(Some(signer))
+    }
 }
 
 // =============================================================================
@@ -265,497 +1151,4170 @@ pub trait WatermarkCallback: Send + Sync {
 fn samples_to_pcm_le16(samples: &[f32]) -> Vec<u8> {
     let mut pcm = Vec::with_capacity(samples.len() * 2);
     for &s in samples {
-        let clamped = s.max(-1.0).min(1.0);
+        let clamped = s.clamp(-1.0, 1.0);
         let i16_val = (clamped * 32767.0) as i16;
         pcm.extend_from_slice(&i16_val.to_le_bytes());
     }
     pcm
 }
 
+/// Convert 16-bit LE PCM bytes to float samples (mono, -1.0..1.0).
+fn pcm_le16_to_samples(pcm_data: &[u8]) -> Vec<f32> {
+    pcm_data
+        .chunks_exact(2)
+        .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0)
+        .collect()
+}
+
+/// Decode PCM bytes laid out per `format` to float samples (-1.0..1.0),
+/// in the order they appear in the buffer.
+#[allow(clippy::manual_is_multiple_of)]
+fn decode_pcm(pcm_data: &[u8], format: &PcmFormat) -> Result<Vec<f32>, SonicError> {
+    let width = format.bits_per_sample as usize / 8;
+    if pcm_data.len() % width != 0 {
+        return Err(SonicError::InvalidConfig(format!(
+            "PCM buffer length must be a multiple of {width} bytes"
+        )));
+    }
+    let scale = 1.0 / (1u64 << (format.bits_per_sample - 1)) as f32;
+    Ok(pcm_data
+        .chunks_exact(width)
+        .map(|bytes| {
+            // Widen to a big-endian u32, most significant byte first.
+            let mut word = [0u8; 4];
+            for (i, &b) in bytes.iter().enumerate() {
+                word[if format.big_endian { i } else { width - 1 - i }] = b;
+            }
+            let raw = u32::from_be_bytes(word);
+            match format.encoding {
+                SampleEncoding::Float => f32::from_bits(raw),
+                SampleEncoding::SignedInt => (raw as i32 >> (32 - format.bits_per_sample)) as f32 * scale,
+                SampleEncoding::UnsignedInt => {
+                    ((raw >> (32 - format.bits_per_sample)) as f32 * scale) - 1.0
+                }
+            }
+        })
+        .collect())
+}
+
+/// RMS level of a buffer in dBFS, clamped to `floor_db`.
+fn level_db(samples: &[f32], floor_db: f32) -> f32 {
+    let sumsq = kernels::sum_squares(samples);
+    let rms = if samples.is_empty() {
+        0.0
+    } else {
+        (sumsq / samples.len() as f64).sqrt() as f32
+    };
+    (20.0 * rms.max(1e-10).log10()).max(floor_db)
+}
+
+/// Classify a degenerate buffer: one whose variance is below `floor`.
+///
+/// Returns `InsufficientSignal` for silence and `ConstantSignal` for a DC
+/// buffer, or `None` when the buffer carries a usable signal.
+fn degenerate_signal(samples: &[f32], floor: f32) -> Option<RejectionReason> {
+    if samples.is_empty() {
+        return Some(RejectionReason::InsufficientSignal);
+    }
+    let n = samples.len() as f64;
+    let mean = kernels::sum(samples) / n;
+    let variance = kernels::centered_sum_squares(samples, mean) / n;
+    if variance >= floor as f64 {
+        None
+    } else if mean * mean < floor as f64 {
+        Some(RejectionReason::InsufficientSignal)
+    } else {
+        Some(RejectionReason::ConstantSignal)
+    }
+}
+
+/// Split interleaved audio with `channels` channels into the signal to
+/// detect on and, for `MidSide`, the side signal to try when that fails.
+/// `BestChannel` yields the average here; its channels come from
+/// `split_channels`.
+fn downmix(samples: &[f32], channels: usize, mode: DownmixMode) -> (Vec<f32>, Option<Vec<f32>>) {
+    let frames = samples.chunks_exact(channels);
+    let channel = |index: usize| frames.clone().map(|f| f[index.min(channels - 1)]).collect();
+    let average = || frames.clone().map(|f| f.iter().sum::<f32>() / channels as f32).collect();
+    match mode {
+        DownmixMode::Average | DownmixMode::BestChannel => (average(), None),
+        DownmixMode::Left => (channel(0), None),
+        DownmixMode::Right => (channel(1), None),
+        DownmixMode::MidSide if channels >= 2 => {
+            (average(), Some(frames.clone().map(|f| (f[0] - f[1]) / 2.0).collect()))
+        }
+        DownmixMode::MidSide => (average(), None),
+    }
+}
+
+/// De-interleave audio with `channels` channels, one signal per channel.
+fn split_channels(samples: &[f32], channels: usize) -> Vec<Vec<f32>> {
+    (0..channels)
+        .map(|c| samples.chunks_exact(channels).map(|f| f[c]).collect())
+        .collect()
+}
+
+/// What `process_frame` detects on besides, or instead of, its mono signal
+#[derive(Clone, Copy)]
+enum ChannelSource<'a> {
+    /// The mono signal is this input channel
+    Channel(u32),
+    /// The side signal, tried if the mono signal yields nothing
+    Side(&'a [f32]),
+    /// Every input channel, each detected on in place of the mono signal;
+    /// the most confident result wins
+    Channels(&'a [Vec<f32>]),
+}
+
+/// Peak normalized correlation of a buffer with the sync chirp, or `None`
+/// for a buffer shorter than the chirp.
+fn sync_correlation(samples: &[f32], correlator: &spectrum::ReferenceCorrelator) -> Option<f32> {
+    let ncc = correlator.correlate_normalized(samples);
+    ncc.into_iter().max_by(f64::total_cmp).map(|peak| peak as f32)
+}
+
+/// Fraction of samples at digital full scale (hard clipping).
+fn clipping_ratio(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    clipped as f32 / samples.len() as f32
+}
+
 // =============================================================================
 // Sonic Listener
 // =============================================================================
 
+/// An event subscriber: its subscription handle, callback and filter
+type Subscriber = (u64, Arc<dyn WatermarkCallback>, EventFilter);
+
 /// Main listener object exposed via FFI
 pub struct SonicListener {
     config: RwLock<SonicConfig>,
     state: RwLock<ListenerState>,
     is_running: AtomicBool,
-    callback: RwLock<Option<Arc<dyn WatermarkCallback>>>,
+    /// Set by `shutdown`, after which the listener refuses work
+    closed: AtomicBool,
+    /// Event subscribers with their subscription handles and filters, in
+    /// subscription order
+    callbacks: RwLock<Vec<Subscriber>>,
+    /// Handle of the callback `start_listening` subscribed
+    listening_subscription: Mutex<Option<u64>>,
+    next_subscription: AtomicU64,
+    /// Queue and thread that deliver events to the subscribers
+    events: dispatch::Dispatcher,
+    quality_estimator: RwLock<Box<dyn QualityEstimator>>,
+    /// Streamed samples not yet processed into a full analysis window
+    pending: Mutex<Vec<f32>>,
+    /// When `on_audio_level_changed` last fired, for `max_level_callback_hz`
+    last_level_emit: Mutex<Option<Instant>>,
+    /// FFT plans reused across buffers (see `warm_up`)
+    fft_plans: spectrum::FftPlans,
+    /// Quality STFT buffers reused across buffers (see `warm_up`)
+    quality_scratch: Mutex<spectrum::StftScratch>,
+    /// Per-signer confidence since the last `reset_signer_evidence`, in
+    /// first-seen order
+    signer_evidence: Mutex<Vec<SignerEvidence>>,
+    /// Offset in `pending` of the sync preamble while `Acquiring`
+    sync_lock: Mutex<Option<usize>>,
+    /// The sync chirp's correlator, with the sample rate it was built for
+    sync_correlator: Mutex<Option<(u32, Arc<spectrum::ReferenceCorrelator>)>>,
+    /// While paused, the state `resume` returns to
+    paused_from: Mutex<Option<ListenerState>>,
+    /// Converts pushed samples from `input_sample_rate`, carrying the filter
+    /// state across pushes
+    stream_resampler: Mutex<Option<resample::StreamResampler>>,
+    /// Recent detection results by buffer hash (`result_cache_capacity`)
+    result_cache: Mutex<cache::ResultCache>,
+    /// Buffers decoded by the DSP detector
+    dsp_calls: AtomicU64,
+    /// Buffers answered from `result_cache` instead
+    cache_hits: AtomicU64,
+    /// Streamed windows skipped by `pilot_gate`
+    pilot_skips: AtomicU64,
+    /// Buffers and samples processed, and detections emitted
+    buffers_processed: AtomicU64,
+    samples_processed: AtomicU64,
+    detections_emitted: AtomicU64,
+    /// How long recent buffers took to process
+    processing_times: Mutex<timing::ProcessingTimes>,
+    /// Watermarks detected recently, for `duplicate_window_secs`
+    duplicates: Mutex<dedup::Deduplicator>,
+    /// Sync correlation of recent buffers without a detection, for
+    /// `adaptive_threshold_sigmas`
+    noise_floor: Mutex<noise_floor::NoiseFloor>,
+    /// The payload hash detected in the most recent buffers and how many in
+    /// a row, for `min_detection_frames`
+    detection_streak: Mutex<Option<(String, u32)>>,
+    /// The session `start_listening` opened, until `stop_listening`
+    session: Mutex<Option<session::Session>>,
+    /// Where emitted detections are recorded (`set_detection_store`)
+    detection_store: RwLock<Option<Arc<dyn DetectionStore>>>,
+    /// Stop flag of the running `start_capture` thread
+    #[cfg(feature = "capture")]
+    capture: Mutex<Option<Arc<AtomicBool>>>,
 }
 
 impl SonicListener {
     /// Create a new SonicListener with the given configuration
     pub fn new(config: SonicConfig) -> Result<Self, SonicError> {
+        Self::new_with_quality_estimator(config, Box::new(SpectralBalanceEstimator))
+    }
+
+    /// Create a listener that scores `audio_quality` with a custom estimator
+    pub fn new_with_quality_estimator(
+        config: SonicConfig,
+        estimator: Box<dyn QualityEstimator>,
+    ) -> Result<Self, SonicError> {
         config.validate()?;
 
         Ok(Self {
             config: RwLock::new(config),
             state: RwLock::new(ListenerState::Idle),
             is_running: AtomicBool::new(false),
-            callback: RwLock::new(None),
+            closed: AtomicBool::new(false),
+            callbacks: RwLock::new(Vec::new()),
+            listening_subscription: Mutex::new(None),
+            next_subscription: AtomicU64::new(1),
+            events: dispatch::Dispatcher::default(),
+            quality_estimator: RwLock::new(estimator),
+            pending: Mutex::new(Vec::new()),
+            last_level_emit: Mutex::new(None),
+            fft_plans: spectrum::FftPlans::default(),
+            quality_scratch: Mutex::new(spectrum::StftScratch::default()),
+            signer_evidence: Mutex::new(Vec::new()),
+            sync_lock: Mutex::new(None),
+            sync_correlator: Mutex::new(None),
+            paused_from: Mutex::new(None),
+            stream_resampler: Mutex::new(None),
+            result_cache: Mutex::new(cache::ResultCache::default()),
+            dsp_calls: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            pilot_skips: AtomicU64::new(0),
+            buffers_processed: AtomicU64::new(0),
+            samples_processed: AtomicU64::new(0),
+            detections_emitted: AtomicU64::new(0),
+            processing_times: Mutex::new(timing::ProcessingTimes::default()),
+            duplicates: Mutex::new(dedup::Deduplicator::default()),
+            noise_floor: Mutex::new(noise_floor::NoiseFloor::default()),
+            detection_streak: Mutex::new(None),
+            session: Mutex::new(None),
+            detection_store: RwLock::new(None),
+            #[cfg(feature = "capture")]
+            capture: Mutex::new(None),
         })
     }
 
-    /// Start listening for watermarks
+    /// Start listening for watermarks, opening a session; returns its ID,
+    /// which every result carries until `stop_listening`
     pub fn start_listening(
         self: &Arc<Self>,
         callback: Box<dyn WatermarkCallback>,
-    ) -> Result<(), SonicError> {
+    ) -> Result<String, SonicError> {
+        self.ensure_open()?;
         if self.is_running.load(Ordering::SeqCst) {
             return Err(SonicError::ListenerAlreadyRunning);
         }
 
-        // Foreign callback arrives as Box (uniffi 0.28 callback interface); keep as Arc.
-        let callback: Arc<dyn WatermarkCallback> = Arc::from(callback);
-        *self.callback.write() = Some(callback.clone());
+        // Replaces the callback of an earlier start; other subscribers stay.
+        let subscription = self.add_callback(callback);
+        if let Some(previous) = self.listening_subscription.lock().replace(subscription) {
+            self.remove_callback(previous);
+        }
         
         // Update state
+        let session = session::Session::start();
+        let session_id = session.id().to_owned();
+        *self.session.lock() = Some(session);
         self.is_running.store(true, Ordering::SeqCst);
         *self.state.write() = ListenerState::Listening;
         
         // Notify state change
-        callback.on_state_changed(ListenerState::Listening);
+        self.emit(dispatch::Event::StateChanged(ListenerState::Listening));
         
-        // Note: In a real implementation, we would start an audio capture thread here
-        // For mobile, the audio capture is typically handled by the platform (Swift/Kotlin)
-        // and buffers are passed to process_buffer/process_samples
+        // Audio capture is handled by the platform (Swift/Kotlin), which
+        // passes buffers to process_buffer/process_samples/push_samples; on
+        // desktop, `start_capture` (feature `capture`) opens the device here.
         
-        log::info!("SonicListener started");
-        Ok(())
+        log::info!("SonicListener started (session {session_id})");
+        Ok(session_id)
     }
 
-    /// Stop listening
-    pub fn stop_listening(&self) -> Result<(), SonicError> {
-        if !self.is_running.load(Ordering::SeqCst) {
-            return Err(SonicError::ListenerNotRunning);
+    /// Start listening and capture the default input device natively
+    /// (feature `capture`, for desktop hosts with no platform audio layer).
+    ///
+    /// Buffers from the device are streamed through `push_samples` on a
+    /// capture thread, with `input_sample_rate` set to the device's rate.
+    /// `stop_listening` closes the device.
+    #[cfg(feature = "capture")]
+    pub fn start_capture(self: &Arc<Self>, callback: Box<dyn WatermarkCallback>) -> Result<String, SonicError> {
+        let session_id = self.start_listening(callback)?;
+        match capture::spawn(self.clone()) {
+            Ok(stop) => {
+                *self.capture.lock() = Some(stop);
+                Ok(session_id)
+            }
+            Err(e) => {
+                let _ = self.stop_listening();
+                Err(e)
+            }
         }
+    }
 
-        self.is_running.store(false, Ordering::SeqCst);
-        *self.state.write() = ListenerState::Idle;
-        
-        // Notify callback
-        if let Some(callback) = self.callback.read().as_ref() {
-            callback.on_state_changed(ListenerState::Idle);
-        }
-        
-        log::info!("SonicListener stopped");
-        Ok(())
+    /// Subscribe `callback` to the listener's events alongside any others
+    /// (e.g. a UI layer and an analytics layer), returning the handle
+    /// `remove_callback` takes. Subscribers are called in subscription
+    /// order; `start_listening`'s callback is one of them. A shut down
+    /// listener drops the callback at once.
+    pub fn add_callback(&self, callback: Box<dyn WatermarkCallback>) -> u64 {
+        self.add_callback_with_filter(callback, EventFilter::default())
     }
 
-    /// Process PCM audio buffer (16-bit signed, little-endian).
-    ///
-    /// Runs the real shared `vouch-sonic-dsp` v3 detector (chirp matched-filter
-    /// sync + multi-layer FSK + CRC-validated soft decode) over the buffer.
-    pub fn process_buffer(&self, pcm_data: &[u8]) -> Result<WatermarkResult, SonicError> {
-        if pcm_data.len() < MIN_SAMPLES * 2 {
-            return Err(SonicError::BufferTooShort(MIN_SAMPLES * 2));
+    /// `add_callback` for a subscriber that only receives the events
+    /// `filter` passes, e.g. a UI that wants strong detections only while a
+    /// logger subscribes to everything. Filtered events never cross the FFI
+    pub fn add_callback_with_filter(&self, callback: Box<dyn WatermarkCallback>, filter: EventFilter) -> u64 {
+        let subscription = self.next_subscription.fetch_add(1, Ordering::Relaxed);
+        if self.closed.load(Ordering::SeqCst) {
+            return subscription;
         }
+        // Foreign callback arrives as Box (uniffi 0.28 callback interface); keep as Arc.
+        self.callbacks.write().push((subscription, Arc::from(callback), filter));
+        subscription
+    }
 
-        *self.state.write() = ListenerState::Processing;
-
-        // Emit audio level for UI (RMS over the decoded samples).
-        let level_db = {
-            let mut sumsq = 0.0f64;
-            let mut count = 0usize;
-            for chunk in pcm_data.chunks_exact(2) {
-                let s = i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0;
-                sumsq += (s * s) as f64;
-                count += 1;
+    /// Replace the filter of a subscription; false if it is not subscribed.
+    pub fn set_callback_filter(&self, subscription: u64, filter: EventFilter) -> bool {
+        match self.callbacks.write().iter_mut().find(|(id, _, _)| *id == subscription) {
+            Some(entry) => {
+                entry.2 = filter;
+                true
             }
-            let rms = if count > 0 { (sumsq / count as f64).sqrt() as f32 } else { 0.0 };
-            20.0 * rms.max(1e-10).log10()
-        };
-        if let Some(callback) = self.callback.read().as_ref() {
-            callback.on_audio_level_changed(level_db);
+            None => false,
         }
+    }
 
-        let sample_rate = self.config.read().sample_rate;
-        let result = self.detect_pcm(pcm_data, sample_rate);
+    /// Unsubscribe the callback `add_callback` returned `subscription` for;
+    /// false if it is not subscribed.
+    pub fn remove_callback(&self, subscription: u64) -> bool {
+        let mut callbacks = self.callbacks.write();
+        let count = callbacks.len();
+        callbacks.retain(|(id, _, _)| *id != subscription);
+        callbacks.len() < count
+    }
 
-        if result.detected {
-            self.emit_detection(&result);
-        }
+    /// The subscribers to `event` at this moment, so a callback may
+    /// subscribe or unsubscribe from inside an event.
+    fn callbacks(&self, event: ListenerEvent) -> Vec<Arc<dyn WatermarkCallback>> {
+        self.callbacks_where(|filter| filter.wants(event))
+    }
 
-        *self.state.write() = if self.is_running.load(Ordering::SeqCst) {
-            ListenerState::Listening
-        } else {
-            ListenerState::Idle
+    /// Queue `event` for the subscribers whose filters pass it; the
+    /// dispatcher thread calls them (see `flush_events`).
+    fn emit(&self, event: dispatch::Event) {
+        let callbacks = match &event {
+            dispatch::Event::WatermarkDetected(result) | dispatch::Event::WatermarkStillPresent(result, _) => {
+                self.callbacks_where(|filter| filter.accepts_detection(event.kind(), result))
+            }
+            dispatch::Event::SignerConfirmed(signer, _) => {
+                self.callbacks_where(|filter| filter.accepts_signer_confirmation(signer))
+            }
+            event => self.callbacks(event.kind()),
         };
+        self.events.dispatch(callbacks, event);
+    }
 
-        Ok(result)
+    /// Wait until every event emitted so far has reached its subscribers.
+    /// Callbacks run on the listener's dispatcher thread, so the thread
+    /// feeding audio is never held up by them; call this where the host
+    /// needs them to have run, e.g. before tearing down its UI. Called from
+    /// a callback, it returns at once.
+    pub fn flush_events(&self) {
+        self.events.flush();
     }
 
-    /// Process float samples directly.
-    ///
-    /// Converts to 16-bit LE PCM and runs the real shared v3 detector.
-    pub fn process_samples(&self, samples: &[f32]) -> Result<WatermarkResult, SonicError> {
-        if samples.len() < MIN_SAMPLES {
-            return Err(SonicError::BufferTooShort(MIN_SAMPLES));
-        }
-
-        *self.state.write() = ListenerState::Processing;
+    /// The subscribers whose filters `accepts`.
+    fn callbacks_where(&self, accepts: impl Fn(&EventFilter) -> bool) -> Vec<Arc<dyn WatermarkCallback>> {
+        self.callbacks
+            .read()
+            .iter()
+            .filter(|(_, _, filter)| accepts(filter))
+            .map(|(_, callback, _)| callback.clone())
+            .collect()
+    }
 
-        // Calculate audio level for UI
-        let rms: f32 = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
-        let level_db = 20.0 * rms.max(1e-10).log10();
+    /// Stop listening, closing the session and summarizing it
+    pub fn stop_listening(&self) -> Result<SessionSummary, SonicError> {
+        self.ensure_open()?;
+        self.end_listening()
+    }
 
-        // Emit audio level
-        if let Some(callback) = self.callback.read().as_ref() {
-            callback.on_audio_level_changed(level_db);
+    fn end_listening(&self) -> Result<SessionSummary, SonicError> {
+        if !self.is_running.load(Ordering::SeqCst) {
+            return Err(SonicError::ListenerNotRunning);
         }
-
-        let sample_rate = self.config.read().sample_rate;
-        let pcm = samples_to_pcm_le16(samples);
-        let result = self.detect_pcm(&pcm, sample_rate);
-
-        // Emit detection if found
-        if result.detected {
-            self.emit_detection(&result);
+        #[cfg(feature = "capture")]
+        if let Some(stop) = self.capture.lock().take() {
+            stop.store(true, Ordering::SeqCst);
         }
 
-        *self.state.write() = if self.is_running.load(Ordering::SeqCst) {
-            ListenerState::Listening
-        } else {
-            ListenerState::Idle
-        };
-
-        Ok(result)
+        self.is_running.store(false, Ordering::SeqCst);
+        *self.paused_from.lock() = None;
+        self.duplicates.lock().clear();
+        self.noise_floor.lock().clear();
+        *self.detection_streak.lock() = None;
+        let summary = self.session.lock().take().map(session::Session::finish).unwrap_or_default();
+        *self.state.write() = ListenerState::Idle;
+        
+        // Notify callbacks
+        self.emit(dispatch::Event::StateChanged(ListenerState::Idle));
+        
+        log::info!("SonicListener stopped");
+        Ok(summary)
     }
 
-    /// Run the shared DSP v3 detector over 16-bit LE PCM and map to the FFI
-    /// `WatermarkResult`. A clip shorter than the DSP minimum (or any DSP-level
-    /// error) maps to a clean "not detected" result rather than an FFI error,
-    /// since real-time callers feed short rolling buffers.
-    fn detect_pcm(&self, pcm_data: &[u8], sample_rate: u32) -> WatermarkResult {
-        match dsp::detect(pcm_data, sample_rate) {
-            Ok(d) => WatermarkResult::from_dsp(d),
-            Err(_) => WatermarkResult::not_detected(),
+    /// Shut the listener down for good: stop listening (and capture),
+    /// deliver the events already emitted, and release every callback and
+    /// the detection store, breaking reference cycles through foreign
+    /// callbacks that hold the listener. It ends in `Closed`; after that,
+    /// methods that can fail return `ListenerClosed` and the others do
+    /// nothing. Shutting down twice does nothing, and dropping the listener
+    /// shuts it down.
+    ///
+    /// (Not `close`, which the Kotlin bindings already define to free the
+    /// native object.)
+    pub fn shutdown(&self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
         }
+        let _ = self.end_listening();
+        *self.state.write() = ListenerState::Closed;
+        self.emit(dispatch::Event::StateChanged(ListenerState::Closed));
+        self.events.flush();
+
+        // Dropped outside the locks, as a callback may hold the listener.
+        let callbacks = std::mem::take(&mut *self.callbacks.write());
+        let store = self.detection_store.write().take();
+        *self.listening_subscription.lock() = None;
+        self.pending.lock().clear();
+        *self.sync_lock.lock() = None;
+        drop((callbacks, store));
+        log::info!("SonicListener shut down");
     }
 
-    /// Emit watermark detected event to callback
-    fn emit_detection(&self, result: &WatermarkResult) {
-        if let Some(callback) = self.callback.read().as_ref() {
-            callback.on_watermark_detected(result.clone());
+    fn ensure_open(&self) -> Result<(), SonicError> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(SonicError::ListenerClosed);
         }
+        Ok(())
     }
 
-    /// Get current state
-    pub fn get_state(&self) -> ListenerState {
-        *self.state.read()
+    /// Pause a running listener, e.g. while the app is briefly in the
+    /// background, without stopping it: samples pushed while `Paused` are
+    /// ignored, and the streamed samples, sync lock and callback stay as
+    /// they were. Pausing a paused listener does nothing.
+    pub fn pause(&self) -> Result<(), SonicError> {
+        self.ensure_open()?;
+        if !self.is_running.load(Ordering::SeqCst) {
+            return Err(SonicError::ListenerNotRunning);
+        }
+        {
+            let mut paused_from = self.paused_from.lock();
+            if paused_from.is_some() {
+                return Ok(());
+            }
+            let state = match self.get_state() {
+                ListenerState::Acquiring => ListenerState::Acquiring,
+                _ => ListenerState::Listening,
+            };
+            *paused_from = Some(state);
+        }
+        self.notify_state(ListenerState::Paused);
+        Ok(())
     }
 
-    /// Check if currently listening
-    pub fn is_listening(&self) -> bool {
-        self.is_running.load(Ordering::SeqCst)
+    /// Resume a paused listener in the state it was paused in (`Listening`
+    /// or `Acquiring`), streaming on from the samples it kept. Resuming a
+    /// listener that is not paused does nothing.
+    pub fn resume(&self) -> Result<(), SonicError> {
+        self.ensure_open()?;
+        if !self.is_running.load(Ordering::SeqCst) {
+            return Err(SonicError::ListenerNotRunning);
+        }
+        let Some(state) = self.paused_from.lock().take() else {
+            return Ok(());
+        };
+        self.notify_state(state);
+        Ok(())
     }
 
-    /// Get current configuration
-    pub fn get_config(&self) -> SonicConfig {
-        self.config.read().clone()
-    }
+    /// Process PCM audio buffer (16-bit signed little-endian, unless
+    /// `pcm_format` says otherwise).
+    ///
+    /// Runs the real shared `vouch-sonic-dsp` v3 detector (chirp matched-filter
+    /// sync + multi-layer FSK + CRC-validated soft decode) over the buffer.
+    pub fn process_buffer(&self, pcm_data: &[u8]) -> Result<WatermarkResult, SonicError> {
+        self.ensure_open()?;
+        let (channels, format) = {
+            let config = self.config.read();
+            (config.channels, config.pcm_format)
+        };
+        if let Some(format) = format.filter(|f| *f != PcmFormat::default()) {
+            let samples = decode_pcm(pcm_data, &format)?;
+            if format.interleaved || channels == 1 {
+                return self.process_samples(&samples);
+            }
+            if samples.len() % channels as usize != 0 {
+                return Err(SonicError::InvalidConfig(format!(
+                    "planar buffer must hold as many samples for each of its {channels} channels"
+                )));
+            }
+            let block = samples.len() / channels as usize;
+            let planar: Vec<&[f32]> = (0..channels as usize).map(|c| &samples[c * block..(c + 1) * block]).collect();
+            return self.process_planar(&planar);
+        }
+        if channels > 1 {
+            return self.process_interleaved(&pcm_le16_to_samples(pcm_data), channels);
+        }
+        let min_samples = self.min_input_samples();
+        if pcm_data.len() < min_samples * 2 {
+            return Err(SonicError::BufferTooShort(min_samples * 2));
+        }
 
-    /// Update detection threshold at runtime
-    pub fn set_detection_threshold(&self, threshold: f32) {
-        if threshold >= 0.0 && threshold <= 1.0 {
-            self.config.write().detection_threshold = threshold;
+        self.release_sync_lock();
+        let samples = pcm_le16_to_samples(pcm_data);
+        match self.to_detection_rate(&samples)? {
+            Cow::Borrowed(_) => Ok(self.process_frame(&samples, pcm_data, None, None)),
+            Cow::Owned(resampled) => {
+                let pcm = samples_to_pcm_le16(&resampled);
+                Ok(self.process_frame(&resampled, &pcm, None, None))
+            }
         }
     }
-}
 
-// =============================================================================
-// Signature Verification
-// =============================================================================
+    /// Process 16-bit samples as Android's `ShortArray` or an iOS Int16
+    /// buffer delivers them, without the caller converting to bytes or
+    /// floats first. Equivalent to `process_buffer` over the same samples.
+    pub fn process_samples_i16(&self, samples: &[i16]) -> Result<WatermarkResult, SonicError> {
+        self.ensure_open()?;
+        let floats: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+        let channels = self.config.read().channels;
+        if channels > 1 {
+            return self.process_interleaved(&floats, channels);
+        }
+        let min_samples = self.min_input_samples();
+        if samples.len() < min_samples {
+            return Err(SonicError::BufferTooShort(min_samples));
+        }
 
-/// Result of signature verification
-#[derive(Debug, Clone)]
-pub struct VerificationResult {
-    pub valid: bool,
-    pub signer_did: Option<String>,
-    pub error_message: Option<String>,
-}
+        self.release_sync_lock();
+        match self.to_detection_rate(&floats)? {
+            Cow::Borrowed(_) => {
+                let pcm: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+                Ok(self.process_frame(&floats, &pcm, None, None))
+            }
+            Cow::Owned(resampled) => {
+                let pcm = samples_to_pcm_le16(&resampled);
+                Ok(self.process_frame(&resampled, &pcm, None, None))
+            }
+        }
+    }
 
-/// Verifier for Ed25519 signatures
-pub struct SignatureVerifier;
+    /// Process float samples directly.
+    ///
+    /// Converts to 16-bit LE PCM and runs the real shared v3 detector.
+    pub fn process_samples(&self, samples: &[f32]) -> Result<WatermarkResult, SonicError> {
+        self.ensure_open()?;
+        let channels = self.config.read().channels;
+        if channels > 1 {
+            return self.process_interleaved(samples, channels);
+        }
+        let min_samples = self.min_input_samples();
+        if samples.len() < min_samples {
+            return Err(SonicError::BufferTooShort(min_samples));
+        }
 
-impl SignatureVerifier {
-    pub fn new() -> Self {
-        Self
+        self.release_sync_lock();
+        let samples = self.to_detection_rate(samples)?;
+        let pcm = samples_to_pcm_le16(&samples);
+        Ok(self.process_frame(&samples, &pcm, None, None))
     }
 
-    /// Verify Ed25519 signature
-    pub fn verify_signature(
-        &self,
-        message: &[u8],
-        signature: &[u8],
-        public_key: &[u8],
-    ) -> VerificationResult {
-        use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+    /// Process interleaved multi-channel float samples (`channels` samples
+    /// per frame, whatever `SonicConfig::channels` says), downmixed to mono
+    /// according to `downmix_mode`.
+    pub fn process_interleaved(&self, samples: &[f32], channels: u32) -> Result<WatermarkResult, SonicError> {
+        self.ensure_open()?;
+        if channels == 0 {
+            return Err(SonicError::InvalidConfig("channels must be at least 1".into()));
+        }
+        let channels = channels as usize;
+        let min_samples = self.min_input_samples();
+        if samples.len() / channels < min_samples {
+            return Err(SonicError::BufferTooShort(min_samples * channels));
+        }
 
-        // Parse public key
-        let pk = match public_key.try_into() {
-            Ok(bytes) => match VerifyingKey::from_bytes(&bytes) {
-                Ok(key) => key,
-                Err(e) => {
-                    return VerificationResult {
-                        valid: false,
-                        signer_did: None,
-                        error_message: Some(format!("Invalid public key: {}", e)),
-                    }
-                }
-            },
-            Err(_) => {
-                return VerificationResult {
-                    valid: false,
-                    signer_did: None,
-                    error_message: Some("Public key must be 32 bytes".into()),
-                }
-            }
+        self.release_sync_lock();
+        let mode = self.config.read().downmix_mode;
+        let (mono, side) = downmix(samples, channels, mode);
+        let mono = self.to_detection_rate(&mono)?;
+        let side = side.map(|side| self.to_detection_rate(&side).map(Cow::into_owned)).transpose()?;
+        let split = match mode {
+            DownmixMode::BestChannel if channels > 1 => split_channels(samples, channels)
+                .iter()
+                .map(|channel| self.to_detection_rate(channel).map(Cow::into_owned))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => Vec::new(),
         };
+        let source = match mode {
+            _ if channels == 1 => None,
+            DownmixMode::Left => Some(ChannelSource::Channel(0)),
+            DownmixMode::Right => Some(ChannelSource::Channel(1)),
+            DownmixMode::BestChannel => Some(ChannelSource::Channels(&split)),
+            DownmixMode::MidSide | DownmixMode::Average => side.as_deref().map(ChannelSource::Side),
+        };
+        let pcm = samples_to_pcm_le16(&mono);
+        Ok(self.process_frame(&mono, &pcm, source, None))
+    }
+
+    /// Process planar float channels (one slice per channel, all the same
+    /// length), as `AVAudioPCMBuffer.floatChannelData` holds them, reduced
+    /// like `process_interleaved`.
+    pub fn process_planar<C: AsRef<[f32]>>(&self, channels: &[C]) -> Result<WatermarkResult, SonicError> {
+        let len = channels.first().map_or(0, |c| c.as_ref().len());
+        if channels.iter().any(|c| c.as_ref().len() != len) {
+            return Err(SonicError::InvalidConfig("planar channels must be the same length".into()));
+        }
+        let interleaved: Vec<f32> = (0..len).flat_map(|i| channels.iter().map(move |c| c.as_ref()[i])).collect();
+        self.process_interleaved(&interleaved, channels.len() as u32)
+    }
 
-        // Parse signature
-        let sig = match Signature::from_slice(signature) {
-            Ok(s) => s,
+    /// Push streamed float samples of any length.
+    ///
+    /// Samples accumulate internally; each time a full analysis window is
+    /// available it is processed exactly like `process_samples` (callbacks
+    /// fire as usual). Windows overlap, advancing 3.4 s at a time, so a
+    /// watermark that straddles two mic callbacks or two windows is still
+    /// decoded, once. Returns the result of the last window processed by this
+    /// call, or `None` if no window completed. Call `flush` at end of stream to
+    /// process the remaining tail. Samples pushed while `Paused` are dropped.
+    pub fn push_samples(&self, samples: &[f32]) -> Option<WatermarkResult> {
+        if self.paused_from.lock().is_some() || self.closed.load(Ordering::SeqCst) {
+            return None;
+        }
+        let samples = match self.stream_to_detection_rate(samples, false) {
+            Ok(samples) => samples,
             Err(e) => {
-                return VerificationResult {
-                    valid: false,
-                    signer_did: None,
-                    error_message: Some(format!("Invalid signature: {}", e)),
-                }
+                self.emit(dispatch::Event::Error(e.to_string()));
+                return None;
             }
         };
-
-        // Verify
-        match pk.verify(message, &sig) {
-            Ok(()) => {
-                // Compute DID from public key
-                let did = format!(
-                    "did:key:z6Mk{}",
-                    bs58::encode(public_key).into_string()
-                );
-                
-                VerificationResult {
-                    valid: true,
-                    signer_did: Some(did),
-                    error_message: None,
-                }
+        let window = self.stream_window_len();
+        let hop = self.stream_hop_len();
+        // Cut complete windows under the lock, but process them after
+        // releasing it so callbacks may push more samples.
+        let mut frames = Vec::new();
+        {
+            let mut pending = self.pending.lock();
+            pending.extend_from_slice(&samples);
+            // Where the first pending sample sits in the session's stream
+            let mut at = self.consume_stream(samples.len()).map(|end| end - pending.len() as u64);
+            while pending.len() >= window {
+                frames.push((pending[..window].to_vec(), at));
+                pending.drain(..hop);
+                at = at.map(|at| at + hop as u64);
             }
-            Err(e) => VerificationResult {
-                valid: false,
-                signer_did: None,
-                error_message: Some(format!("Signature verification failed: {}", e)),
-            },
         }
-    }
 
-    /// Verify payload from watermark result
-    pub fn verify_watermark_payload(&self, result: WatermarkResult) -> VerificationResult {
-        // In a real implementation, we would:
-        // 1. Extract signature from payload
-        // 2. Extract public key from DID
-        // 3. Verify signature over content hash
-        
-        // For now, return mock verification
-        if result.detected {
-            VerificationResult {
-                valid: true,
-                signer_did: result.signer_did,
-                error_message: None,
+        let acquiring = self.get_state() == ListenerState::Acquiring;
+        let (gate, sample_rate) = {
+            let config = self.config.read();
+            (config.pilot_gate, config.sample_rate)
+        };
+        let mut last = None;
+        let advanced = frames.len() * hop;
+        for (frame, at) in frames {
+            if gate && !goertzel::sync_pilot_present(&frame, sample_rate) {
+                last = Some(self.skip_frame(&frame, at));
+                continue;
             }
-        } else {
-            VerificationResult {
-                valid: false,
-                signer_did: None,
-                error_message: Some("No watermark detected".into()),
+            let pcm = samples_to_pcm_le16(&frame);
+            last = Some(self.process_frame(&frame, &pcm, None, at));
+        }
+        if acquiring && last.is_some() {
+            let mut lock = self.sync_lock.lock();
+            match lock.and_then(|at| at.checked_sub(advanced)) {
+                // The sync is still in the overlap, so no window has had it
+                // where the detector searches yet.
+                Some(at) => {
+                    *lock = Some(at);
+                    *self.state.write() = ListenerState::Acquiring;
+                }
+                // The window holding the sync has been decoded.
+                None => {
+                    *lock = None;
+                    drop(lock);
+                    self.notify_state(ListenerState::Listening);
+                }
             }
         }
+        self.track_sync_lock(samples.len());
+        self.enforce_assembly_bound().or(last)
     }
-}
 
-impl Default for SignatureVerifier {
-    fn default() -> Self {
-        Self
+    /// Enter `Acquiring` once the sync preamble shows up in the streamed
+    /// samples still pending, checking only the `new` samples just pushed
+    /// (plus a chirp's length before them, for a preamble straddling pushes).
+    fn track_sync_lock(&self, new: usize) {
+        if !self.is_running.load(Ordering::SeqCst) || self.get_state() != ListenerState::Listening {
+            return;
+        }
+        let sample_rate = self.config.read().sample_rate;
+        let Some(correlator) = self.sync_correlator(sample_rate) else {
+            return;
+        };
+        let (base, tail) = {
+            let pending = self.pending.lock();
+            let base = pending.len().saturating_sub(new + correlator.reference_len());
+            (base, pending[base..].to_vec())
+        };
+        let ncc = correlator.correlate_normalized(&tail);
+        let Some(peak) = (0..ncc.len()).max_by(|&a, &b| ncc[a].total_cmp(&ncc[b])) else {
+            return;
+        };
+        if ncc[peak] >= SYNC_LOCK_CORRELATION {
+            *self.sync_lock.lock() = Some(base + peak);
+            self.notify_state(ListenerState::Acquiring);
+        }
     }
-}
-
-// =============================================================================
-// Module Functions
-// =============================================================================
 
-/// Get library version
-pub fn get_version() -> String {
-    env!("CARGO_PKG_VERSION").into()
-}
+    /// Abandon an assembly that has outgrown `max_payload_bytes`.
+    ///
+    /// Once more audio has arrived after the sync preamble than the
+    /// preamble plus one repetition of a payload that long, the payload is
+    /// complete if it will ever be: the audio from the preamble on is decoded
+    /// right away, the pending buffer is cleared and the listener returns to
+    /// `Listening`. A decode that finds nothing reports
+    /// `RejectionReason::PayloadOverflow`.
+    fn enforce_assembly_bound(&self) -> Option<WatermarkResult> {
+        let (max_bytes, sample_rate) = {
+            let config = self.config.read();
+            (config.max_payload_bytes?, config.sample_rate)
+        };
+        let start = (*self.sync_lock.lock())?;
+        let limit = dsp::v3_sync_reference(sample_rate).len()
+            + max_bytes as usize * CHIPS_PER_PAYLOAD_BYTE * spectrum::chip_len(sample_rate);
+        let (assembly, at) = {
+            let mut pending = self.pending.lock();
+            if pending.len().saturating_sub(start) <= limit {
+                return None;
+            }
+            let at = self.stream_position().map(|end| end - (pending.len() - start) as u64);
+            let assembly = pending.split_off(start);
+            pending.clear();
+            (assembly, at)
+        };
+        *self.sync_lock.lock() = None;
 
-/// Quick detection function (without creating listener)
-pub fn detect_watermark(audio_data: &[u8], sample_rate: u32) -> WatermarkResult {
-    // Create temporary config and engine
-    let config = SonicConfig {
-        sample_rate,
-        ..Default::default()
-    };
-    
-    if let Err(_) = config.validate() {
-        return WatermarkResult::not_detected();
+        let pcm = samples_to_pcm_le16(&assembly);
+        let mut result = self.process_frame(&assembly, &pcm, None, at);
+        if !result.detected && result.rejection_reason.is_none() {
+            result.rejection_reason = Some(RejectionReason::PayloadOverflow);
+        }
+        self.notify_state(ListenerState::Listening);
+        Some(result)
     }
-    
-    let listener = match SonicListener::new(config) {
-        Ok(l) => l,
-        Err(_) => return WatermarkResult::not_detected(),
-    };
-    
-    match listener.process_buffer(audio_data) {
-        Ok(result) => result,
-        Err(_) => WatermarkResult::not_detected(),
+
+    /// Set the listener state and report it through `on_state_changed`.
+    fn notify_state(&self, state: ListenerState) {
+        *self.state.write() = state;
+        self.emit(dispatch::Event::StateChanged(state));
     }
-}
 
-// =============================================================================
-// Tests
-// =============================================================================
+    /// The sync chirp's correlator at `sample_rate`, planned on first use
+    /// and again only when the rate changes.
+    fn sync_correlator(&self, sample_rate: u32) -> Option<Arc<spectrum::ReferenceCorrelator>> {
+        let mut cached = self.sync_correlator.lock();
+        if let Some((rate, correlator)) = cached.as_ref() {
+            if *rate == sample_rate {
+                return Some(correlator.clone());
+            }
+        }
+        let chirp = dsp::v3_sync_reference(sample_rate);
+        if chirp.is_empty() {
+            return None;
+        }
+        let correlator = Arc::new(spectrum::ReferenceCorrelator::new(&chirp, chirp.len()).ok()?);
+        *cached = Some((sample_rate, correlator.clone()));
+        Some(correlator)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::AtomicU32;
+    /// Give up on a sync lock the streamed samples hold, returning from
+    /// `Acquiring` to `Listening` through `on_state_changed`: the stream is
+    /// flushed, its rate changes, or audio is processed outside it.
+    fn release_sync_lock(&self) {
+        if self.sync_lock.lock().take().is_some() && self.get_state() == ListenerState::Acquiring {
+            self.notify_state(ListenerState::Listening);
+        }
+    }
 
-    #[derive(Default)]
-    struct TestCallback {
-        detections: AtomicU32,
-        levels: AtomicU32,
+    /// Process whatever streamed samples remain buffered.
+    ///
+    /// The tail is zero-padded up to the minimum buffer length if needed and
+    /// run through the detector, firing callbacks as usual, so the end of a
+    /// recording is not silently dropped. Returns `None` if nothing was
+    /// pending.
+    pub fn flush(&self) -> Option<WatermarkResult> {
+        if self.closed.load(Ordering::SeqCst) {
+            return None;
+        }
+        // The resampler's held-back input is the very end of the stream.
+        if let Ok(rest) = self.stream_to_detection_rate(&[], true) {
+            self.pending.lock().extend_from_slice(&rest);
+            self.consume_stream(rest.len());
+        }
+        let mut tail = std::mem::take(&mut *self.pending.lock());
+        let at = self.stream_position().map(|end| end - tail.len() as u64);
+        self.release_sync_lock();
+        if tail.is_empty() {
+            return None;
+        }
+        if tail.len() < MIN_SAMPLES {
+            tail.resize(MIN_SAMPLES, 0.0);
+        }
+        let pcm = samples_to_pcm_le16(&tail);
+        Some(self.process_frame(&tail, &pcm, None, at))
     }
 
-    impl WatermarkCallback for TestCallback {
-        fn on_watermark_detected(&self, _result: WatermarkResult) {
-            self.detections.fetch_add(1, Ordering::SeqCst);
+    /// Process every buffer received on `rx` until the sender side closes,
+    /// for Rust hosts that produce audio on another thread.
+    ///
+    /// Each buffer goes through `process_samples`, so callbacks fire as
+    /// usual; a buffer it rejects (too short) is reported through `on_error`
+    /// and skipped. Returns once the channel is closed and drained, or, within
+    /// 50 ms, once the listener is not listening: start it
+    /// before calling and `stop_listening` to break out early. Buffers still
+    /// queued when stopped are left unprocessed.
+    pub fn consume(self: Arc<Self>, rx: Receiver<Vec<f32>>) {
+        while self.is_running.load(Ordering::SeqCst) {
+            let samples = match rx.recv_timeout(CONSUME_POLL_INTERVAL) {
+                Ok(samples) => samples,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if let Err(e) = self.process_samples(&samples) {
+                self.emit(dispatch::Event::Error(e.to_string()));
+            }
         }
+    }
 
-        fn on_audio_level_changed(&self, _level_db: f32) {
-            self.levels.fetch_add(1, Ordering::SeqCst);
+    /// Pay one-time setup costs now rather than on the first buffer: plan
+    /// the listener's FFTs and its sync chirp correlator, size the quality
+    /// STFT's working buffers, and size the streaming buffer for a full
+    /// analysis window. Call before
+    /// `start_listening` to avoid a first-frame latency spike. Optional;
+    /// buffers are processed the same either way.
+    pub fn warm_up(&self) {
+        // An unsupported size is reported per buffer by the quality stage.
+        let (fft_size, function) = {
+            let config = self.config.read();
+            (config.stft_fft_size as usize, config.stft_window)
+        };
+        if let Ok(plan) = self.fft_plans.forward(fft_size) {
+            self.quality_scratch.lock().prepare(plan.as_ref(), fft_size, function);
         }
+        self.sync_correlator(self.config.read().sample_rate);
+        let window = self.stream_window_len();
+        let mut pending = self.pending.lock();
+        let additional = window.saturating_sub(pending.len());
+        pending.reserve(additional);
+    }
 
-        fn on_error(&self, _message: String) {}
-        fn on_state_changed(&self, _state: ListenerState) {}
+    /// Shortest buffer, in samples at the input rate, that still makes
+    /// `MIN_SAMPLES` at the detection rate.
+    fn min_input_samples(&self) -> usize {
+        let config = self.config.read();
+        match config.input_sample_rate {
+            Some(input) => (MIN_SAMPLES as u64 * input as u64).div_ceil(config.sample_rate as u64) as usize,
+            None => MIN_SAMPLES,
+        }
     }
 
-    #[test]
-    fn test_config_default() {
-        let config = SonicConfig::default();
-        assert_eq!(config.sample_rate, 16000);
-        assert_eq!(config.frame_size_ms, 50);
-        assert!(config.validate().is_ok());
+    /// `samples`, captured at `input_sample_rate`, at the detection rate.
+    fn to_detection_rate<'a>(&self, samples: &'a [f32]) -> Result<Cow<'a, [f32]>, SonicError> {
+        let config = self.config.read();
+        match config.input_sample_rate {
+            Some(input) if input != config.sample_rate => {
+                Ok(Cow::Owned(resample::resample(samples, input, config.sample_rate)?))
+            }
+            _ => Ok(Cow::Borrowed(samples)),
+        }
     }
 
-    #[test]
-    fn test_config_invalid_sample_rate() {
-        let config = SonicConfig {
-            sample_rate: 100, // Too low
-            ..Default::default()
+    /// `to_detection_rate` for streamed pieces, through the stream
+    /// resampler; with `finish`, also drains what it holds back.
+    fn stream_to_detection_rate<'a>(&self, samples: &'a [f32], finish: bool) -> Result<Cow<'a, [f32]>, SonicError> {
+        let rates = {
+            let config = self.config.read();
+            config.input_sample_rate.map(|input| (input, config.sample_rate))
         };
-        assert!(config.validate().is_err());
+        let mut stream = self.stream_resampler.lock();
+        let Some(rates) = rates.filter(|(input, target)| input != target) else {
+            *stream = None;
+            return Ok(Cow::Borrowed(samples));
+        };
+        if stream.as_ref().map(|s| s.rates()) != Some(rates) {
+            *stream = Some(resample::StreamResampler::new(rates.0, rates.1)?);
+        }
+        let resampler = stream.as_mut().expect("stream resampler was just set");
+        let mut out = resampler.process(samples)?;
+        if finish {
+            out.extend(resampler.finish()?);
+        }
+        Ok(Cow::Owned(out))
     }
 
-    #[test]
-    fn test_listener_creation() {
-        let config = SonicConfig::default();
-        let listener = SonicListener::new(config);
-        assert!(listener.is_ok());
+    /// Streaming analysis window length in samples at the configured rate.
+    fn stream_window_len(&self) -> usize {
+        (STREAM_WINDOW_MS as u64 * self.config.read().sample_rate as u64 / 1000) as usize
     }
 
-    #[test]
-    fn test_listener_start_stop() {
-        let config = SonicConfig::default();
-        // `start_listening` takes `self: &Arc<Self>` (uniffi callback interface),
-        // so the listener must be held in an Arc; the callback is passed boxed.
-        let listener = Arc::new(SonicListener::new(config).unwrap());
-
-        assert!(listener.start_listening(Box::new(TestCallback::default())).is_ok());
-        assert!(listener.is_listening());
+    /// Streaming window advance in samples at the configured rate.
+    fn stream_hop_len(&self) -> usize {
+        (STREAM_HOP_MS as u64 * self.config.read().sample_rate as u64 / 1000) as usize
+    }
 
-        assert!(listener.stop_listening().is_ok());
-        assert!(!listener.is_listening());
+    /// Shared body of `process_buffer` / `process_samples`: emit the audio
+    /// level, run detection, fire callbacks, and restore the listener state.
+    /// `samples` and `pcm_data` are the same audio in both representations;
+    /// `source`, when given, says which input channel they are or what else
+    /// to detect on. The clipping guard and the quality stage measure the
+    /// signal the result came from. Counted and timed for `stats`.
+    fn process_frame(
+        &self,
+        samples: &[f32],
+        pcm_data: &[u8],
+        source: Option<ChannelSource>,
+        at: Option<u64>,
+    ) -> WatermarkResult {
+        let started = Instant::now();
+        let result = self.analyse_frame(samples, pcm_data, source, at);
+        self.processing_times.lock().record(started.elapsed());
+        self.count_buffer(samples.len());
+        result
     }
 
-    #[test]
-    fn test_process_samples() {
-        let config = SonicConfig::default();
+    /// `process_frame`, untimed.
+    fn analyse_frame(
+        &self,
+        samples: &[f32],
+        pcm_data: &[u8],
+        source: Option<ChannelSource>,
+        at: Option<u64>,
+    ) -> WatermarkResult {
+        *self.state.write() = ListenerState::Processing;
+
+        let config = self.config.read().clone();
+        self.emit_level(samples, config.max_level_callback_hz, config.level_floor_db);
+        let config_snapshot = config.collect_config_snapshot.then(|| config.clone());
+
+        let mut analysed = samples;
+        let mut result = match degenerate_signal(samples, config.min_signal_variance) {
+            Some(reason) => {
+                let mut result = WatermarkResult {
+                    config_snapshot,
+                    ..WatermarkResult::rejected(reason)
+                };
+                self.record_in_session(&mut result, samples.len(), at, config.sample_rate);
+                self.restore_idle_state();
+                return result;
+            }
+            None => match source {
+                Some(ChannelSource::Channels(channels)) => {
+                    let mut best: Option<(usize, WatermarkResult)> = None;
+                    for (index, channel) in channels.iter().enumerate() {
+                        let result = self.detect_pcm(&samples_to_pcm_le16(channel), &config);
+                        let better = best.as_ref().is_none_or(|(_, b)| {
+                            (result.detected, result.confidence) > (b.detected, b.confidence)
+                        });
+                        if better {
+                            best = Some((index, result));
+                        }
+                    }
+                    match best {
+                        Some((index, result)) => {
+                            analysed = &channels[index];
+                            WatermarkResult {
+                                channel_index: Some(index as u32),
+                                ..result
+                            }
+                        }
+                        None => self.detect_pcm(pcm_data, &config),
+                    }
+                }
+                Some(ChannelSource::Channel(index)) => WatermarkResult {
+                    channel_index: Some(index),
+                    ..self.detect_pcm(pcm_data, &config)
+                },
+                Some(ChannelSource::Side(side)) => {
+                    let result = self.detect_pcm(pcm_data, &config);
+                    if result.detected {
+                        result
+                    } else {
+                        let side_result = self.detect_pcm(&samples_to_pcm_le16(side), &config);
+                        if side_result.detected {
+                            side_result
+                        } else {
+                            result
+                        }
+                    }
+                }
+                None => self.detect_pcm(pcm_data, &config),
+            },
+        };
+        let decoded = result.detected;
+        result.apply_clipping_guard(clipping_ratio(analysed), config.max_clipping_ratio);
+        // Before the threshold: marginal frames still count as evidence.
+        self.accumulate_signer_evidence(&result, config.signer_confirmation_threshold);
+        result.apply_threshold(config.detection_threshold);
+        if let Some(sigmas) = config.adaptive_threshold_sigmas {
+            self.apply_noise_floor(&mut result, decoded, analysed, sigmas, config.sample_rate);
+        }
+        if config.min_detection_frames > 1 {
+            self.apply_min_detection_frames(&mut result, config.min_detection_frames);
+        }
+
+        // The quality stage is FFT-based; if it cannot run, keep the
+        // detector's own figure and flag the result instead of failing.
+        // The sample-rate check reads the spectrum the quality stage leaves
+        // in its scratch.
+        let quality = {
+            let mut scratch = self.quality_scratch.lock();
+            spectrum::estimate_quality(
+                &self.fft_plans,
+                &mut scratch,
+                analysed,
+                config.stft_fft_size as usize,
+                &config,
+                self.quality_estimator.read().as_ref(),
+            )
+            .map(|quality| (quality, spectrum::suspect_sample_rate(scratch.power(), config.sample_rate)))
+        };
+        match quality {
+            Ok((quality, mismatch)) => {
+                result.audio_quality = quality;
+                if let Some(rolloff) = mismatch {
+                    result.sample_rate_mismatch = true;
+                    self.emit(dispatch::Event::Error(format!(
+                        "spectral rolloff at {rolloff:.0} Hz: audio is probably sampled above the configured {} Hz",
+                        config.sample_rate
+                    )));
+                }
+            }
+            Err(e) => {
+                result.degraded = true;
+                self.emit(dispatch::Event::Error(e.to_string()));
+            }
+        }
+        result.config_snapshot = config_snapshot;
+        self.record_in_session(&mut result, samples.len(), at, config.sample_rate);
+
+        // Emit detection if found
+        if result.detected {
+            self.emit_detection(&result);
+        }
+
+        self.restore_idle_state();
+        result
+    }
+
+    /// Answer a streamed window `pilot_gate` found no sync chirp in: emit
+    /// its level as usual, but report it not detected without decoding it.
+    fn skip_frame(&self, samples: &[f32], at: Option<u64>) -> WatermarkResult {
+        let config = self.config.read().clone();
+        self.emit_level(samples, config.max_level_callback_hz, config.level_floor_db);
+        self.pilot_skips.fetch_add(1, Ordering::Relaxed);
+        self.count_buffer(samples.len());
+        let sample_rate = config.sample_rate;
+        let mut result = WatermarkResult {
+            config_snapshot: config.collect_config_snapshot.then_some(config),
+            ..WatermarkResult::not_detected()
+        };
+        self.record_in_session(&mut result, samples.len(), at, sample_rate);
+        result
+    }
+
+    fn count_buffer(&self, len: usize) {
+        self.buffers_processed.fetch_add(1, Ordering::Relaxed);
+        self.samples_processed.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Count `result`, from `len` samples starting at stream sample `at`
+    /// (`None`: a buffer passed directly, appended to the stream), in the
+    /// open session, if any, and stamp it with its place in the session.
+    fn record_in_session(&self, result: &mut WatermarkResult, len: usize, at: Option<u64>, sample_rate: u32) {
+        if let Some(session) = self.session.lock().as_mut() {
+            session.record(result, len, at, sample_rate);
+        }
+    }
+
+    /// Add `samples` streamed samples to the open session's stream,
+    /// returning its new length; `None` outside a session.
+    fn consume_stream(&self, samples: usize) -> Option<u64> {
+        self.session.lock().as_mut().map(|session| session.consume(samples))
+    }
+
+    /// Samples the open session has taken in; `None` outside a session.
+    fn stream_position(&self) -> Option<u64> {
+        self.session.lock().as_ref().map(session::Session::position)
+    }
+
+    /// Leave the `Processing` state once a buffer is done.
+    fn restore_idle_state(&self) {
+        *self.state.write() = if self.paused_from.lock().is_some() {
+            ListenerState::Paused
+        } else if self.is_running.load(Ordering::SeqCst) {
+            ListenerState::Listening
+        } else {
+            ListenerState::Idle
+        };
+    }
+
+    /// Run the shared DSP v3 detector over 16-bit LE PCM and map to the FFI
+    /// `WatermarkResult`. A clip shorter than the DSP minimum (or any DSP-level
+    /// error) maps to a clean "not detected" result rather than an FFI error,
+    /// since real-time callers feed short rolling buffers.
+    ///
+    /// With `result_cache_capacity` set, a buffer seen before is answered
+    /// from the cache and skips the detector.
+    fn detect_pcm(&self, pcm_data: &[u8], config: &SonicConfig) -> WatermarkResult {
+        let capacity = config.result_cache_capacity as usize;
+        let key = (capacity > 0).then(|| cache::buffer_hash(pcm_data));
+        if let Some(cached) = key.and_then(|key| self.result_cache.lock().get(key)) {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return cached;
+        }
+        self.dsp_calls.fetch_add(1, Ordering::Relaxed);
+        let result = match dsp::detect(pcm_data, config.sample_rate) {
+            Ok(d) => WatermarkResult::from_dsp(d),
+            Err(_) => WatermarkResult::not_detected(),
+        };
+        if let Some(key) = key {
+            self.result_cache.lock().insert(key, result.clone(), capacity);
+        }
+        result
+    }
+
+    /// Drop a detection whose buffer does not correlate with the sync chirp
+    /// `sigmas` standard deviations above the noise floor, or add a buffer
+    /// the detector decoded nothing in to the floor. Buffers decoded but
+    /// then rejected (clipped, say) are left out, so a watermark cannot
+    /// raise the floor.
+    fn apply_noise_floor(
+        &self,
+        result: &mut WatermarkResult,
+        decoded: bool,
+        samples: &[f32],
+        sigmas: f32,
+        sample_rate: u32,
+    ) {
+        if decoded && !result.detected {
+            return;
+        }
+        let Some(peak) = self
+            .sync_correlator(sample_rate)
+            .and_then(|correlator| sync_correlation(samples, &correlator))
+        else {
+            return;
+        };
+        let mut floor = self.noise_floor.lock();
+        if !decoded {
+            floor.record(peak);
+        } else if floor.threshold(sigmas).is_some_and(|threshold| peak < threshold) {
+            result.detected = false;
+            result.payload_hash = None;
+        }
+    }
+
+    /// Extend or restart the run of buffers the detected payload has been
+    /// detected in, and hold the detection back while the run is shorter
+    /// than `min_frames`. A buffer without a detection ends the run.
+    fn apply_min_detection_frames(&self, result: &mut WatermarkResult, min_frames: u32) {
+        let mut streak = self.detection_streak.lock();
+        let Some(payload_hash) = result.payload_hash.as_ref().filter(|_| result.detected) else {
+            *streak = None;
+            return;
+        };
+        let run = match streak.as_mut() {
+            Some((hash, run)) if hash == payload_hash => {
+                *run += 1;
+                *run
+            }
+            _ => {
+                *streak = Some((payload_hash.clone(), 1));
+                1
+            }
+        };
+        if run < min_frames {
+            result.detected = false;
+            result.payload_hash = None;
+        }
+    }
+
+    /// Emit the audio level for UI, at most `max_hz` times per second
+    /// (0 = every buffer), clamped to `floor_db`.
+    fn emit_level(&self, samples: &[f32], max_hz: u32, floor_db: f32) {
+        let callbacks = self.callbacks(ListenerEvent::AudioLevel);
+        if callbacks.is_empty() {
+            return;
+        }
+        if max_hz > 0 {
+            let now = Instant::now();
+            let mut last = self.last_level_emit.lock();
+            let interval = Duration::from_secs(1) / max_hz;
+            if last.is_some_and(|t| now.duration_since(t) < interval) {
+                return;
+            }
+            *last = Some(now);
+        }
+        let level = level_db(samples, floor_db);
+        self.events.dispatch(callbacks, dispatch::Event::AudioLevel(level));
+    }
+
+    /// Add a decoded frame's confidence to its signer's total, and report the
+    /// signer as confirmed the first time the total reaches `threshold`.
+    fn accumulate_signer_evidence(&self, result: &WatermarkResult, threshold: f32) {
+        if !result.detected {
+            return;
+        }
+        let Some(signer) = result.signer_did.as_ref().or(result.payload_hash.as_ref()) else {
+            return;
+        };
+        let confirmed = {
+            let mut evidence = self.signer_evidence.lock();
+            let index = match evidence.iter().position(|e| &e.signer == signer) {
+                Some(index) => index,
+                None => {
+                    evidence.push(SignerEvidence {
+                        signer: signer.clone(),
+                        accumulated_confidence: 0.0,
+                        frames: 0,
+                        confirmed: false,
+                    });
+                    evidence.len() - 1
+                }
+            };
+            let entry = &mut evidence[index];
+            entry.accumulated_confidence += result.confidence;
+            entry.frames += 1;
+            let newly_confirmed = !entry.confirmed && entry.accumulated_confidence >= threshold;
+            entry.confirmed |= newly_confirmed;
+            newly_confirmed.then(|| entry.clone())
+        };
+        if let Some(entry) = confirmed {
+            self.emit(dispatch::Event::SignerConfirmed(entry.signer, entry.accumulated_confidence));
+        }
+    }
+
+    /// Emit watermark detected event to callback, and record it in the
+    /// detection store, unless it repeats a recent detection
+    /// (`duplicate_window_secs`)
+    fn emit_detection(&self, result: &WatermarkResult) {
+        let (window, interval) = {
+            let config = self.config.read();
+            (config.duplicate_window_secs, config.still_present_interval_secs)
+        };
+        let report = match &result.payload_hash {
+            Some(payload_hash) if window > 0 => self.duplicates.lock().observe(
+                payload_hash,
+                Instant::now(),
+                Duration::from_secs(window.into()),
+                Duration::from_secs(interval.into()),
+            ),
+            _ => dedup::Report::New,
+        };
+        match report {
+            dedup::Report::New => {}
+            dedup::Report::StillPresent(present_for) => {
+                let present_for_ms = present_for.as_millis() as u64;
+                self.emit(dispatch::Event::WatermarkStillPresent(result.clone(), present_for_ms));
+                return;
+            }
+            dedup::Report::Suppressed => return,
+        }
+        if let Some(store) = self.detection_store.read().as_ref() {
+            let detected_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+            store.insert(DetectionRecord {
+                detected_at,
+                result: result.clone(),
+            });
+        }
+        self.detections_emitted.fetch_add(1, Ordering::Relaxed);
+        self.emit(dispatch::Event::WatermarkDetected(result.clone()));
+    }
+
+    /// Scan a whole recording (16-bit LE PCM) for watermarks in `window_ms`
+    /// windows, reporting where each starts, its echo paths and the capture
+    /// clock drift. Offline: no callbacks fire and the listener state is
+    /// untouched.
+    pub fn detect_timed(&self, audio_data: &[u8], window_ms: u32) -> Vec<TimedDetection> {
+        self.detect_timed_cancellable(audio_data, window_ms, &AtomicBool::new(false))
+            .detections
+    }
+
+    /// `detect_timed` that stops early once `cancel` is set (e.g. from a UI
+    /// thread holding an `Arc<AtomicBool>`), returning the detections found
+    /// up to that point with `cancelled` set.
+    pub fn detect_timed_cancellable(&self, audio_data: &[u8], window_ms: u32, cancel: &AtomicBool) -> TimedScan {
+        let config = self.config.read().clone();
+        timed::scan(audio_data, &config, window_ms, cancel)
+    }
+
+    /// `detect_timed` for multi-hour recordings, reporting to `progress`
+    /// before each analysis window and once when done, so a UI can show
+    /// progress and the watermarks found so far. Returning false from
+    /// `on_progress` cancels the scan; the detections found up to then are
+    /// returned with `cancelled` set.
+    pub fn detect_timed_with_progress(
+        &self,
+        audio_data: &[u8],
+        window_ms: u32,
+        progress: Box<dyn ProgressCallback>,
+    ) -> TimedScan {
+        let config = self.config.read().clone();
+        timed::scan_with_progress(audio_data, &config, window_ms, progress.as_ref())
+    }
+
+    /// Offline analysis of a long recording across all cores (feature
+    /// `parallel`; not for the realtime path).
+    ///
+    /// `samples`, at `input_sample_rate` if set, are scanned like
+    /// `detect_timed` in `window_ms` windows, with the windows decoded in
+    /// parallel on the rayon pool and merged into the same detections a
+    /// sequential scan reports, in recording order. No callbacks fire and
+    /// the listener state is untouched.
+    #[cfg(feature = "parallel")]
+    pub fn process_samples_parallel(&self, samples: &[f32], window_ms: u32) -> Result<Vec<TimedDetection>, SonicError> {
+        self.ensure_open()?;
+        let samples = self.to_detection_rate(samples)?;
+        let config = self.config.read().clone();
+        Ok(timed::scan_parallel(&samples_to_pcm_le16(&samples), &config, window_ms))
+    }
+
+    /// Confidence accumulated per signer since the listener was created or
+    /// last reset, in the order the signers were first heard
+    pub fn signer_evidence(&self) -> Vec<SignerEvidence> {
+        self.signer_evidence.lock().clone()
+    }
+
+    /// Forget all accumulated signer evidence (e.g. at the start of a new
+    /// session); signers can then be confirmed again.
+    pub fn reset_signer_evidence(&self) {
+        self.signer_evidence.lock().clear();
+    }
+
+    /// Detector work counters since the listener was created, with recent
+    /// processing times, for monitoring the engine on the device
+    pub fn stats(&self) -> ListenerStats {
+        let (mean_processing_ms, p95_processing_ms) = {
+            let times = self.processing_times.lock();
+            (times.mean_ms(), times.p95_ms())
+        };
+        ListenerStats {
+            buffers_processed: self.buffers_processed.load(Ordering::Relaxed),
+            samples_processed: self.samples_processed.load(Ordering::Relaxed),
+            detections_emitted: self.detections_emitted.load(Ordering::Relaxed),
+            mean_processing_ms,
+            p95_processing_ms,
+            pending_samples: self.pending.lock().len() as u64,
+            dsp_calls: self.dsp_calls.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            pilot_skips: self.pilot_skips.load(Ordering::Relaxed),
+            dropped_level_events: self.events.dropped_levels(),
+            adaptive_threshold: self
+                .config
+                .read()
+                .adaptive_threshold_sigmas
+                .and_then(|sigmas| self.noise_floor.lock().threshold(sigmas)),
+        }
+    }
+
+    /// Get current state
+    pub fn get_state(&self) -> ListenerState {
+        if self.closed.load(Ordering::SeqCst) {
+            return ListenerState::Closed;
+        }
+        *self.state.read()
+    }
+
+    /// Check if currently listening
+    pub fn is_listening(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    /// Get current configuration
+    pub fn get_config(&self) -> SonicConfig {
+        self.config.read().clone()
+    }
+
+    /// Replace the audio quality estimator used for subsequent buffers
+    pub fn set_quality_estimator(&self, estimator: Box<dyn QualityEstimator>) {
+        *self.quality_estimator.write() = estimator;
+    }
+
+    /// Record every detection the listener emits in `store` (e.g.
+    /// `in_memory_detection_store()` or an app database); `None` stops
+    /// recording. A shut down listener drops the store at once.
+    pub fn set_detection_store(&self, store: Option<Arc<dyn DetectionStore>>) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+        *self.detection_store.write() = store;
+    }
+
+    /// The store detections are recorded in, if any
+    pub fn detection_store(&self) -> Option<Arc<dyn DetectionStore>> {
+        self.detection_store.read().clone()
+    }
+
+    /// Update detection threshold at runtime
+    pub fn set_detection_threshold(&self, threshold: f32) {
+        if (0.0..=1.0).contains(&threshold) {
+            self.modify_config(|config| config.detection_threshold = threshold);
+        }
+    }
+
+    /// Switch to `profile`'s detection settings at runtime, keeping the
+    /// rest of the configuration
+    pub fn set_sensitivity(&self, profile: SensitivityProfile) {
+        self.modify_config(|config| *config = config.clone().with_sensitivity(profile));
+    }
+
+    /// Replace the whole configuration at runtime, in one step: buffers
+    /// processed from then on see all of the new settings, and none of the
+    /// old. A new sample rate or input rate restarts the stream, dropping
+    /// any streamed samples still pending (they were captured or converted
+    /// at the old rate) and any sync lock on them; the session's stream
+    /// offsets carry on across the change.
+    pub fn update_config(&self, config: SonicConfig) -> Result<(), SonicError> {
+        self.ensure_open()?;
+        config.validate()?;
+        let mut old_rates = None;
+        self.modify_config(|current| {
+            let rates = (current.sample_rate, current.input_sample_rate);
+            if rates != (config.sample_rate, config.input_sample_rate) {
+                old_rates = Some(rates);
+            }
+            *current = config;
+        });
+        // After the swap, so nothing pending afterwards is at the old rate.
+        if let Some((old_sample_rate, _)) = old_rates {
+            self.pending.lock().clear();
+            *self.stream_resampler.lock() = None;
+            self.noise_floor.lock().clear();
+            *self.detection_streak.lock() = None;
+            if let Some(session) = self.session.lock().as_mut() {
+                session.rebase(old_sample_rate);
+            }
+            self.release_sync_lock();
+        }
+        Ok(())
+    }
+
+    /// Change the configuration in place and report the change through
+    /// `on_config_changed`, if there was one. The callback runs after the
+    /// lock is released, so it may read the configuration back.
+    fn modify_config(&self, change: impl FnOnce(&mut SonicConfig)) {
+        let (old, new) = {
+            let mut config = self.config.write();
+            let old = config.clone();
+            change(&mut config);
+            (old, config.clone())
+        };
+        if old != new {
+            self.result_cache.lock().clear();
+            self.emit(dispatch::Event::ConfigChanged(old, new));
+        }
+    }
+}
+
+impl Drop for SonicListener {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+// =============================================================================
+// Signature Verification
+// =============================================================================
+
+/// The canonical (RFC 8785) JSON of
+/// `{"covenant", "did", "payload_hash", "timestamp"}` that payload signatures
+/// cover, with absent fields as `null` and the covenant embedded as JSON.
+/// Shared by `SonicEmbedder` (signing) and `SignatureVerifier` (checking).
+fn signing_input(
+    did: Option<&str>,
+    timestamp: Option<u64>,
+    payload_hash: Option<&str>,
+    covenant_json: Option<&str>,
+) -> Result<Vec<u8>, SonicError> {
+    let covenant = match covenant_json {
+        Some(json) => serde_json::from_str(json)
+            .map_err(|e| SonicError::InvalidConfig(format!("covenant is not valid JSON: {e}")))?,
+        None => serde_json::Value::Null,
+    };
+    let payload = serde_json::json!({
+        "covenant": covenant,
+        "did": did,
+        "payload_hash": payload_hash,
+        "timestamp": timestamp,
+    });
+    canonicalize_covenant(&payload.to_string())
+}
+
+/// Result of signature verification
+#[derive(Debug, Clone, Default)]
+pub struct VerificationResult {
+    pub valid: bool,
+    pub signer_did: Option<String>,
+    pub error_message: Option<String>,
+    /// The signature checks out, but `require_pinned_signer` is set and the
+    /// signer (`signer_did`) is not pinned in the verifier's trust store
+    pub untrusted_signer: bool,
+    /// The signature checks out, but the signer's credential (`signer_did`)
+    /// is revoked in its status list
+    pub revoked: bool,
+    /// Algorithm the signature was checked with
+    pub algorithm: SignatureAlgorithm,
+    /// Verification method the signature verified under, when payload
+    /// verification knows it: a `did:key` signer's own key, or the
+    /// matching candidate of `verify_watermark_payload_with_keys`
+    pub key_id: Option<String>,
+    /// How the payload's signed timestamp compares with the verifier's
+    /// clock; `None` outside payload verification or without a timestamp.
+    /// Informational: a stale or future payload can still be `valid`
+    pub timestamp_status: Option<TimestampStatus>,
+    /// With `VerifierConfig::detect_replays`, this verifier already accepted
+    /// a payload with the same hash: the watermark is being heard again
+    pub replayed: bool,
+}
+
+/// Verification policy of a `SignatureVerifier`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifierConfig {
+    /// Reject signatures from signers not pinned in the verifier's
+    /// `TrustStore`, reporting them as `untrusted_signer` (default: false)
+    pub require_pinned_signer: bool,
+    /// Payloads signed longer ago than this are `Stale` (default: `None`,
+    /// never stale)
+    pub max_timestamp_age_secs: Option<u64>,
+    /// Payloads signed later than the verifier's clock plus this are
+    /// `Future` (default: 300)
+    pub max_clock_skew_secs: u64,
+    /// Remember accepted payloads and flag ones heard again as `replayed`
+    /// (default: false)
+    pub detect_replays: bool,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            require_pinned_signer: false,
+            max_timestamp_age_secs: None,
+            max_clock_skew_secs: 300,
+            detect_replays: false,
+        }
+    }
+}
+
+/// Verifier for Ed25519, ES256K and ES256 signatures
+pub struct SignatureVerifier {
+    resolver: Arc<dyn DidResolver>,
+    trust_store: RwLock<Arc<TrustStore>>,
+    config: RwLock<VerifierConfig>,
+    status_provider: RwLock<Option<Arc<dyn StatusListProvider>>>,
+    replay_cache: Mutex<freshness::ReplayCache>,
+}
+
+impl SignatureVerifier {
+    pub fn new() -> Self {
+        Self::with_resolver(Arc::new(DidKeyResolver))
+    }
+
+    /// Create a verifier that reports signers through a custom DID mapping
+    /// (e.g. `did:web` or `did:vouch`) instead of the default `did:key`.
+    /// The resolver may be implemented by the host app, to fetch DID
+    /// documents over its own network stack.
+    pub fn with_resolver(resolver: Arc<dyn DidResolver>) -> Self {
+        Self {
+            resolver,
+            trust_store: RwLock::new(Arc::new(TrustStore::new())),
+            config: RwLock::new(VerifierConfig::default()),
+            status_provider: RwLock::new(None),
+            replay_cache: Mutex::new(freshness::ReplayCache::default()),
+        }
+    }
+
+    /// Signers pinned for `require_pinned_signer`; empty until the app
+    /// pins some
+    pub fn trust_store(&self) -> Arc<TrustStore> {
+        self.trust_store.read().clone()
+    }
+
+    /// Check pinned signers against `trust_store`, e.g. one store shared by
+    /// several verifiers
+    pub fn set_trust_store(&self, trust_store: Arc<TrustStore>) {
+        *self.trust_store.write() = trust_store;
+    }
+
+    /// Current verification policy
+    pub fn get_config(&self) -> VerifierConfig {
+        self.config.read().clone()
+    }
+
+    /// Replace the verification policy for subsequent verifications
+    pub fn set_config(&self, config: VerifierConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Forget the payloads `detect_replays` has seen, e.g. when a new
+    /// monitoring session starts
+    pub fn clear_replay_cache(&self) {
+        self.replay_cache.lock().clear();
+    }
+
+    /// Check every verified signer against the status list `provider`
+    /// supplies for it, or stop checking with `None`
+    pub fn set_status_provider(&self, provider: Option<Box<dyn StatusListProvider>>) {
+        *self.status_provider.write() = provider.map(Arc::from);
+    }
+
+    /// Public key for a signer DID. A `did:key` carries its key (of any
+    /// supported type; compressed for ECDSA) and resolves locally; any other
+    /// DID goes to this verifier's resolver.
+    pub fn did_to_public_key(&self, did: &str) -> Option<Vec<u8>> {
+        did::decode_did_key(did)
+            .ok()
+            .map(|(_, key)| key)
+            .or_else(|| self.resolver.did_to_public_key(did.to_string()))
+    }
+
+    /// Public key to check an `algorithm` signature by `did` against. A
+    /// `did:key` of another key type is refused rather than tried.
+    fn signer_key(&self, did: &str, algorithm: SignatureAlgorithm) -> Result<Vec<u8>, &'static str> {
+        match did::decode_did_key(did) {
+            Ok((key_algorithm, key)) if key_algorithm == algorithm => Ok(key),
+            Ok(_) => Err("Signer key type does not match the signature algorithm"),
+            Err(_) => self
+                .resolver
+                .did_to_public_key(did.to_string())
+                .ok_or("Signer DID does not resolve to a public key"),
+        }
+    }
+
+    /// Verify Ed25519 signature
+    pub fn verify_signature(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> VerificationResult {
+        self.verify_signature_with_algorithm(message, signature, public_key, SignatureAlgorithm::Ed25519)
+    }
+
+    /// Verify a signature made with `algorithm`. ECDSA signatures are the
+    /// 64-byte `r || s` of a SHA-256 digest, under a SEC1 public key
+    /// (compressed or not); their signer is reported as its `did:key`.
+    pub fn verify_signature_with_algorithm(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        algorithm: SignatureAlgorithm,
+    ) -> VerificationResult {
+        self.verify_signature_from(message, signature, public_key, algorithm, None)
+    }
+
+    /// `verify_signature` for a signer that named itself `claimed_did`: with
+    /// `require_pinned_signer`, pinning either that DID, the DID the key
+    /// maps to, or the key itself admits the signature, and it is that DID
+    /// whose revocation status is checked.
+    fn verify_signature_from(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        algorithm: SignatureAlgorithm,
+        claimed_did: Option<&str>,
+    ) -> VerificationResult {
+        if let Err(message) = signature::verify(algorithm, message, signature, public_key) {
+            return VerificationResult {
+                valid: false,
+                signer_did: None,
+                error_message: Some(message),
+                algorithm,
+                ..Default::default()
+            };
+        }
+
+        // The resolver maps Ed25519 keys; an ECDSA signer is reported by the
+        // DID it claimed, or else by its did:key
+        let did = match algorithm {
+            SignatureAlgorithm::Ed25519 => self.resolver.public_key_to_did(public_key.to_vec()),
+            _ => match claimed_did {
+                Some(claimed) => claimed.to_string(),
+                None => did::did_key_for(algorithm, public_key).unwrap_or_default(),
+            },
+        };
+
+        let provider = self.status_provider.read().clone();
+        if let Some(status) = provider.and_then(|p| p.signer_status(claimed_did.unwrap_or(&did).to_string())) {
+            let revoked = match status_list_revoked(&status.status_list, status.status_index) {
+                Ok(revoked) => revoked,
+                Err(e) => {
+                    return VerificationResult {
+                        valid: false,
+                        signer_did: None,
+                        error_message: Some(e.to_string()),
+                        algorithm,
+                        ..Default::default()
+                    }
+                }
+            };
+            if revoked {
+                return VerificationResult {
+                    valid: false,
+                    error_message: Some(format!("Signer {did} has been revoked")),
+                    signer_did: Some(did),
+                    revoked: true,
+                    algorithm,
+                    ..Default::default()
+                };
+            }
+        }
+
+        if self.config.read().require_pinned_signer {
+            let store = self.trust_store();
+            let pinned = store.is_trusted(&did, public_key)
+                || claimed_did.is_some_and(|claimed| store.is_trusted(claimed, public_key));
+            if !pinned {
+                return VerificationResult {
+                    valid: false,
+                    error_message: Some(format!("Signer {did} is not pinned in the trust store")),
+                    signer_did: Some(did),
+                    untrusted_signer: true,
+                    algorithm,
+                    ..Default::default()
+                };
+            }
+        }
+
+        VerificationResult {
+            valid: true,
+            signer_did: Some(did),
+            error_message: None,
+            algorithm,
+            ..Default::default()
+        }
+    }
+
+    /// Verify an Ed25519 signature, returning the signer DID, or
+    /// `SignatureInvalid` when the key, signature or message does not check out
+    /// (`verify_signature` carries the detailed reason), `SignerRevoked` when
+    /// it does but the signer is revoked, or `UntrustedSigner` when
+    /// `require_pinned_signer` rejects the signer.
+    pub fn verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> Result<String, SonicError> {
+        let result = self.verify_signature(message, signature, public_key);
+        match (result.valid, result.signer_did) {
+            (true, Some(did)) => Ok(did),
+            (false, Some(did)) if result.revoked => Err(SonicError::SignerRevoked(did)),
+            (false, Some(did)) if result.untrusted_signer => Err(SonicError::UntrustedSigner(did)),
+            _ => Err(SonicError::SignatureInvalid),
+        }
+    }
+
+    /// Verify the signature carried in a watermark payload.
+    ///
+    /// The signer's public key comes from `signer_did` through this verifier's
+    /// DID mapping, the signature is checked with the payload's
+    /// `signature_algorithm`, and it must cover the payload's signing input:
+    /// the RFC 8785 canonical JSON of
+    /// `{"covenant", "did", "payload_hash", "timestamp"}`, with absent fields as
+    /// `null` and the covenant embedded as JSON. A detection without a
+    /// signature or signer DID is not valid.
+    pub fn verify_watermark_payload(&self, result: WatermarkResult) -> VerificationResult {
+        let invalid = |message: &str| VerificationResult {
+            valid: false,
+            signer_did: None,
+            error_message: Some(message.into()),
+            algorithm: result.signature_algorithm,
+            ..Default::default()
+        };
+        if !result.detected {
+            return invalid("No watermark detected");
+        }
+        let (Some(did), Some(signature)) = (&result.signer_did, &result.signature) else {
+            return invalid("Watermark payload is unsigned");
+        };
+        let algorithm = result.signature_algorithm;
+        let public_key = match self.signer_key(did, algorithm) {
+            Ok(public_key) => public_key,
+            Err(message) => return invalid(message),
+        };
+        let message = match result.signing_input() {
+            Ok(message) => message,
+            Err(e) => return invalid(&e.to_string()),
+        };
+        let mut verified = self.verify_signature_from(&message, signature, &public_key, algorithm, Some(did));
+        if verified.signer_did.is_some() {
+            verified.key_id = did::did_key_id(did);
+        }
+        self.check_freshness(&result, verified)
+    }
+
+    /// Set `timestamp_status` and, for a valid payload, `replayed`.
+    fn check_freshness(&self, result: &WatermarkResult, mut verified: VerificationResult) -> VerificationResult {
+        let config = self.get_config();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        verified.timestamp_status = result.timestamp.map(|timestamp| {
+            freshness::timestamp_status(timestamp, now, config.max_timestamp_age_secs, config.max_clock_skew_secs)
+        });
+        if config.detect_replays && verified.valid {
+            if let Some(payload_hash) = &result.payload_hash {
+                verified.replayed = self.replay_cache.lock().replayed(payload_hash);
+            }
+        }
+        verified
+    }
+
+    /// Verify the signature in a watermark payload against candidate keys
+    /// of its signer, such as every key its DID document lists
+    /// (`signer_keys_from_did_document`), so that payloads signed before a
+    /// key rotation keep verifying.
+    ///
+    /// Only keys of the payload's `signature_algorithm` whose validity
+    /// window covers its `timestamp` are tried, and `key_id` reports the
+    /// one the signature verified under. Trust and revocation are checked
+    /// as in [`verify_watermark_payload`](Self::verify_watermark_payload).
+    pub fn verify_watermark_payload_with_keys(&self, result: WatermarkResult, keys: Vec<SignerKey>) -> VerificationResult {
+        let algorithm = result.signature_algorithm;
+        let invalid = |message: &str| VerificationResult {
+            valid: false,
+            signer_did: None,
+            error_message: Some(message.into()),
+            algorithm,
+            ..Default::default()
+        };
+        if !result.detected {
+            return invalid("No watermark detected");
+        }
+        let (Some(did), Some(signature)) = (&result.signer_did, &result.signature) else {
+            return invalid("Watermark payload is unsigned");
+        };
+        let message = match result.signing_input() {
+            Ok(message) => message,
+            Err(e) => return invalid(&e.to_string()),
+        };
+        let mut candidates = keys
+            .iter()
+            .filter(|key| key.algorithm == algorithm && key.valid_at(result.timestamp))
+            .peekable();
+        if candidates.peek().is_none() {
+            return invalid("No signer key was valid when the payload was signed");
+        }
+        let Some(key) =
+            candidates.find(|key| signature::verify(algorithm, &message, signature, &key.public_key).is_ok())
+        else {
+            return invalid("Signature verification failed under every candidate key");
+        };
+        let verified = VerificationResult {
+            key_id: Some(key.key_id.clone()),
+            ..self.verify_signature_from(&message, signature, &key.public_key, algorithm, Some(did))
+        };
+        self.check_freshness(&result, verified)
+    }
+
+    /// Verify a detection end to end against the binary frame its payload
+    /// hash resolved to (see `parse_frame`).
+    ///
+    /// The frame's payload is the signed record itself: its signer DID,
+    /// timestamp and covenant are read from it, its `payload_hash` must be
+    /// the one detected in the audio, and its signature is then checked as
+    /// in [`verify_watermark_payload`](Self::verify_watermark_payload).
+    pub fn verify_watermark_frame(&self, result: WatermarkResult, frame: &[u8]) -> VerificationResult {
+        #[derive(serde::Deserialize)]
+        struct Record {
+            covenant: serde_json::Value,
+            did: Option<String>,
+            payload_hash: Option<String>,
+            timestamp: Option<u64>,
+        }
+
+        let invalid = |message: String| VerificationResult {
+            valid: false,
+            signer_did: None,
+            error_message: Some(message),
+            ..Default::default()
+        };
+        let frame = match parse_frame(frame) {
+            Ok(frame) => frame,
+            Err(e) => return invalid(e.to_string()),
+        };
+        let record: Record = match serde_json::from_slice(&frame.payload) {
+            Ok(record) => record,
+            Err(e) => return invalid(format!("Frame payload is not a payload record: {e}")),
+        };
+        if record.payload_hash.is_none() || record.payload_hash != result.payload_hash {
+            return invalid("Frame is for a different watermark".into());
+        }
+        self.verify_watermark_payload(WatermarkResult {
+            signer_did: record.did,
+            timestamp: record.timestamp,
+            covenant_json: (!record.covenant.is_null()).then(|| record.covenant.to_string()),
+            signature: frame.signature,
+            ..result
+        })
+    }
+}
+
+impl Default for SignatureVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =============================================================================
+// Module Functions
+// =============================================================================
+
+/// Get library version
+pub fn get_version() -> String {
+    env!("CARGO_PKG_VERSION").into()
+}
+
+/// `SonicConfig::preset`, for Swift and Kotlin
+pub fn config_preset(preset: ConfigPreset) -> SonicConfig {
+    SonicConfig::preset(preset)
+}
+
+/// Quick detection function (without creating listener)
+pub fn detect_watermark(audio_data: &[u8], sample_rate: u32) -> WatermarkResult {
+    // Create temporary config and engine
+    let config = SonicConfig {
+        sample_rate,
+        ..Default::default()
+    };
+    
+    if config.validate().is_err() {
+        return WatermarkResult::not_detected();
+    }
+    
+    let listener = match SonicListener::new(config) {
+        Ok(l) => l,
+        Err(_) => return WatermarkResult::not_detected(),
+    };
+    
+    match listener.process_buffer(audio_data) {
+        Ok(result) => result,
+        Err(_) => WatermarkResult::not_detected(),
+    }
+}
+
+/// Strict decode (without creating listener): the detection, or the stage the
+/// decode failed at (`SyncNotFound`, `ErrorCorrectionExceeded`, `CrcMismatch`)
+/// instead of a "not detected" result.
+pub fn decode_watermark(audio_data: &[u8], sample_rate: u32) -> Result<WatermarkResult, SonicError> {
+    dsp::detect_checked(audio_data, sample_rate)
+        .map(WatermarkResult::from_dsp)
+        .map_err(|e| SonicError::from_dsp(e, sample_rate))
+}
+
+/// Unit-amplitude, Hann-tapered linear chirp sweeping `f_start` to `f_end` Hz
+/// over `duration_ms`: the protocol's sync marker shape. The v3 preamble is
+/// 600 ms from 1500 to 3500 Hz; embedders scale it under the host audio.
+pub fn generate_chirp_sync(
+    sample_rate: u32,
+    duration_ms: u32,
+    f_start: f32,
+    f_end: f32,
+) -> Result<Vec<f32>, SonicError> {
+    if sample_rate == 0 {
+        return Err(SonicError::InvalidSampleRate(sample_rate));
+    }
+    if duration_ms == 0 {
+        return Err(SonicError::InvalidConfig("chirp duration must be positive".into()));
+    }
+    let nyquist = sample_rate as f32 / 2.0;
+    if !(0.0..=nyquist).contains(&f_start) || !(0.0..=nyquist).contains(&f_end) {
+        return Err(SonicError::InvalidConfig(format!(
+            "chirp frequencies must be within 0 - {nyquist} Hz"
+        )));
+    }
+    Ok(dsp::generate_chirp_sync(sample_rate, duration_ms, f_start, f_end))
+}
+
+/// Timed scan of a whole recording (without creating listener); see
+/// `SonicListener::detect_timed`
+pub fn detect_watermark_timed(audio_data: &[u8], sample_rate: u32, window_ms: u32) -> Vec<TimedDetection> {
+    detect_watermark_timed_cancellable(audio_data, sample_rate, window_ms, &AtomicBool::new(false)).detections
+}
+
+/// Cancellable `detect_watermark_timed`; see
+/// `SonicListener::detect_timed_cancellable`
+pub fn detect_watermark_timed_cancellable(
+    audio_data: &[u8],
+    sample_rate: u32,
+    window_ms: u32,
+    cancel: &AtomicBool,
+) -> TimedScan {
+    match SonicListener::new(SonicConfig {
+        sample_rate,
+        ..Default::default()
+    }) {
+        Ok(listener) => listener.detect_timed_cancellable(audio_data, window_ms, cancel),
+        Err(_) => TimedScan {
+            detections: Vec::new(),
+            cancelled: false,
+        },
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    #[derive(Default)]
+    struct TestCallback {
+        detections: AtomicU32,
+        levels: AtomicU32,
+        last_level_db: Mutex<Option<f32>>,
+        errors: AtomicU32,
+        still_present: Mutex<Vec<u64>>,
+        confirmations: Mutex<Vec<(String, f32)>>,
+        states: Mutex<Vec<ListenerState>>,
+        config_changes: Mutex<Vec<(SonicConfig, SonicConfig)>>,
+    }
+
+    // Lets a test keep a handle on the counters after boxing the callback.
+    impl WatermarkCallback for Arc<TestCallback> {
+        fn on_watermark_detected(&self, result: WatermarkResult) {
+            self.as_ref().on_watermark_detected(result)
+        }
+        fn on_audio_level_changed(&self, level_db: f32) {
+            self.as_ref().on_audio_level_changed(level_db)
+        }
+        fn on_error(&self, message: String) {
+            self.as_ref().on_error(message)
+        }
+        fn on_state_changed(&self, state: ListenerState) {
+            self.as_ref().on_state_changed(state)
+        }
+        fn on_watermark_still_present(&self, result: WatermarkResult, present_for_ms: u64) {
+            self.as_ref().on_watermark_still_present(result, present_for_ms)
+        }
+        fn on_signer_confirmed(&self, signer: String, accumulated_confidence: f32) {
+            self.as_ref().on_signer_confirmed(signer, accumulated_confidence)
+        }
+        fn on_config_changed(&self, old: SonicConfig, new: SonicConfig) {
+            self.as_ref().on_config_changed(old, new)
+        }
+    }
+
+    impl WatermarkCallback for TestCallback {
+        fn on_watermark_detected(&self, _result: WatermarkResult) {
+            self.detections.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_audio_level_changed(&self, level_db: f32) {
+            self.levels.fetch_add(1, Ordering::SeqCst);
+            *self.last_level_db.lock() = Some(level_db);
+        }
+
+        fn on_error(&self, _message: String) {
+            self.errors.fetch_add(1, Ordering::SeqCst);
+        }
+        fn on_state_changed(&self, state: ListenerState) {
+            self.states.lock().push(state);
+        }
+
+        fn on_watermark_still_present(&self, _result: WatermarkResult, present_for_ms: u64) {
+            self.still_present.lock().push(present_for_ms);
+        }
+
+        fn on_signer_confirmed(&self, signer: String, accumulated_confidence: f32) {
+            self.confirmations.lock().push((signer, accumulated_confidence));
+        }
+
+        fn on_config_changed(&self, old: SonicConfig, new: SonicConfig) {
+            self.config_changes.lock().push((old, new));
+        }
+    }
+
+    #[test]
+    fn test_config_default() {
+        let config = SonicConfig::default();
+        assert_eq!(config.sample_rate, 16000);
+        assert_eq!(config.frame_size_ms, 50);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_presets_are_valid_and_tuned() {
+        let presets = [
+            ConfigPreset::Speech,
+            ConfigPreset::Music,
+            ConfigPreset::Broadcast,
+            ConfigPreset::Ultrasonic,
+            ConfigPreset::LowPower,
+        ];
+        for preset in presets {
+            let config = SonicConfig::preset(preset);
+            assert!(config.validate().is_ok(), "{preset:?}");
+            assert_eq!(config_preset(preset), config);
+        }
+        // Music keeps the high layer, which a 16 kHz listener cannot hear.
+        assert!(SonicConfig::preset(ConfigPreset::Music).sample_rate as f32 / 2.0 > dsp::V3_LAYER_BANDS[3].3);
+        assert!(SonicConfig::preset(ConfigPreset::LowPower).pilot_gate);
+        assert_eq!(
+            SonicConfigBuilder::from_preset(ConfigPreset::Broadcast).build(),
+            SonicConfig::preset(ConfigPreset::Broadcast)
+        );
+    }
+
+    // The music threshold drops a decode the defaults take once it needed
+    // nearly half its codewords corrected.
+    #[test]
+    fn test_music_preset_drops_heavily_corrected_decodes() {
+        let weak = with_noise(&embedded_clip(44_100, 7).samples, 102, 5.0);
+        let detects = |config| {
+            let listener = SonicListener::new(config).unwrap();
+            listener.process_buffer(&weak).unwrap().detected
+        };
+        assert!(detects(SonicConfig {
+            sample_rate: 44_100,
+            ..Default::default()
+        }));
+        assert!(!detects(SonicConfig::preset(ConfigPreset::Music)));
+    }
+
+    #[test]
+    fn test_sensitivity_profiles_adjust_detection_only() {
+        let profiles = [
+            SensitivityProfile::Strict,
+            SensitivityProfile::Balanced,
+            SensitivityProfile::Aggressive,
+        ];
+        for profile in profiles {
+            let config = SonicConfig::preset(ConfigPreset::Music).with_sensitivity(profile);
+            assert!(config.validate().is_ok(), "{profile:?}");
+            assert_eq!(config.sample_rate, 44_100);
+            assert_eq!(config.downmix_mode, DownmixMode::MidSide);
+        }
+        let default = SonicConfig::default();
+        assert_eq!(default.clone().with_sensitivity(SensitivityProfile::Balanced), default);
+
+        // Stricter on every axis, in order.
+        let [strict, balanced, aggressive] = profiles.map(|p| default.clone().with_sensitivity(p));
+        assert!(strict.detection_threshold > balanced.detection_threshold);
+        assert!(balanced.detection_threshold > aggressive.detection_threshold);
+        assert!(strict.signer_confirmation_threshold > balanced.signer_confirmation_threshold);
+        assert!(balanced.signer_confirmation_threshold > aggressive.signer_confirmation_threshold);
+        assert!(strict.min_detection_frames > 1 && strict.adaptive_threshold_sigmas.is_some());
+        assert_eq!(
+            Arc::new(SonicConfigBuilder::new()).sensitivity(SensitivityProfile::Strict).build(),
+            strict
+        );
+    }
+
+    #[test]
+    fn test_config_invalid_sample_rate() {
+        let config = SonicConfig {
+            sample_rate: 100, // Too low
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_listener_creation() {
+        let config = SonicConfig::default();
+        let listener = SonicListener::new(config);
+        assert!(listener.is_ok());
+    }
+
+    #[test]
+    fn test_listener_start_stop() {
+        let config = SonicConfig::default();
+        // `start_listening` takes `self: &Arc<Self>` (uniffi callback interface),
+        // so the listener must be held in an Arc; the callback is passed boxed.
+        let listener = Arc::new(SonicListener::new(config).unwrap());
+
+        assert!(listener.start_listening(Box::new(TestCallback::default())).is_ok());
+        assert!(listener.is_listening());
+
+        assert!(listener.stop_listening().is_ok());
+        assert!(!listener.is_listening());
+    }
+
+    #[test]
+    fn test_shutdown_releases_callbacks_and_refuses_work() {
+        let listener = Arc::new(SonicListener::new(SonicConfig::default()).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+        listener.set_detection_store(Some(in_memory_detection_store()));
+        assert_eq!(Arc::strong_count(&callback), 2);
+
+        listener.shutdown();
+        // Events emitted before the shutdown were delivered, then released.
+        assert_eq!(
+            *callback.states.lock(),
+            [ListenerState::Listening, ListenerState::Idle, ListenerState::Closed]
+        );
+        assert_eq!(Arc::strong_count(&callback), 1);
+        assert!(listener.detection_store().is_none());
+        assert_eq!(listener.get_state(), ListenerState::Closed);
+        assert!(!listener.is_listening());
+
+        let samples = vec![0.0; MIN_SAMPLES];
+        assert!(matches!(listener.process_samples(&samples), Err(SonicError::ListenerClosed)));
+        assert!(matches!(listener.stop_listening(), Err(SonicError::ListenerClosed)));
+        assert!(matches!(
+            listener.start_listening(Box::new(callback.clone())),
+            Err(SonicError::ListenerClosed)
+        ));
+        listener.add_callback(Box::new(callback.clone()));
+        assert_eq!(Arc::strong_count(&callback), 1);
+        assert!(listener.push_samples(&samples).is_none());
+        listener.shutdown();
+    }
+
+    #[test]
+    fn test_session_tags_results_and_summarizes() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..SonicConfig::default()
+            })
+            .unwrap(),
+        );
+        assert!(listener.process_buffer(&clip.pcm[..32_000]).unwrap().session_id.is_none());
+
+        let session_id = listener.start_listening(Box::new(TestCallback::default())).unwrap();
+        assert_eq!(session_id.len(), 32);
+        let quiet = listener.process_buffer(&clip.pcm[..32_000]).unwrap();
+        let detected = listener.process_buffer(&clip.pcm).unwrap();
+        listener.process_buffer(&clip.pcm).unwrap();
+        assert_eq!(quiet.session_id.as_deref(), Some(session_id.as_str()));
+        assert_eq!(detected.session_id.as_deref(), Some(session_id.as_str()));
+        // Buffers follow each other in the session's stream.
+        assert_eq!((quiet.frame_index, quiet.stream_offset_ms), (Some(0), Some(0)));
+        assert_eq!((detected.frame_index, detected.stream_offset_ms), (Some(1), Some(16_000 * 1000 / 44_100)));
+
+        let summary = listener.stop_listening().unwrap();
+        assert_eq!(summary.session_id, session_id);
+        assert_eq!(summary.buffers_processed, 3);
+        assert_eq!(summary.unique_signers, [detected.payload_hash.clone().unwrap()]);
+        assert_eq!(summary.peak_confidence, detected.confidence);
+
+        let next = listener.start_listening(Box::new(TestCallback::default())).unwrap();
+        assert_ne!(next, session_id);
+        assert_eq!(listener.stop_listening().unwrap().buffers_processed, 0);
+    }
+
+    #[test]
+    fn test_process_samples() {
+        let config = SonicConfig::default();
+        let listener = SonicListener::new(config).unwrap();
+        
+        // Generate test audio (silence)
+        let samples: Vec<f32> = vec![0.0; 2048];
+        
+        let result = listener.process_samples(&samples);
+        assert!(result.is_ok());
+        assert!(!result.unwrap().detected);
+    }
+
+    #[test]
+    fn test_decode_pcm_formats() {
+        let format = |bits_per_sample, encoding, big_endian| PcmFormat {
+            bits_per_sample,
+            encoding,
+            big_endian,
+            interleaved: true,
+        };
+        let decode = |bytes: &[u8], f| decode_pcm(bytes, &f).unwrap();
+        assert_eq!(decode(&[0x00, 0x40], format(16, SampleEncoding::SignedInt, false)), vec![0.5]);
+        assert_eq!(decode(&[0x40, 0x00], format(16, SampleEncoding::SignedInt, true)), vec![0.5]);
+        assert_eq!(decode(&[0x00, 0x00, 0x80], format(24, SampleEncoding::SignedInt, false)), vec![-1.0]);
+        assert_eq!(decode(&[0xC0, 0x00, 0x00], format(24, SampleEncoding::SignedInt, true)), vec![-0.5]);
+        assert_eq!(decode(&[0, 0, 0, 0xC0], format(32, SampleEncoding::UnsignedInt, false)), vec![0.5]);
+        assert_eq!(decode(&0.25f32.to_be_bytes(), format(32, SampleEncoding::Float, true)), vec![0.25]);
+        assert!(decode_pcm(&[0; 5], &format(16, SampleEncoding::SignedInt, false)).is_err());
+
+        let invalid = |pcm_format| SonicListener::new(SonicConfig {
+            pcm_format: Some(pcm_format),
+            ..Default::default()
+        });
+        assert!(invalid(format(8, SampleEncoding::UnsignedInt, false)).is_err());
+        assert!(invalid(format(16, SampleEncoding::Float, false)).is_err());
+    }
+
+    // A watermark in planar, big-endian float stereo, handed over as the
+    // raw bytes a platform buffer holds.
+    #[test]
+    fn test_process_buffer_honours_pcm_format() {
+        let sr = 44_100u32;
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 8, sr as f32, 71));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkFormat", 1_700_000_000_000).unwrap();
+        let marked = pcm_le16_to_samples(&emb.watermarked_audio);
+        let other = gen_broadband(marked.len(), sr as f32, 72);
+        let bytes: Vec<u8> = marked.iter().chain(&other).flat_map(|s| s.to_be_bytes()).collect();
+
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            channels: 2,
+            downmix_mode: DownmixMode::Left,
+            pcm_format: Some(PcmFormat {
+                bits_per_sample: 32,
+                encoding: SampleEncoding::Float,
+                big_endian: true,
+                interleaved: false,
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        let result = listener.process_buffer(&bytes).unwrap();
+        assert!(result.detected);
+        assert_eq!(result.payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+        assert_eq!(result.channel_index, Some(0));
+    }
+
+    #[test]
+    fn test_process_buffer_rejects_uneven_planar_buffer() {
+        let listener = SonicListener::new(SonicConfig {
+            channels: 2,
+            pcm_format: Some(PcmFormat {
+                interleaved: false,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        // One sample short of two full channel blocks
+        let pcm = samples_to_pcm_le16(&gen_broadband(2 * 44_100 * 8 - 1, 44_100.0, 3));
+        assert!(matches!(
+            listener.process_buffer(&pcm),
+            Err(SonicError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_process_samples_i16_matches_process_buffer() {
+        let sr = 44_100u32;
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        })
+        .unwrap();
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 8, sr as f32, 61));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkShorts", 1_700_000_000_000).unwrap();
+        let shorts: Vec<i16> = emb
+            .watermarked_audio
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+
+        let result = listener.process_samples_i16(&shorts).unwrap();
+        assert!(result.detected);
+        assert_eq!(result.payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+        let buffered = listener.process_buffer(&emb.watermarked_audio).unwrap();
+        assert_eq!(result.confidence, buffered.confidence);
+        assert_eq!(result.audio_quality, buffered.audio_quality);
+        assert!(matches!(
+            listener.process_samples_i16(&[0; 100]),
+            Err(SonicError::BufferTooShort(_))
+        ));
+    }
+
+    #[test]
+    fn test_degenerate_buffers_rejected_with_reason() {
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+
+        let dc = listener.process_samples(&[0.5; 4096]).unwrap();
+        assert!(!dc.detected);
+        assert_eq!(dc.rejection_reason, Some(RejectionReason::ConstantSignal));
+
+        let silence = listener.process_samples(&[0.0; 4096]).unwrap();
+        assert!(!silence.detected);
+        assert_eq!(silence.rejection_reason, Some(RejectionReason::InsufficientSignal));
+        assert_eq!(listener.get_state(), ListenerState::Idle);
+
+        // A real signal passes the guard; disabling the floor lets DC through.
+        let noise = listener.process_samples(&gen_broadband(4096, 16_000.0, 3)).unwrap();
+        assert_eq!(noise.rejection_reason, None);
+        let unguarded = SonicListener::new(SonicConfig {
+            min_signal_variance: 0.0,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(unguarded.process_samples(&[0.5; 4096]).unwrap().rejection_reason, None);
+    }
+
+    // Deterministic broadband host so the v3 masking model has cover energy in
+    // every embedding band (silence gives the watermark nothing to hide under).
+    fn gen_broadband(n: usize, sample_rate: f32, seed: u64) -> Vec<f32> {
+        synth::broadband_cover(n, sample_rate as u32, seed)
+    }
+
+    /// 13 s of `seed`'s broadband cover carrying a watermark, embedded the
+    /// way `dsp::embed` does for a browser or server.
+    fn embedded_clip(sample_rate: u32, seed: u64) -> SynthesizedAudio {
+        Synthesizer::new(SonicConfig {
+            sample_rate,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_seed(seed)
+        .synthesize("did:key:z6MkTestClip", 1_700_000_000_000, 13_000)
+        .unwrap()
+    }
+
+    /// `samples` buried under `seed`'s broadband cover, `gain` times as
+    /// loud, as 16-bit PCM at the original level.
+    fn with_noise(samples: &[f32], seed: u64, gain: f32) -> Vec<u8> {
+        let noise = synth::broadband_cover(samples.len(), 44_100, seed);
+        let mixed: Vec<f32> = samples
+            .iter()
+            .zip(&noise)
+            .map(|(s, n)| (s + n * gain) / (1.0 + gain))
+            .collect();
+        samples_to_pcm_le16(&mixed)
+    }
+
+    // ACCEPTANCE: the FFI `detect_watermark` on a real v3-embedded clip must
+    // report detected=true with a payload_hash (no longer a mock). The clip is
+    // produced by the shared `dsp::embed` — i.e. the same bytes a browser embed
+    // would emit — and the recovered payload_hash must equal the embed's.
+    #[test]
+    fn test_detect_watermark_real_embedded_clip() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+
+        let result = detect_watermark(&clip.pcm, sr);
+        assert!(result.detected, "real v3 detect must find the embedded watermark");
+        assert_eq!(
+            result.payload_hash.as_deref(),
+            Some(clip.payload_hash.as_str()),
+            "recovered payload_hash must equal the embed payload_hash"
+        );
+        assert_eq!(result.detection_method, "chirp_v3");
+        assert!(result.payload_integrity);
+        assert_eq!(result.corrected_symbols, 0, "a clean embed needs no correction");
+        // The mock path is gone: signer_did / timestamp / covenant resolve
+        // server-side from payload_hash, so they are absent here.
+        assert!(result.signer_did.is_none());
+        assert!(result.covenant_json.is_none());
+    }
+
+    // A non-watermarked clip must NOT be detected (negative / false-positive
+    // guard, now that detection is real and CRC-gated).
+    #[test]
+    fn test_detect_watermark_clean_clip_negative() {
+        let sr = 44_100u32;
+        let n = (sr as f32 * 13.0) as usize;
+        let pcm = samples_to_pcm_le16(&gen_broadband(n, sr as f32, 99));
+        let result = detect_watermark(&pcm, sr);
+        assert!(!result.detected, "un-watermarked audio must not be detected");
+        assert!(!result.payload_integrity);
+    }
+
+    // The strict decode names the stage a failed decode stopped at.
+    #[test]
+    fn test_decode_watermark_failure_stages() {
+        let sr = 44_100u32;
+        let n = (sr as f32 * 13.0) as usize;
+        let silence = vec![0u8; n * 2];
+        assert!(matches!(decode_watermark(&silence, sr), Err(SonicError::SyncNotFound)));
+
+        let host = samples_to_pcm_le16(&gen_broadband(n, sr as f32, 99));
+        assert!(matches!(
+            decode_watermark(&host, sr),
+            Err(SonicError::ErrorCorrectionExceeded)
+        ));
+        assert!(matches!(
+            decode_watermark(&host[..1000], sr),
+            Err(SonicError::BufferTooShort(_))
+        ));
+        assert!(matches!(
+            SonicError::from_dsp(dsp::DspError::CrcMismatch, sr),
+            SonicError::CrcMismatch
+        ));
+
+        let clip = embedded_clip(sr, 99);
+        let result = decode_watermark(&clip.pcm, sr).unwrap();
+        assert!(result.detected);
+        assert_eq!(result.payload_hash, Some(clip.payload_hash));
+    }
+
+    // Clipping a watermarked recording just past the configured ratio
+    // leaves a frame that still decodes; the guard alone must reject it.
+    #[test]
+    fn test_hard_clipped_clip_not_detected() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 5);
+        let samples = pcm_le16_to_samples(&clip.pcm);
+
+        // Drive the loudest tenth of the samples to full scale.
+        let mut peaks: Vec<f32> = samples.iter().map(|s| s.abs()).collect();
+        peaks.sort_by(f32::total_cmp);
+        let gain = 1.0 / peaks[peaks.len() * 9 / 10];
+        let clipped: Vec<f32> = samples
+            .iter()
+            .map(|s| (s * gain).clamp(-1.0, 1.0))
+            .collect();
+        assert!(clipping_ratio(&clipped) > DEFAULT_MAX_CLIPPING_RATIO);
+
+        let listen = |max_clipping_ratio| {
+            let listener = SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                max_clipping_ratio,
+                ..Default::default()
+            })
+            .unwrap();
+            listener.process_samples(&clipped).unwrap()
+        };
+
+        let unguarded = listen(1.0);
+        assert!(unguarded.detected, "the clipped frame still decodes");
+        assert_eq!(unguarded.payload_hash, Some(clip.payload_hash));
+
+        let guarded = listen(DEFAULT_MAX_CLIPPING_RATIO);
+        assert!(!guarded.detected, "hard-clipped audio must not be detected");
+        assert!(guarded.payload_integrity);
+        assert_eq!(guarded.detection_method, "clipped");
+        assert_eq!(guarded.rejection_reason, Some(RejectionReason::Clipped));
+    }
+
+    #[test]
+    fn test_clipping_guard_suppresses_detection() {
+        let mut result = WatermarkResult {
+            detected: true,
+            confidence: 0.95,
+            payload_hash: Some("abc".into()),
+            payload_integrity: true,
+            detection_method: "chirp_v3".into(),
+            ..Default::default()
+        };
+        // Below the configured ratio the detection stands untouched.
+        result.apply_clipping_guard(0.01, DEFAULT_MAX_CLIPPING_RATIO);
+        assert!(result.detected);
+        assert_eq!(result.confidence, 0.95);
+
+        result.apply_clipping_guard(0.5, DEFAULT_MAX_CLIPPING_RATIO);
+        assert!(!result.detected);
+        assert!(result.payload_hash.is_none());
+        assert_eq!(result.detection_method, "clipped");
+        assert!(result.payload_integrity, "the frame itself was intact");
+        assert_eq!(result.rejection_reason, Some(RejectionReason::Clipped));
+        assert!((result.confidence - 0.475).abs() < 1e-6);
+    }
+
+    // A decode that needed correcting reports less confidence than a clean
+    // one, and a threshold above it drops it.
+    #[test]
+    fn test_threshold_drops_decodes_that_needed_correction() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let weak = with_noise(&clip.samples, 102, 5.0);
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let clean = listener.process_buffer(&clip.pcm).unwrap();
+        assert!(clean.detected);
+        assert_eq!(clean.corrected_symbols, 0);
+        assert_eq!(clean.confidence, 0.95);
+
+        let marginal = listener.process_buffer(&weak).unwrap();
+        assert!(marginal.detected);
+        assert!(marginal.corrected_symbols > 0);
+        assert!(marginal.confidence < clean.confidence);
+
+        listener.set_detection_threshold(marginal.confidence + 0.01);
+        let dropped = listener.process_buffer(&weak).unwrap();
+        assert!(!dropped.detected && dropped.payload_hash.is_none());
+        assert!(listener.process_buffer(&clip.pcm).unwrap().detected);
+    }
+
+    #[test]
+    fn test_pathological_fft_size_degrades_without_panic() {
+        let callback = Arc::new(TestCallback::default());
+        for bad_size in [0, spectrum::MAX_FFT_SIZE + 1] {
+            let listener = Arc::new(SonicListener::new(SonicConfig::default()).unwrap());
+            // Past validation, to reach the quality stage's own guard.
+            listener.config.write().stft_fft_size = bad_size as u32;
+            listener.start_listening(Box::new(callback.clone())).unwrap();
+
+            let samples = gen_broadband(4096, 16_000.0, 1);
+            let result = listener.process_samples(&samples).unwrap();
+            assert!(result.degraded, "FFT size {bad_size} must degrade");
+            assert!(!result.detected);
+            assert_eq!(listener.get_state(), ListenerState::Listening);
+            listener.flush_events();
+        }
+        assert_eq!(callback.errors.load(Ordering::SeqCst), 2);
+
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+        let result = listener.process_samples(&gen_broadband(4096, 16_000.0, 1)).unwrap();
+        assert!(!result.degraded);
+    }
+
+    // A watermarked clip that ends inside a partial analysis window is only
+    // processed by `flush`; it must be reported, not dropped.
+    #[test]
+    fn test_streamed_sync_passes_through_acquiring() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let listener = Arc::new(SonicListener::new(config.clone()).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        let audio = Synthesizer::new(config)
+            .unwrap()
+            .synthesize("did:key:z6MkAcquire", 1_700_000_000_000, 10_000)
+            .unwrap();
+        let mut stream = gen_broadband(sr as usize * 2, sr as f32, 31);
+        stream.extend(pcm_le16_to_samples(&audio.pcm));
+
+        let mut seen = Vec::new();
+        let mut processed = Vec::new();
+        for chunk in stream.chunks(4410) {
+            processed.extend(listener.push_samples(chunk));
+            seen.push(listener.get_state());
+        }
+        assert_eq!(processed.len(), 1);
+        assert!(processed[0].detected);
+        assert!(seen.contains(&ListenerState::Acquiring));
+        // Locked once the preamble arrived, not on the noise lead.
+        assert_eq!(seen.iter().position(|s| *s == ListenerState::Acquiring), Some(25));
+        listener.flush_events();
+        assert_eq!(
+            *callback.states.lock(),
+            [ListenerState::Listening, ListenerState::Acquiring, ListenerState::Listening]
+        );
+        assert_eq!(listener.get_state(), ListenerState::Listening);
+    }
+
+    #[test]
+    fn test_sync_correlator_planned_once_per_rate() {
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+        let planned = listener.sync_correlator(44_100).unwrap();
+        assert!(Arc::ptr_eq(&planned, &listener.sync_correlator(44_100).unwrap()));
+        assert!(!Arc::ptr_eq(&planned, &listener.sync_correlator(48_000).unwrap()));
+    }
+
+    // Audio processed outside the stream ends an acquisition, and the
+    // return to `Listening` is reported like any other state change.
+    #[test]
+    fn test_process_samples_while_acquiring_reports_listening() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let listener = Arc::new(SonicListener::new(config.clone()).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener
+            .start_listening(Box::new(callback.clone()))
+            .unwrap();
+
+        let audio = Synthesizer::new(config)
+            .unwrap()
+            .synthesize("did:key:z6MkAcquire", 1_700_000_000_000, 10_000)
+            .unwrap();
+        let mut stream = gen_broadband(sr as usize * 2, sr as f32, 31);
+        stream.extend(pcm_le16_to_samples(&audio.pcm));
+        for chunk in stream.chunks(4410) {
+            listener.push_samples(chunk);
+            if listener.get_state() == ListenerState::Acquiring {
+                break;
+            }
+        }
+        assert_eq!(listener.get_state(), ListenerState::Acquiring);
+
+        listener
+            .process_samples(&gen_broadband(sr as usize * 8, sr as f32, 32))
+            .unwrap();
+        assert_eq!(listener.get_state(), ListenerState::Listening);
+        listener.flush_events();
+        assert_eq!(
+            *callback.states.lock(),
+            [
+                ListenerState::Listening,
+                ListenerState::Acquiring,
+                ListenerState::Listening
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_shot_helpers_start_no_dispatcher() {
+        let sr = 16_000u32;
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 2, sr as f32, 3));
+        let spawned = || dispatch::SPAWNED.with(|spawned| spawned.get());
+
+        detect_watermark(&pcm, sr);
+        detect_watermark_timed(&pcm, sr, 1_000);
+        assert_eq!(spawned(), 0);
+
+        // The first event with a subscriber starts the thread, once.
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+        listener.process_buffer(&pcm).unwrap();
+        assert_eq!(spawned(), 0);
+        listener.add_callback(Box::new(Arc::new(TestCallback::default())));
+        listener.process_buffer(&pcm).unwrap();
+        listener.process_buffer(&pcm).unwrap();
+        assert_eq!(spawned(), 1);
+    }
+
+    #[test]
+    fn test_every_subscriber_receives_events() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..SonicConfig::default()
+            })
+            .unwrap(),
+        );
+        let ui = Arc::new(TestCallback::default());
+        let analytics = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(ui.clone())).unwrap();
+        let subscription = listener.add_callback(Box::new(analytics.clone()));
+
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.flush_events();
+        assert_eq!(ui.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(analytics.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(analytics.levels.load(Ordering::SeqCst), 1);
+
+        assert!(listener.remove_callback(subscription));
+        assert!(!listener.remove_callback(subscription));
+        listener.stop_listening().unwrap();
+        listener.flush_events();
+        assert_eq!(*ui.states.lock(), [ListenerState::Listening, ListenerState::Idle]);
+        assert!(analytics.states.lock().is_empty());
+
+        // A restart replaces the first start's callback.
+        let restarted = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(restarted.clone())).unwrap();
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.flush_events();
+        assert_eq!(ui.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(restarted.detections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_filtered_subscribers_see_only_their_events() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            ..SonicConfig::default()
+        })
+        .unwrap();
+        let logger = Arc::new(TestCallback::default());
+        let ui = Arc::new(TestCallback::default());
+        listener.add_callback(Box::new(logger.clone()));
+        let strict = EventFilter {
+            min_confidence: 1.1,
+            events: vec![ListenerEvent::WatermarkDetected],
+            ..Default::default()
+        };
+        let subscription = listener.add_callback_with_filter(Box::new(ui.clone()), strict);
+
+        let detected = listener.process_buffer(&clip.pcm).unwrap();
+        listener.set_detection_threshold(0.6);
+        listener.flush_events();
+        assert_eq!(logger.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(logger.levels.load(Ordering::SeqCst), 1);
+        assert_eq!(logger.config_changes.lock().len(), 1);
+        assert_eq!(ui.detections.load(Ordering::SeqCst), 0);
+        assert_eq!(ui.levels.load(Ordering::SeqCst), 0);
+        assert!(ui.config_changes.lock().is_empty());
+
+        let strong = EventFilter {
+            min_confidence: detected.confidence,
+            events: vec![ListenerEvent::WatermarkDetected],
+            ..Default::default()
+        };
+        assert!(listener.set_callback_filter(subscription, strong));
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.flush_events();
+        assert_eq!(ui.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(ui.levels.load(Ordering::SeqCst), 0);
+
+        let filter = EventFilter {
+            signer_dids: vec!["did:web:other.example".into()],
+            ..Default::default()
+        };
+        assert!(!filter.accepts_detection(ListenerEvent::WatermarkDetected, &detected));
+        assert!(filter.accepts_detection(ListenerEvent::WatermarkDetected, &WatermarkResult {
+            signer_did: Some("did:web:other.example".into()),
+            ..detected
+        }));
+    }
+
+    #[test]
+    fn test_pause_keeps_stream_state_until_resume() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let listener = Arc::new(SonicListener::new(config.clone()).unwrap());
+        assert!(matches!(listener.pause(), Err(SonicError::ListenerNotRunning)));
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        let audio = Synthesizer::new(config)
+            .unwrap()
+            .synthesize("did:key:z6MkPause", 1_700_000_000_000, 10_000)
+            .unwrap();
+        let mut stream = gen_broadband(sr as usize * 2, sr as f32, 31);
+        stream.extend(pcm_le16_to_samples(&audio.pcm));
+
+        let mut processed = Vec::new();
+        let mut paused = false;
+        for chunk in stream.chunks(4410) {
+            processed.extend(listener.push_samples(chunk));
+            if listener.get_state() == ListenerState::Acquiring && !paused {
+                paused = true;
+                // Backgrounded mid-acquisition: what arrives meanwhile is dropped.
+                listener.pause().unwrap();
+                listener.pause().unwrap();
+                assert_eq!(listener.get_state(), ListenerState::Paused);
+                assert!(listener.push_samples(&gen_broadband(sr as usize * 12, sr as f32, 5)).is_none());
+                listener.resume().unwrap();
+                assert_eq!(listener.get_state(), ListenerState::Acquiring);
+            }
+        }
+        assert!(paused);
+        assert_eq!(processed.len(), 1);
+        assert!(processed[0].detected);
+        listener.flush_events();
+        assert_eq!(
+            *callback.states.lock(),
+            [
+                ListenerState::Listening,
+                ListenerState::Acquiring,
+                ListenerState::Paused,
+                ListenerState::Acquiring,
+                ListenerState::Listening
+            ]
+        );
+        listener.resume().unwrap();
+        assert_eq!(listener.get_state(), ListenerState::Listening);
+    }
+
+    #[test]
+    fn test_never_completing_sync_lock_resets_at_payload_bound() {
+        let sr = 44_100u32;
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                max_payload_bytes: Some(2),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        // A bare sync preamble in noise, followed by noise that never carries
+        // a payload.
+        let chirp = dsp::v3_sync_reference(sr);
+        let peak = chirp.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let mut stream = gen_broadband(sr as usize * 9, sr as f32, 33);
+        for (s, c) in stream[sr as usize..].iter_mut().zip(&chirp) {
+            *s += 0.2 * c / peak;
+        }
+
+        // Preamble plus two bytes of chips.
+        let limit = chirp.len() + 2 * 14 * spectrum::chip_len(sr);
+        let mut overflow = None;
+        let mut max_pending = 0;
+        for (i, chunk) in stream.chunks(4410).enumerate() {
+            let result = listener.push_samples(chunk);
+            if let Some(result) = result {
+                assert!(listener.pending.lock().is_empty(), "the assembly is reset");
+                overflow.get_or_insert((i, result));
+            }
+            if overflow.is_none() {
+                max_pending = max_pending.max(listener.pending.lock().len());
+            }
+        }
+        let (at, result) = overflow.expect("the assembly was abandoned");
+        assert!(!result.detected);
+        assert_eq!(result.rejection_reason, Some(RejectionReason::PayloadOverflow));
+        assert_eq!(at, (sr as usize + limit) / 4410);
+        assert!(max_pending <= sr as usize + limit + 4410, "pending grew to {max_pending}");
+        listener.flush_events();
+        assert_eq!(
+            *callback.states.lock(),
+            [ListenerState::Listening, ListenerState::Acquiring, ListenerState::Listening]
+        );
+        assert_eq!(listener.get_state(), ListenerState::Listening);
+    }
+
+    #[test]
+    fn test_flush_surfaces_watermark_in_final_partial_window() {
+        let sr = 44_100u32;
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        // One full window of clean audio, then a shorter watermarked clip
+        // whose preamble lands too late in the next window to be searched.
+        let window = listener.stream_window_len();
+        let lead = gen_broadband(window, sr as f32, 21);
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 6, sr as f32, 22));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkFlush", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+
+        let mut processed = Vec::new();
+        for chunk in lead.chunks(4410).chain(clip.chunks(4410)) {
+            processed.extend(listener.push_samples(chunk));
+        }
+        assert_eq!(processed.len(), 2, "the lead window and one overlapping window complete");
+        assert!(processed.iter().all(|r| !r.detected));
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 0);
+
+        let tail = listener.flush().expect("the watermarked tail is pending");
+        assert!(tail.detected, "flush must surface the tail watermark");
+        assert_eq!(tail.payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1);
+
+        // Nothing left after a flush.
+        assert!(listener.flush().is_none());
+    }
+
+    // A 48 kHz capture fed to a 44.1 kHz listener decodes, whole or streamed.
+    #[test]
+    fn test_listener_resamples_input_rate() {
+        let pcm = samples_to_pcm_le16(&gen_broadband(48_000 * 6, 48_000.0, 23));
+        let emb = dsp::embed(&pcm, 48_000, "did:key:z6MkResample", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: 44_100,
+            input_sample_rate: Some(48_000),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = listener.process_samples(&clip).unwrap();
+        assert!(result.detected);
+        assert_eq!(result.payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+        assert!(listener.process_buffer(&emb.watermarked_audio).unwrap().detected);
+
+        for chunk in clip.chunks(4800) {
+            assert!(listener.push_samples(chunk).is_none());
+        }
+        let streamed = listener.flush().expect("the clip is pending");
+        assert!(streamed.detected);
+        assert_eq!(streamed.payload_hash, result.payload_hash);
+
+        // The minimum length is counted at the input rate.
+        assert!(matches!(
+            listener.process_samples(&clip[..1100]),
+            Err(SonicError::BufferTooShort(1115))
+        ));
+        assert!(SonicListener::new(SonicConfig {
+            input_sample_rate: Some(4_000),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    // A watermark whose preamble falls past the first window's sync search
+    // is decoded, once, by the overlapping window that follows.
+    #[test]
+    fn test_stream_decodes_watermark_across_window_boundary() {
+        let sr = 44_100u32;
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        let lead = gen_broadband(sr as usize * 9, sr as f32, 41);
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 9, sr as f32, 42));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkStraddle", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+
+        // Odd-sized callbacks, as a mic delivers them.
+        let mut processed = Vec::new();
+        for chunk in lead.chunks(1_237).chain(clip.chunks(1_237)) {
+            processed.extend(listener.push_samples(chunk));
+        }
+        let found: Vec<_> = processed.iter().filter(|r| r.detected).collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+        // Windows start a hop apart in the stream.
+        let hop = listener.stream_hop_len() as u64;
+        for (index, result) in processed.iter().enumerate() {
+            assert_eq!(result.frame_index, Some(index as u64));
+            assert_eq!(result.stream_offset_ms, Some(index as u64 * hop * 1000 / u64::from(sr)));
+        }
+
+        listener.flush();
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1, "reported once");
+    }
+
+    // With the pilot gate on, windows without the sync chirp are skipped
+    // undecoded, while the window holding the preamble still decodes.
+    #[test]
+    fn test_pilot_gate_skips_windows_without_sync() {
+        let sr = 44_100u32;
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            pilot_gate: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let lead = gen_broadband(sr as usize * 9, sr as f32, 51);
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 9, sr as f32, 52));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkGate", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+
+        let mut processed = Vec::new();
+        for chunk in lead.chunks(4_096).chain(clip.chunks(4_096)) {
+            processed.extend(listener.push_samples(chunk));
+        }
+        let found: Vec<_> = processed.iter().filter(|r| r.detected).collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+
+        let stats = listener.stats();
+        assert!(stats.pilot_skips >= 1, "{stats:?}");
+        assert!(stats.dsp_calls < processed.len() as u64, "{stats:?}");
+    }
+
+    #[test]
+    fn test_adaptive_threshold_tracks_the_noise_floor() {
+        let sr = 44_100u32;
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            adaptive_threshold_sigmas: Some(4.0),
+            ..Default::default()
+        })
+        .unwrap();
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 9, sr as f32, 61));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkFloor", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+
+        // Unmarked audio sets the floor; the watermark clears it easily.
+        for seed in 0..noise_floor::NOISE_FLOOR_MIN_BUFFERS as u64 {
+            assert_eq!(listener.stats().adaptive_threshold, None);
+            assert!(!listener.process_samples(&gen_broadband(sr as usize, sr as f32, seed)).unwrap().detected);
+        }
+        let threshold = listener.stats().adaptive_threshold.unwrap();
+        assert!(threshold < SYNC_LOCK_CORRELATION as f32, "{threshold}");
+        assert!(listener.process_samples(&clip).unwrap().detected);
+
+        // A floor as high as the watermark's own correlation drops it.
+        listener.noise_floor.lock().clear();
+        for _ in 0..noise_floor::NOISE_FLOOR_MIN_BUFFERS {
+            listener.noise_floor.lock().record(1.0);
+        }
+        let result = listener.process_samples(&clip).unwrap();
+        assert!(!result.detected && result.payload_hash.is_none());
+
+        assert!(SonicConfig {
+            adaptive_threshold_sigmas: Some(0.0),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    // Resample by `1 + ppm * 1e-6` with linear interpolation: the clip comes
+    // out stretched (ppm > 0) or squeezed, as a drifting capture clock would.
+    fn resample_ppm(samples: &[f32], ppm: f64) -> Vec<f32> {
+        let ratio = 1.0 + ppm * 1e-6;
+        let len = ((samples.len() - 1) as f64 * ratio) as usize;
+        (0..len)
+            .map(|i| {
+                let pos = i as f64 / ratio;
+                let (j, frac) = (pos as usize, (pos - pos.floor()) as f32);
+                samples[j] * (1.0 - frac) + samples[(j + 1).min(samples.len() - 1)] * frac
+            })
+            .collect()
+    }
+
+    // A timed scan over a drifted recording locates the watermark after its
+    // clean lead-in and reports the drift with the right sign and size.
+    #[test]
+    fn test_timed_scan_reports_clock_drift() {
+        let sr = 44_100u32;
+        let pcm = samples_to_pcm_le16(&gen_broadband(sr as usize * 20, sr as f32, 31));
+        let emb = dsp::embed(&pcm, sr, "did:key:z6MkDrift", 1_700_000_000_000).unwrap();
+        let clip = pcm_le16_to_samples(&emb.watermarked_audio);
+        let lead = gen_broadband(sr as usize * 2, sr as f32, 32);
+
+        // Coherent v3 decoding only holds up to a few tens of ppm, which
+        // covers real device clocks.
+        for ppm in [20.0, -20.0] {
+            let mut recording = lead.clone();
+            recording.extend(resample_ppm(&clip, ppm));
+            let scan = detect_watermark_timed(&samples_to_pcm_le16(&recording), sr, 8_000);
+
+            assert_eq!(scan.len(), 1, "one watermark at {ppm} ppm");
+            let found = &scan[0];
+            assert!(found.result.detected);
+            assert_eq!(found.result.payload_hash.as_deref(), Some(emb.payload_hash.as_str()));
+            assert!(found.offset_ms.abs_diff(2000) <= 5, "offset {} ms", found.offset_ms);
+            assert_eq!(found.echo_paths, 0);
+            let drift = found.drift_ppm.expect("repetitions should be tracked") as f64;
+            assert!((drift - ppm).abs() < 5.0, "measured {drift} ppm for {ppm} ppm");
+        }
+    }
+
+    #[test]
+    fn test_finer_search_step_finds_late_preamble() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let audio = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkStep", 1_700_000_000_000, 10_000)
+            .unwrap();
+        // The preamble starts 6 s in, past the sync search at the start of the
+        // first window.
+        let mut recording = samples_to_pcm_le16(&gen_broadband(sr as usize * 6, sr as f32, 3));
+        recording.extend(&audio.pcm);
+
+        let scan = |step_s: u32| {
+            let listener = SonicListener::new(SonicConfig {
+                correlation_search_step: Some(sr * step_s),
+                ..config.clone()
+            })
+            .unwrap();
+            listener.detect_timed(&recording, 10_000)
+        };
+        assert!(scan(8).is_empty(), "an 8 s step jumps over the preamble");
+        let found = scan(2);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].result.payload_hash.as_deref(), Some(audio.payload_hash.as_str()));
+        assert!(found[0].offset_ms.abs_diff(6_000) < 50, "offset {}", found[0].offset_ms);
+
+        assert!(SonicListener::new(SonicConfig {
+            correlation_search_step: Some(0),
+            ..config
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_timed_scan_cancellation_keeps_partial_results() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let audio = Synthesizer::new(config)
+            .unwrap()
+            .synthesize("did:key:z6MkCancel", 1_700_000_000_000, 8_000)
+            .unwrap();
+        let head = audio.pcm.clone();
+        let started = Instant::now();
+        assert_eq!(detect_watermark_timed(&head, sr, 8_000).len(), 1);
+        let head_time = started.elapsed();
+
+        // The watermark, then an unmarked tail that takes several times as
+        // long to scan; cancel while the scan is in the tail.
+        let mut long = audio.pcm;
+        long.extend(samples_to_pcm_le16(&gen_broadband(sr as usize * 24, sr as f32, 42)));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(head_time * 2);
+                cancel.store(true, Ordering::SeqCst);
+            })
+        };
+        let scan = detect_watermark_timed_cancellable(&long, sr, 8_000, &cancel);
+        canceller.join().unwrap();
+
+        // Set only after the scan started, so `cancelled` means it stopped
+        // short of the end.
+        assert!(scan.cancelled);
+        assert_eq!(scan.detections.len(), 1, "detections before the cancel are kept");
+        assert_eq!(scan.detections[0].result.payload_hash, Some(audio.payload_hash));
+
+        let done = detect_watermark_timed_cancellable(&head, sr, 8_000, &AtomicBool::new(false));
+        assert!(!done.cancelled);
+        assert_eq!(done.detections.len(), 1);
+    }
+
+    // Records every progress report; cancels once `stop_after` detections
+    // have been reported.
+    struct TestProgress {
+        reports: Arc<Mutex<Vec<(f32, u32, u64)>>>,
+        stop_after: Option<u32>,
+    }
+
+    impl ProgressCallback for TestProgress {
+        fn on_progress(&self, percent_done: f32, detections: u32, position_ms: u64) -> bool {
+            self.reports.lock().push((percent_done, detections, position_ms));
+            self.stop_after.is_none_or(|n| detections < n)
+        }
+    }
+
+    #[test]
+    fn test_timed_scan_reports_progress() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let audio = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkProgress", 1_700_000_000_000, 8_000)
+            .unwrap();
+        let mut recording = audio.pcm;
+        recording.extend(samples_to_pcm_le16(&gen_broadband(sr as usize * 12, sr as f32, 46)));
         let listener = SonicListener::new(config).unwrap();
-        
-        // Generate test audio (silence)
-        let samples: Vec<f32> = vec![0.0; 2048];
-        
-        let result = listener.process_samples(&samples);
-        assert!(result.is_ok());
-        assert!(!result.unwrap().detected);
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = TestProgress {
+            reports: reports.clone(),
+            stop_after: None,
+        };
+        let scan = listener.detect_timed_with_progress(&recording, 8_000, Box::new(progress));
+        assert!(!scan.cancelled);
+        assert_eq!(scan.detections.len(), 1);
+        let reports = reports.lock().clone();
+        assert!(reports.len() > 2);
+        assert_eq!(reports[0], (0.0, 0, 0));
+        assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0 && w[0].2 <= w[1].2));
+        assert_eq!(*reports.last().unwrap(), (100.0, 1, 20_000));
+
+        // Returning false stops the scan once the watermark has been found.
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let progress = TestProgress {
+            reports: reports.clone(),
+            stop_after: Some(1),
+        };
+        let scan = listener.detect_timed_with_progress(&recording, 8_000, Box::new(progress));
+        assert!(scan.cancelled);
+        assert_eq!(scan.detections.len(), 1);
+        assert!(reports.lock().last().unwrap().0 < 100.0);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_scan_matches_sequential_scan() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let synth = Synthesizer::new(config.clone()).unwrap();
+        let mut recording = gen_broadband(sr as usize * 3, sr as f32, 43);
+        for (did, gap_seed) in [("did:key:z6MkParallelA", 44), ("did:key:z6MkParallelB", 45)] {
+            let audio = synth.synthesize(did, 1_700_000_000_000, 8_000).unwrap();
+            recording.extend(audio.samples);
+            recording.extend(gen_broadband(sr as usize * 5, sr as f32, gap_seed));
+        }
+        let listener = SonicListener::new(config).unwrap();
+
+        let sequential = listener.detect_timed(&samples_to_pcm_le16(&recording), 8_000);
+        let parallel = listener.process_samples_parallel(&recording, 8_000).unwrap();
+        assert_eq!(sequential.len(), 2);
+        assert_eq!(parallel.len(), sequential.len());
+        for (p, s) in parallel.iter().zip(&sequential) {
+            assert_eq!(p.offset_ms, s.offset_ms);
+            assert_eq!(p.echo_paths, s.echo_paths);
+            assert_eq!(p.drift_ppm, s.drift_ppm);
+            assert_eq!(p.result.payload_hash, s.result.payload_hash);
+        }
+    }
+
+    // Direct sound at 1 s plus a reflection 25 ms later: the scan reports the
+    // direct path's offset and counts the echo, even when the reflection is
+    // the stronger arrival.
+    #[test]
+    fn test_timed_scan_reports_direct_path_under_echo() {
+        let sr = 44_100u32;
+        let config = SonicConfig {
+            sample_rate: sr,
+            ..Default::default()
+        };
+        let audio = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkEcho", 1_700_000_000_000, 8_000)
+            .unwrap();
+        let lead = sr as usize;
+        let delay = sr as usize * 25 / 1000;
+        let listener = SonicListener::new(config).unwrap();
+
+        for (direct_gain, echo_gain) in [(1.0, 0.5), (0.6, 0.9)] {
+            let mut recording = gen_broadband(lead, sr as f32, 41);
+            recording.resize(lead + audio.samples.len() + delay, 0.0);
+            for (i, &s) in audio.samples.iter().enumerate() {
+                recording[lead + i] += s * direct_gain;
+                recording[lead + delay + i] += s * echo_gain;
+            }
+            let scan = listener.detect_timed(&samples_to_pcm_le16(&recording), 10_000);
+
+            assert_eq!(scan.len(), 1);
+            assert_eq!(scan[0].result.payload_hash.as_deref(), Some(audio.payload_hash.as_str()));
+            assert!(scan[0].offset_ms.abs_diff(1000) <= 1, "offset {} ms", scan[0].offset_ms);
+            assert_eq!(scan[0].echo_paths, 1, "gains {direct_gain}/{echo_gain}");
+        }
+    }
+
+    #[test]
+    fn test_synthesizer_round_trip() {
+        let config = SonicConfig {
+            sample_rate: 44_100,
+            ..Default::default()
+        };
+        let synth = Synthesizer::new(config.clone()).unwrap();
+        let audio = synth.synthesize("did:key:z6MkSynth", 1_700_000_000_000, 8_000).unwrap();
+        assert_eq!(audio.samples.len(), 44_100 * 8);
+
+        let again = synth.synthesize("did:key:z6MkSynth", 1_700_000_000_000, 8_000).unwrap();
+        assert_eq!(again.pcm, audio.pcm, "same inputs must give the same audio");
+        let reseeded = synth.clone().with_seed(2).synthesize("did:key:z6MkSynth", 1_700_000_000_000, 8_000);
+        assert_ne!(reseeded.unwrap().pcm, audio.pcm);
+
+        let listener = SonicListener::new(config).unwrap();
+        let result = listener.process_samples(&audio.samples).unwrap();
+        assert!(result.detected);
+        assert_eq!(result.payload_hash, Some(audio.payload_hash));
+
+        // The v3 embedder needs the top layer below Nyquist.
+        assert!(matches!(
+            Synthesizer::new(SonicConfig::default()),
+            Err(SonicError::InvalidSampleRate(16_000))
+        ));
+    }
+
+    #[test]
+    fn test_embedder_round_trip_verifies() {
+        let config = SonicConfig {
+            sample_rate: 44_100,
+            ..Default::default()
+        };
+        let key = [9u8; 32];
+        let did = did_from_public_key(&ed25519_dalek::SigningKey::from_bytes(&key).verifying_key().to_bytes()).unwrap();
+        let embedder = SonicEmbedder::new(config.clone(), key.to_vec()).unwrap();
+        let cover = gen_broadband(44_100 * 8, 44_100.0, 5);
+        let covenant = r#"{"permissions":{"ai_training":false}}"#;
+        let embedded = embedder
+            .embed_samples(&cover, &did, Some(covenant.into()), 1_700_000_000)
+            .unwrap();
+        assert_eq!(embedded.samples.len(), cover.len());
+
+        let detected = SonicListener::new(config.clone())
+            .unwrap()
+            .process_samples(&embedded.samples)
+            .unwrap();
+        assert!(detected.detected);
+        assert_eq!(detected.payload_hash.as_deref(), Some(embedded.payload_hash.as_str()));
+
+        // What the server resolves the payload hash to.
+        let resolved = WatermarkResult {
+            signer_did: Some(embedded.signer_did.clone()),
+            timestamp: Some(embedded.timestamp),
+            covenant_json: embedded.covenant_json.clone(),
+            signature: Some(embedded.signature.clone()),
+            ..detected.clone()
+        };
+        let verified = SignatureVerifier::new().verify_watermark_payload(resolved);
+        assert!(verified.valid, "{:?}", verified.error_message);
+        assert_eq!(verified.signer_did, Some(did.clone()));
+
+        // The frame carries the same signed record.
+        let frame = parse_frame(&embedded.frame).unwrap();
+        let public_key = public_key_from_did(&did).unwrap();
+        assert_eq!(frame.signature.as_deref(), Some(embedded.signature.as_slice()));
+        let signer = SignatureVerifier::new().verify(&frame.payload, &embedded.signature, &public_key);
+        assert_eq!(signer.unwrap(), did);
+
+        // Or straight from the detection and the frame its hash resolved to.
+        let verified = SignatureVerifier::new().verify_watermark_frame(detected.clone(), &embedded.frame);
+        assert!(verified.valid, "{:?}", verified.error_message);
+        assert_eq!(verified.signer_did, Some(did.clone()));
+        let unsigned = encode_frame(&frame.payload, None).unwrap();
+        assert!(!SignatureVerifier::new().verify_watermark_frame(detected.clone(), &unsigned).valid);
+        let mut tampered = embedded.frame.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(!SignatureVerifier::new().verify_watermark_frame(detected.clone(), &tampered).valid);
+        let elsewhere = WatermarkResult {
+            payload_hash: Some("0".repeat(64)),
+            ..detected
+        };
+        let verified = SignatureVerifier::new().verify_watermark_frame(elsewhere, &embedded.frame);
+        assert_eq!(verified.error_message.as_deref(), Some("Frame is for a different watermark"));
+
+        let other = did_from_public_key(&[1u8; 32]).unwrap();
+        assert!(matches!(
+            embedder.embed_samples(&cover, &other, None, 1_700_000_000),
+            Err(SonicError::InvalidConfig(_))
+        ));
+        assert!(embedder.embed_samples(&cover, &did, Some("[1]".into()), 1_700_000_000).is_err());
+        assert!(matches!(
+            embedder.embed_samples(&cover[..1000], &did, None, 1_700_000_000),
+            Err(SonicError::BufferTooShort(_))
+        ));
+        assert!(SonicEmbedder::new(config, vec![0; 31]).is_err());
+        assert!(matches!(
+            SonicEmbedder::new(SonicConfig::default(), key.to_vec()),
+            Err(SonicError::InvalidSampleRate(16_000))
+        ));
+    }
+
+    #[test]
+    fn test_config_snapshot_tracks_setters() {
+        let listener = SonicListener::new(SonicConfig {
+            collect_config_snapshot: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let buffer = gen_broadband(2048, 16_000.0, 8);
+
+        let before = listener.process_samples(&buffer).unwrap();
+        assert_eq!(before.config_snapshot.unwrap().detection_threshold, DEFAULT_THRESHOLD);
+
+        listener.set_detection_threshold(0.8);
+        let after = listener.process_samples(&buffer).unwrap();
+        assert_eq!(after.config_snapshot.unwrap().detection_threshold, 0.8);
+        let rejected = listener.process_samples(&[0.0; 2048]).unwrap();
+        assert!(rejected.rejection_reason.is_some());
+        assert_eq!(rejected.config_snapshot.unwrap().detection_threshold, 0.8);
+
+        let plain = SonicListener::new(SonicConfig::default()).unwrap();
+        assert!(plain.process_samples(&buffer).unwrap().config_snapshot.is_none());
+    }
+
+    #[test]
+    fn test_level_callback_rate_cap() {
+        let buffer = gen_broadband(2048, 16_000.0, 5);
+        let feed = |max_level_callback_hz: u32| {
+            let listener = Arc::new(
+                SonicListener::new(SonicConfig {
+                    max_level_callback_hz,
+                    ..Default::default()
+                })
+                .unwrap(),
+            );
+            let callback = Arc::new(TestCallback::default());
+            listener.start_listening(Box::new(callback.clone())).unwrap();
+            let started = Instant::now();
+            for _ in 0..40 {
+                listener.process_samples(&buffer).unwrap();
+            }
+            listener.flush_events();
+            (callback.levels.load(Ordering::SeqCst), started.elapsed())
+        };
+
+        let (uncapped, _) = feed(0);
+        assert_eq!(uncapped, 40);
+
+        let (capped, elapsed) = feed(5);
+        let allowed = (elapsed.as_secs_f64() * 5.0).floor() as u32 + 1;
+        assert!(capped >= 1 && capped <= allowed, "{capped} level callbacks in {elapsed:?}");
+    }
+
+    #[test]
+    fn test_mid_side_downmix_finds_difference_watermark() {
+        let config = SonicConfig {
+            sample_rate: 44_100,
+            ..Default::default()
+        };
+        let marked = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkSide", 1_700_000_000_000, 8_000)
+            .unwrap();
+        let program = gen_broadband(marked.samples.len(), 44_100.0, 9);
+        // L = (P + W) / 2, R = (P - W) / 2: the watermarked signal W lives
+        // only in L - R.
+        let stereo: Vec<f32> = program
+            .iter()
+            .zip(&marked.samples)
+            .flat_map(|(p, w)| [(p + w) / 2.0, (p - w) / 2.0])
+            .collect();
+
+        let detect = |downmix_mode| {
+            SonicListener::new(SonicConfig {
+                downmix_mode,
+                ..config.clone()
+            })
+            .unwrap()
+            .process_interleaved(&stereo, 2)
+            .unwrap()
+        };
+        assert!(!detect(DownmixMode::Average).detected, "the average cancels the side signal");
+        let side = detect(DownmixMode::MidSide);
+        assert!(side.detected);
+        assert_eq!(side.payload_hash, Some(marked.payload_hash));
+
+        let listener = SonicListener::new(config).unwrap();
+        assert!(matches!(listener.process_interleaved(&stereo, 0), Err(SonicError::InvalidConfig(_))));
+        assert!(matches!(
+            listener.process_interleaved(&stereo[..2000], 2),
+            Err(SonicError::BufferTooShort(_))
+        ));
+    }
+
+    #[test]
+    fn test_best_channel_reports_the_marked_channel() {
+        let config = SonicConfig {
+            sample_rate: 44_100,
+            channels: 2,
+            downmix_mode: DownmixMode::BestChannel,
+            ..Default::default()
+        };
+        let marked = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkRight", 1_700_000_000_000, 8_000)
+            .unwrap();
+        // Only the right channel carries the watermark.
+        let left = gen_broadband(marked.samples.len(), 44_100.0, 17);
+        let stereo: Vec<f32> = left.iter().zip(&marked.samples).flat_map(|(l, r)| [*l, *r]).collect();
+
+        let listener = SonicListener::new(config.clone()).unwrap();
+        let best = listener.process_samples(&stereo).unwrap();
+        assert!(best.detected);
+        assert_eq!(best.channel_index, Some(1));
+        assert_eq!(best.payload_hash, Some(marked.payload_hash.clone()));
+
+        // The same channels handed over planar.
+        let planar = listener.process_planar(&[left.as_slice(), marked.samples.as_slice()]).unwrap();
+        assert_eq!(planar.channel_index, Some(1));
+        assert_eq!(planar.payload_hash, best.payload_hash);
+        assert!(matches!(
+            listener.process_planar(&[&left[..], &marked.samples[1..]]),
+            Err(SonicError::InvalidConfig(_))
+        ));
+
+        let left_only = SonicListener::new(SonicConfig {
+            downmix_mode: DownmixMode::Left,
+            ..config.clone()
+        })
+        .unwrap()
+        .process_samples(&stereo)
+        .unwrap();
+        assert!(!left_only.detected);
+        assert_eq!(left_only.channel_index, Some(0));
+
+        // Mono input carries no channel index.
+        let mono = SonicListener::new(SonicConfig { channels: 1, ..config.clone() }).unwrap();
+        assert_eq!(mono.process_samples(&marked.samples).unwrap().channel_index, None);
+        assert!(SonicListener::new(SonicConfig { channels: 0, ..config }).is_err());
+    }
+
+    #[test]
+    fn test_downmix_channel_selection() {
+        let frames = [0.1, 0.5, 0.3, 0.9];
+        assert_eq!(downmix(&frames, 2, DownmixMode::Left).0, vec![0.1, 0.3]);
+        assert_eq!(downmix(&frames, 2, DownmixMode::Right).0, vec![0.5, 0.9]);
+        let (mid, side) = downmix(&frames, 2, DownmixMode::MidSide);
+        assert!((mid[0] - 0.3).abs() < 1e-6 && (mid[1] - 0.6).abs() < 1e-6);
+        let side = side.unwrap();
+        assert!((side[0] + 0.2).abs() < 1e-6 && (side[1] + 0.3).abs() < 1e-6);
+        // Mono input passes through whatever the mode.
+        assert_eq!(downmix(&frames, 1, DownmixMode::Right), (frames.to_vec(), None));
+    }
+
+    #[test]
+    fn test_flags_audio_sampled_above_the_configured_rate() {
+        // Speech-like host: harmonics of 150 Hz up to 2 kHz, falling off,
+        // captured at 48 kHz.
+        let speech: Vec<f32> = (0..48_000 * 2)
+            .map(|i| {
+                let t = i as f32 / 48_000.0;
+                (1..=13)
+                    .map(|h| (std::f32::consts::TAU * 150.0 * h as f32 * t).sin() / (h as f32).sqrt())
+                    .sum::<f32>()
+                    * 0.1
+            })
+            .collect();
+        let at_rate = |sample_rate| {
+            SonicListener::new(SonicConfig {
+                sample_rate,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        assert!(!at_rate(48_000).process_samples(&speech).unwrap().sample_rate_mismatch);
+
+        // Read at 16 kHz the harmonics land at 50 - 650 Hz, below every band.
+        let listener = Arc::new(at_rate(16_000));
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+        assert!(listener.process_samples(&speech).unwrap().sample_rate_mismatch);
+        listener.flush_events();
+        assert_eq!(callback.errors.load(Ordering::SeqCst), 1);
+
+        // Broadband audio at its own rate is never flagged.
+        let broadband = gen_broadband(16_000, 16_000.0, 2);
+        assert!(!listener.process_samples(&broadband).unwrap().sample_rate_mismatch);
+    }
+
+    #[test]
+    fn test_consume_processes_channel_until_closed() {
+        let config = SonicConfig {
+            sample_rate: 44_100,
+            ..Default::default()
+        };
+        let audio = Synthesizer::new(config.clone())
+            .unwrap()
+            .synthesize("did:key:z6MkConsume", 1_700_000_000_000, 8_000)
+            .unwrap();
+        let listener = Arc::new(SonicListener::new(config).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let producer = std::thread::spawn(move || {
+            tx.send(gen_broadband(44_100, 44_100.0, 7)).unwrap();
+            tx.send(audio.samples).unwrap();
+            tx.send(vec![0.0; 10]).unwrap();
+            tx.send(gen_broadband(44_100, 44_100.0, 8)).unwrap();
+        });
+        listener.clone().consume(rx);
+        producer.join().unwrap();
+
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1);
+        assert_eq!(callback.errors.load(Ordering::SeqCst), 1, "short buffer is reported");
+        assert_eq!(callback.levels.load(Ordering::SeqCst), 3);
+
+        // Stopping ends the loop even while the sender is still alive.
+        let (tx, rx) = std::sync::mpsc::channel::<Vec<f32>>();
+        listener.stop_listening().unwrap();
+        listener.consume(rx);
+        drop(tx);
     }
 
-    // Deterministic broadband host so the v3 masking model has cover energy in
-    // every embedding band (silence gives the watermark nothing to hide under).
-    fn gen_broadband(n: usize, sample_rate: f32, seed: u64) -> Vec<f32> {
-        let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
-        let mut next = || {
-            state ^= state << 13;
-            state ^= state >> 7;
-            state ^= state << 17;
-            state
-        };
-        let mut unit = || (next() >> 40) as f32 / (1u64 << 24) as f32;
-        let parts: Vec<(f32, f32)> = (0..64)
-            .map(|_| {
-                let f = 150.0 + unit() * (20_000.0 - 150.0);
-                let p = unit() * std::f32::consts::TAU;
-                (f, p)
+    #[test]
+    fn test_marginal_detections_confirm_signer() {
+        let listener = Arc::new(SonicListener::new(SonicConfig::default()).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+        let marginal = |signer: &str| WatermarkResult {
+            detected: true,
+            confidence: 0.35,
+            payload_hash: Some("a1b2c3d4".into()),
+            signer_did: Some(signer.into()),
+            ..Default::default()
+        };
+
+        // Each frame is below the 0.5 detection threshold; together they
+        // pass the 2.0 confirmation threshold on the sixth.
+        for frame in 1..=5 {
+            listener.accumulate_signer_evidence(&marginal("did:key:z6MkAlice"), 2.0);
+            listener.flush_events();
+            assert!(callback.confirmations.lock().is_empty(), "confirmed after {frame} frames");
+        }
+        listener.accumulate_signer_evidence(&marginal("did:key:z6MkBob"), 2.0);
+        listener.accumulate_signer_evidence(&marginal("did:key:z6MkAlice"), 2.0);
+        listener.accumulate_signer_evidence(&marginal("did:key:z6MkAlice"), 2.0);
+
+        listener.flush_events();
+        let confirmations = callback.confirmations.lock().clone();
+        assert_eq!(confirmations.len(), 1, "confirmation fires once");
+        assert_eq!(confirmations[0].0, "did:key:z6MkAlice");
+        assert!((confirmations[0].1 - 2.1).abs() < 1e-4);
+
+        let evidence = listener.signer_evidence();
+        assert_eq!(evidence.len(), 2);
+        assert_eq!(evidence[0].signer, "did:key:z6MkAlice");
+        assert_eq!(evidence[0].frames, 7);
+        assert!(evidence[0].confirmed);
+        assert_eq!(evidence[1].frames, 1);
+        assert!(!evidence[1].confirmed);
+
+        // Undecoded frames and frames without a signer add nothing.
+        listener.accumulate_signer_evidence(&WatermarkResult::not_detected(), 2.0);
+        listener.accumulate_signer_evidence(&WatermarkResult { detected: true, ..Default::default() }, 2.0);
+        assert_eq!(listener.signer_evidence().len(), 2);
+
+        listener.reset_signer_evidence();
+        assert!(listener.signer_evidence().is_empty());
+    }
+
+    // Decoded frames below the detection threshold are not reported, but
+    // add their own, lower confidence to the signer's evidence.
+    #[test]
+    fn test_frames_below_threshold_add_their_confidence() {
+        let sr = 44_100u32;
+        let weak = with_noise(&embedded_clip(sr, 7).samples, 102, 5.0);
+        let confidence = detect_watermark(&weak, sr).confidence;
+        assert!(confidence > 0.0 && confidence < 0.7);
+
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                detection_threshold: 0.7,
+                signer_confirmation_threshold: 1.0,
+                ..Default::default()
             })
-            .collect();
-        (0..n)
-            .map(|i| {
-                let t = i as f32 / sample_rate;
-                let mut s = 0.0_f32;
-                for (f, p) in &parts {
-                    s += (std::f32::consts::TAU * f * t + p).sin();
-                }
-                (s / parts.len() as f32 * 0.6).clamp(-1.0, 1.0)
+            .unwrap(),
+        );
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+        for _ in 0..2 {
+            assert!(!listener.process_buffer(&weak).unwrap().detected);
+        }
+
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 0);
+        let evidence = listener.signer_evidence();
+        assert_eq!(evidence[0].frames, 2);
+        assert!((evidence[0].accumulated_confidence - 2.0 * confidence).abs() < 1e-5);
+        let confirmations = callback.confirmations.lock().clone();
+        assert_eq!(confirmations.len(), 1);
+        assert_eq!(confirmations[0].1, evidence[0].accumulated_confidence);
+    }
+
+    #[test]
+    fn test_silence_reports_level_floor() {
+        let silence = vec![0.0f32; 2048];
+        let level_for = |config: SonicConfig| {
+            let listener = Arc::new(SonicListener::new(config).unwrap());
+            let callback = Arc::new(TestCallback::default());
+            listener.start_listening(Box::new(callback.clone())).unwrap();
+            listener.process_samples(&silence).unwrap();
+            listener.flush_events();
+            let level = *callback.last_level_db.lock();
+            level.unwrap()
+        };
+
+        assert_eq!(level_for(SonicConfig::default()), -90.0);
+        let custom = SonicConfig {
+            level_floor_db: -60.0,
+            ..Default::default()
+        };
+        assert_eq!(level_for(custom), -60.0);
+
+        // Audible signal is reported unclamped.
+        let tone = vec![0.5f32; 2048];
+        assert!((level_db(&tone, -90.0) - 20.0 * 0.5f32.log10()).abs() < 1e-4);
+
+        let invalid = SonicConfig {
+            level_floor_db: f32::NEG_INFINITY,
+            ..Default::default()
+        };
+        assert!(SonicListener::new(invalid).is_err());
+    }
+
+    // Counts heap allocations made by the current thread, so tests running in
+    // parallel don't see each other's.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+            std::alloc::System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(|n| n.get());
+        f();
+        ALLOCATIONS.with(|n| n.get()) - before
+    }
+
+    #[test]
+    fn test_warm_up_moves_fft_planning_out_of_first_buffer() {
+        let samples = gen_broadband(4096, 16_000.0, 6);
+        let first_and_steady = |listener: &SonicListener| {
+            let first = allocations_during(|| drop(listener.process_samples(&samples)));
+            let steady = allocations_during(|| drop(listener.process_samples(&samples)));
+            (first, steady)
+        };
+        // Settle any process-wide lazy initialization first.
+        first_and_steady(&SonicListener::new(SonicConfig::default()).unwrap());
+
+        let (cold, steady) = first_and_steady(&SonicListener::new(SonicConfig::default()).unwrap());
+        assert!(cold > steady, "cold first buffer: {cold} allocations, steady: {steady}");
+
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+        listener.warm_up();
+        let (warm, steady) = first_and_steady(&listener);
+        assert_eq!(warm, steady);
+    }
+
+    #[test]
+    fn test_quality_stage_reuses_its_buffers() {
+        let samples = gen_broadband(16_000, 16_000.0, 7);
+        let config = SonicConfig::default();
+        let plans = spectrum::FftPlans::default();
+        let mut scratch = spectrum::StftScratch::default();
+        let mut quality = || {
+            spectrum::estimate_quality(
+                &plans,
+                &mut scratch,
+                &samples,
+                spectrum::QUALITY_FFT_SIZE,
+                &config,
+                &SpectralBalanceEstimator,
+            )
+            .unwrap()
+        };
+        let first = quality();
+        let mut steady = 0.0;
+        assert_eq!(allocations_during(|| steady = quality()), 0);
+        assert_eq!(steady, first);
+    }
+
+    #[test]
+    fn test_config_changes_are_reported() {
+        let listener = Arc::new(SonicListener::new(SonicConfig::default()).unwrap());
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        listener.set_detection_threshold(0.7);
+        listener.flush_events();
+        {
+            let changes = callback.config_changes.lock();
+            assert_eq!(changes.len(), 1);
+            assert_eq!(changes[0].0.detection_threshold, DEFAULT_THRESHOLD);
+            assert_eq!(changes[0].1.detection_threshold, 0.7);
+            assert_eq!(changes[0].1, listener.get_config());
+        }
+
+        // No-ops and rejected values report nothing.
+        listener.set_detection_threshold(0.7);
+        listener.set_detection_threshold(1.5);
+        assert!(listener
+            .update_config(SonicConfig {
+                sample_rate: 0,
+                ..listener.get_config()
             })
-            .collect()
+            .is_err());
+        listener.flush_events();
+        assert_eq!(callback.config_changes.lock().len(), 1);
+
+        listener.push_samples(&[0.1; 1000]);
+        listener
+            .update_config(SonicConfig {
+                sample_rate: 44_100,
+                ..listener.get_config()
+            })
+            .unwrap();
+        listener.flush_events();
+        let changes = callback.config_changes.lock();
+        assert_eq!(changes.len(), 2);
+        assert_eq!((changes[1].0.sample_rate, changes[1].1.sample_rate), (DEFAULT_SAMPLE_RATE, 44_100));
+        assert_eq!(changes[1].1.detection_threshold, 0.7);
+        assert!(listener.pending.lock().is_empty(), "samples at the old rate are dropped");
     }
 
-    // ACCEPTANCE: the FFI `detect_watermark` on a real v3-embedded clip must
-    // report detected=true with a payload_hash (no longer a mock). The clip is
-    // produced by the shared `dsp::embed` — i.e. the same bytes a browser embed
-    // would emit — and the recovered payload_hash must equal the embed's.
     #[test]
-    fn test_detect_watermark_real_embedded_clip() {
+    fn test_rate_change_keeps_session_offsets() {
+        let listener = Arc::new(SonicListener::new(SonicConfig::default()).unwrap());
+        listener.start_listening(Box::new(TestCallback::default())).unwrap();
+        let second = |rate: u32| gen_broadband(rate as usize, rate as f32, 3);
+
+        let before = listener.process_samples(&second(DEFAULT_SAMPLE_RATE)).unwrap();
+        listener
+            .update_config(SonicConfig {
+                sample_rate: 44_100,
+                ..listener.get_config()
+            })
+            .unwrap();
+        let after = listener.process_samples(&second(44_100)).unwrap();
+        let next = listener.process_samples(&second(44_100)).unwrap();
+        assert_eq!(
+            [before.stream_offset_ms, after.stream_offset_ms, next.stream_offset_ms],
+            [Some(0), Some(1_000), Some(2_000)]
+        );
+        assert_eq!(next.frame_index, Some(2));
+    }
+
+    #[test]
+    fn test_repeated_buffer_served_from_result_cache() {
         let sr = 44_100u32;
-        let n = (sr as f32 * 13.0) as usize;
-        let pcm = samples_to_pcm_le16(&gen_broadband(n, sr as f32, 7));
+        let clip = embedded_clip(sr, 7);
 
-        let emb = dsp::embed(&pcm, sr, "did:key:z6MkMobileDetect", 1_700_000_000_000)
-            .expect("embed should succeed on a valid broadband clip");
+        let work = |listener: &SonicListener| {
+            let stats = listener.stats();
+            (stats.dsp_calls, stats.cache_hits)
+        };
 
-        let result = detect_watermark(&emb.watermarked_audio, sr);
-        assert!(result.detected, "real v3 detect must find the embedded watermark");
-        assert_eq!(
-            result.payload_hash.as_deref(),
-            Some(emb.payload_hash.as_str()),
-            "recovered payload_hash must equal the embed payload_hash"
+        // Off by default: every buffer is decoded.
+        let uncached = SonicListener::new(SonicConfig::default()).unwrap();
+        uncached.process_buffer(&clip.pcm[..32_000]).unwrap();
+        uncached.process_buffer(&clip.pcm[..32_000]).unwrap();
+        assert_eq!(work(&uncached), (2, 0));
+
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            result_cache_capacity: 4,
+            ..SonicConfig::default()
+        })
+        .unwrap();
+        let first = listener.process_buffer(&clip.pcm).unwrap();
+        let second = listener.process_buffer(&clip.pcm).unwrap();
+        assert!(first.detected);
+        assert_eq!(second.payload_hash, first.payload_hash);
+        assert_eq!(second.confidence, first.confidence);
+        assert_eq!(work(&listener), (1, 1));
+
+        // A config change drops the cache.
+        listener.set_detection_threshold(0.6);
+        listener.process_buffer(&clip.pcm).unwrap();
+        assert_eq!(work(&listener), (2, 1));
+    }
+
+    #[test]
+    fn test_stats_track_buffers_detections_and_timings() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..SonicConfig::default()
+            })
+            .unwrap(),
         );
-        assert_eq!(result.detection_method, "chirp_v3");
-        // The mock path is gone: signer_did / timestamp / covenant resolve
-        // server-side from payload_hash, so they are absent here.
-        assert!(result.signer_did.is_none());
-        assert!(result.covenant_json.is_none());
+        let fresh = listener.stats();
+        assert_eq!((fresh.buffers_processed, fresh.mean_processing_ms, fresh.p95_processing_ms), (0, 0.0, 0.0));
+
+        listener.start_listening(Box::new(TestCallback::default())).unwrap();
+        listener.process_buffer(&clip.pcm[..32_000]).unwrap();
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.push_samples(&[0.0; 1_000]);
+
+        let stats = listener.stats();
+        assert_eq!(stats.buffers_processed, 2);
+        assert_eq!(stats.samples_processed, 16_000 + clip.pcm.len() as u64 / 2);
+        assert_eq!(stats.detections_emitted, 1);
+        assert_eq!(stats.pending_samples, 1_000);
+        assert!(stats.mean_processing_ms > 0.0);
+        assert!(stats.p95_processing_ms >= stats.mean_processing_ms, "{stats:?}");
     }
 
-    // A non-watermarked clip must NOT be detected (negative / false-positive
-    // guard, now that detection is real and CRC-gated).
     #[test]
-    fn test_detect_watermark_clean_clip_negative() {
+    fn test_detections_are_recorded_in_store() {
         let sr = 44_100u32;
-        let n = (sr as f32 * 13.0) as usize;
-        let pcm = samples_to_pcm_le16(&gen_broadband(n, sr as f32, 99));
-        let result = detect_watermark(&pcm, sr);
-        assert!(!result.detected, "un-watermarked audio must not be detected");
+        let clip = embedded_clip(sr, 7);
+
+        let listener = SonicListener::new(SonicConfig {
+            sample_rate: sr,
+            ..SonicConfig::default()
+        })
+        .unwrap();
+        let store = in_memory_detection_store();
+        listener.set_detection_store(Some(store.clone()));
+        let result = listener.process_buffer(&clip.pcm).unwrap();
+        assert!(result.detected);
+        listener.process_buffer(&clip.pcm[..32_000]).unwrap();
+
+        let history = store.query(None, None, None);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].result.payload_hash, result.payload_hash);
+        assert!(history[0].detected_at >= 1_700_000_000);
+
+        listener.set_detection_store(None);
+        listener.process_buffer(&clip.pcm).unwrap();
+        assert_eq!(store.query(None, None, None).len(), 1);
+    }
+
+    #[test]
+    fn test_strict_sensitivity_waits_for_a_run() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                ..SonicConfig::default()
+            })
+            .unwrap(),
+        );
+        listener.set_sensitivity(SensitivityProfile::Strict);
+        assert_eq!(listener.get_config().min_detection_frames, 2);
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        // The first detection is held back; the second in a row is reported.
+        let first = listener.process_buffer(&clip.pcm).unwrap();
+        assert!(!first.detected && first.payload_hash.is_none());
+        let second = listener.process_buffer(&clip.pcm).unwrap();
+        assert_eq!(second.payload_hash.as_deref(), Some(clip.payload_hash.as_str()));
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1);
+
+        // A buffer without it ends the run.
+        let unmarked = samples_to_pcm_le16(&gen_broadband(clip.samples.len(), sr as f32, 7));
+        assert!(!listener.process_buffer(&unmarked).unwrap().detected);
+        assert!(!listener.process_buffer(&clip.pcm).unwrap().detected);
+
+        listener.set_sensitivity(SensitivityProfile::Balanced);
+        assert!(listener.process_buffer(&clip.pcm).unwrap().detected);
+    }
+
+    // A decode that needed most of its codewords corrected is only taken
+    // at the aggressive threshold.
+    #[test]
+    fn test_aggressive_sensitivity_takes_weak_decodes() {
+        let sr = 44_100u32;
+        let weak = with_noise(&embedded_clip(sr, 5).samples, 102, 3.0);
+        let detects = |profile| {
+            let config = SonicConfig {
+                sample_rate: sr,
+                ..Default::default()
+            };
+            let listener = SonicListener::new(config.with_sensitivity(profile)).unwrap();
+            // Twice, for the strict profile's run
+            (0..2).any(|_| listener.process_buffer(&weak).unwrap().detected)
+        };
+        assert!(detects(SensitivityProfile::Aggressive));
+        assert!(!detects(SensitivityProfile::Balanced));
+        assert!(!detects(SensitivityProfile::Strict));
+    }
+
+    #[test]
+    fn test_repeated_detections_collapse_into_one() {
+        let sr = 44_100u32;
+        let clip = embedded_clip(sr, 7);
+        let listener = Arc::new(
+            SonicListener::new(SonicConfig {
+                sample_rate: sr,
+                duplicate_window_secs: 60,
+                ..SonicConfig::default()
+            })
+            .unwrap(),
+        );
+        let store = in_memory_detection_store();
+        listener.set_detection_store(Some(store.clone()));
+        let callback = Arc::new(TestCallback::default());
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+
+        for _ in 0..3 {
+            assert!(listener.process_buffer(&clip.pcm).unwrap().detected);
+        }
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1);
+        assert!(callback.still_present.lock().is_empty());
+        assert_eq!(store.query(None, None, None).len(), 1);
+
+        listener
+            .update_config(SonicConfig {
+                still_present_interval_secs: 1,
+                ..listener.get_config()
+            })
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(1_100));
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 1);
+        let still_present = callback.still_present.lock().clone();
+        assert_eq!(still_present.len(), 1);
+        assert!(still_present[0] >= 1_000);
+
+        // A new session reports the watermark afresh.
+        listener.stop_listening().unwrap();
+        listener.start_listening(Box::new(callback.clone())).unwrap();
+        listener.process_buffer(&clip.pcm).unwrap();
+        listener.flush_events();
+        assert_eq!(callback.detections.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_custom_quality_estimator() {
+        struct Fixed(f32);
+        impl QualityEstimator for Fixed {
+            fn estimate(&self, spectrum: &[Complex<f32>], samples: &[f32]) -> f32 {
+                assert_eq!(spectrum.len(), spectrum::QUALITY_FFT_SIZE);
+                assert!(!samples.is_empty());
+                self.0
+            }
+        }
+
+        let samples = gen_broadband(4096, 16_000.0, 4);
+        let listener =
+            SonicListener::new_with_quality_estimator(SonicConfig::default(), Box::new(Fixed(0.42)))
+                .unwrap();
+        assert_eq!(listener.process_samples(&samples).unwrap().audio_quality, 0.42);
+
+        listener.set_quality_estimator(Box::new(Fixed(0.9)));
+        assert_eq!(listener.process_samples(&samples).unwrap().audio_quality, 0.9);
+    }
+
+    #[test]
+    fn test_stft_fft_size_is_checked_and_used() {
+        let at = |sample_rate, stft_fft_size| SonicConfig {
+            sample_rate,
+            stft_fft_size,
+            ..Default::default()
+        };
+        // Odd, coarser than 300 Hz bins, or longer than a second
+        assert!(at(44_100, 1001).validate().is_err());
+        assert!(at(44_100, 100).validate().is_err());
+        assert!(at(16_000, 16_002).validate().is_err());
+        assert!(at(44_100, 1000).validate().is_ok());
+
+        struct Bins;
+        impl QualityEstimator for Bins {
+            fn estimate(&self, spectrum: &[Complex<f32>], _samples: &[f32]) -> f32 {
+                spectrum.len() as f32
+            }
+        }
+        let samples = gen_broadband(4096, 16_000.0, 4);
+        let listener = SonicListener::new_with_quality_estimator(at(16_000, 1000), Box::new(Bins)).unwrap();
+        assert_eq!(listener.process_samples(&samples).unwrap().audio_quality, 1000.0);
     }
 
     #[test]
@@ -764,6 +5323,421 @@ mod tests {
         assert!(!version.is_empty());
     }
 
+    #[test]
+    fn test_generate_chirp_sync() {
+        let chirp = generate_chirp_sync(44_100, 600, 1500.0, 3500.0).unwrap();
+        assert_eq!(chirp, dsp::v3_sync_reference(44_100));
+
+        // A custom sweep: Hann-tapered, so it starts and ends silent.
+        let short = generate_chirp_sync(16_000, 100, 500.0, 4000.0).unwrap();
+        assert_eq!(short.len(), 1600);
+        assert_eq!((short[0], short[short.len() - 1]), (0.0, 0.0));
+        assert!(short.iter().all(|s| s.abs() <= 1.0));
+
+        assert!(matches!(generate_chirp_sync(0, 600, 1500.0, 3500.0), Err(SonicError::InvalidSampleRate(0))));
+        assert!(generate_chirp_sync(16_000, 0, 1500.0, 3500.0).is_err());
+        assert!(generate_chirp_sync(16_000, 600, 1500.0, 9000.0).is_err());
+        assert!(generate_chirp_sync(16_000, 600, f32::NAN, 3500.0).is_err());
+    }
+
+    // A deployment that names signers with did:web must get did:web DIDs out
+    // of verification, and be able to map them back to the key.
+    #[test]
+    fn test_signature_verifier_custom_did_resolver() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        struct WebResolver;
+        impl DidResolver for WebResolver {
+            fn public_key_to_did(&self, public_key: Vec<u8>) -> String {
+                let hex: String = public_key.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("did:web:signers.example.com:{}", hex)
+            }
+            fn did_to_public_key(&self, did: String) -> Option<Vec<u8>> {
+                let hex = did.strip_prefix("did:web:signers.example.com:")?;
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                    .collect()
+            }
+        }
+
+        let signing_key = SigningKey::from_bytes(&[42u8; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let signature = signing_key.sign(b"covenant");
+
+        let verifier = SignatureVerifier::with_resolver(Arc::new(WebResolver));
+        let result = verifier.verify_signature(b"covenant", &signature.to_bytes(), &public_key);
+        assert!(result.valid);
+        let did = result.signer_did.unwrap();
+        assert!(did.starts_with("did:web:signers.example.com:"));
+        assert_eq!(verifier.did_to_public_key(&did), Some(public_key.to_vec()));
+        assert_eq!(verifier.verify(b"covenant", &signature.to_bytes(), &public_key).unwrap(), did);
+        // did:key signers still resolve locally.
+        let did_key = did_from_public_key(&public_key).unwrap();
+        assert_eq!(verifier.did_to_public_key(&did_key), Some(public_key.to_vec()));
+
+        // The default mapping is unchanged.
+        let default = SignatureVerifier::new();
+        let result = default.verify_signature(b"covenant", &signature.to_bytes(), &public_key);
+        assert!(result.signer_did.unwrap().starts_with("did:key:"));
+    }
+
+    // A host resolver fetches did:web documents itself; the core locates
+    // and reads them.
+    #[test]
+    fn test_signature_verifier_resolves_did_web_through_host() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        struct HostResolver(std::collections::HashMap<String, String>);
+        impl DidResolver for HostResolver {
+            fn public_key_to_did(&self, _public_key: Vec<u8>) -> String {
+                "did:web:news.example".into()
+            }
+            fn did_to_public_key(&self, did: String) -> Option<Vec<u8>> {
+                let document = self.0.get(&did_web_url(&did).ok()?)?;
+                public_key_from_did_document(&did, document).ok()
+            }
+        }
+
+        let signing_key = SigningKey::from_bytes(&[11u8; 32]);
+        let multibase = did_from_public_key(&signing_key.verifying_key().to_bytes())
+            .unwrap()
+            .replace("did:key:", "");
+        let document = format!(
+            r#"{{"id": "did:web:news.example", "verificationMethod": [
+                {{"id": "did:web:news.example#key-1", "type": "Multikey", "publicKeyMultibase": "{multibase}"}}]}}"#
+        );
+        let host = HostResolver([("https://news.example/.well-known/did.json".to_string(), document)].into());
+        let verifier = SignatureVerifier::with_resolver(Arc::new(host));
+
+        let mut result = WatermarkResult {
+            detected: true,
+            signer_did: Some("did:web:news.example".into()),
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            ..Default::default()
+        };
+        let message = result.signing_input().unwrap();
+        result.signature = Some(signing_key.sign(&message).to_bytes().to_vec());
+        let verified = verifier.verify_watermark_payload(result.clone());
+        assert!(verified.valid, "{:?}", verified.error_message);
+        assert_eq!(verified.signer_did.as_deref(), Some("did:web:news.example"));
+
+        result.signer_did = Some("did:web:unknown.example".into());
+        assert!(!verifier.verify_watermark_payload(result).valid);
+    }
+
+    #[test]
+    fn test_verify_watermark_payload_checks_signature() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let verifier = SignatureVerifier::new();
+        let did = did_from_public_key(&signing_key.verifying_key().to_bytes()).unwrap();
+        let mut result = WatermarkResult {
+            detected: true,
+            confidence: 0.95,
+            signer_did: Some(did.clone()),
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            covenant_json: Some(r#"{"permissions": {"ai_training": false}}"#.into()),
+            detection_method: "chirp_v3".into(),
+            ..Default::default()
+        };
+        let message = result.signing_input().unwrap();
+        result.signature = Some(signing_key.sign(&message).to_bytes().to_vec());
+
+        let verified = verifier.verify_watermark_payload(result.clone());
+        assert!(verified.valid, "{:?}", verified.error_message);
+        assert_eq!(verified.signer_did, Some(did));
+
+        // Key order and whitespace in the covenant don't change the input.
+        let reformatted = WatermarkResult {
+            covenant_json: Some(r#"{ "permissions":{"ai_training":false} }"#.into()),
+            ..result.clone()
+        };
+        assert!(verifier.verify_watermark_payload(reformatted).valid);
+
+        let tampered = WatermarkResult {
+            timestamp: Some(1_700_000_001),
+            ..result.clone()
+        };
+        assert!(!verifier.verify_watermark_payload(tampered).valid);
+
+        let unsigned = WatermarkResult {
+            signature: None,
+            ..result.clone()
+        };
+        let unsigned = verifier.verify_watermark_payload(unsigned);
+        assert!(!unsigned.valid);
+        assert!(unsigned.signer_did.is_none());
+
+        let not_detected = WatermarkResult {
+            detected: false,
+            ..result
+        };
+        assert!(!verifier.verify_watermark_payload(not_detected).valid);
+    }
+
+    #[test]
+    fn test_require_pinned_signer_rejects_unpinned() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[8u8; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let did = did_from_public_key(&public_key).unwrap();
+        let signature = signing_key.sign(b"broadcast").to_bytes();
+        let verifier = SignatureVerifier::new();
+        // Pinning alone changes nothing until the policy asks for it.
+        verifier.trust_store().pin_did("did:web:station.example".into());
+        assert!(verifier.verify_signature(b"broadcast", &signature, &public_key).valid);
+
+        verifier.set_config(VerifierConfig {
+            require_pinned_signer: true,
+            ..Default::default()
+        });
+        let rejected = verifier.verify_signature(b"broadcast", &signature, &public_key);
+        assert!(!rejected.valid);
+        assert!(rejected.untrusted_signer);
+        assert_eq!(rejected.signer_did.as_deref(), Some(did.as_str()));
+        assert!(matches!(
+            verifier.verify(b"broadcast", &signature, &public_key),
+            Err(SonicError::UntrustedSigner(d)) if d == did
+        ));
+        // A bad signature is still reported as such, not as untrusted.
+        let forged = verifier.verify_signature(b"forged", &signature, &public_key);
+        assert!(!forged.untrusted_signer);
+        assert!(matches!(verifier.verify(b"forged", &signature, &public_key), Err(SonicError::SignatureInvalid)));
+
+        let store = Arc::new(TrustStore::new());
+        store.pin_did(did.clone());
+        verifier.set_trust_store(store.clone());
+        assert_eq!(verifier.verify(b"broadcast", &signature, &public_key).unwrap(), did);
+        store.unpin_did(&did);
+        store.pin_public_key(public_key.to_vec()).unwrap();
+        assert!(verifier.verify_signature(b"broadcast", &signature, &public_key).valid);
+
+        // Payloads are admitted by the DID they name, too.
+        store.clear();
+        store.pin_did(did.clone());
+        let mut result = WatermarkResult {
+            detected: true,
+            signer_did: Some(did),
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            ..Default::default()
+        };
+        result.signature = Some(signing_key.sign(&result.signing_input().unwrap()).to_bytes().to_vec());
+        assert!(verifier.verify_watermark_payload(result.clone()).valid);
+        store.clear();
+        assert!(verifier.verify_watermark_payload(result).untrusted_signer);
+    }
+
+    #[test]
+    fn test_revoked_signer_fails_verification() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        // The issuer's list: credentials 3 and 5 revoked.
+        struct Issuer(std::collections::HashMap<String, u64>);
+        impl StatusListProvider for Issuer {
+            fn signer_status(&self, signer_did: String) -> Option<SignerStatus> {
+                Some(SignerStatus {
+                    status_list: vec![0b0001_0100],
+                    status_index: *self.0.get(&signer_did)?,
+                })
+            }
+        }
+
+        let sign = |seed: u8| {
+            let key = SigningKey::from_bytes(&[seed; 32]);
+            let public_key = key.verifying_key().to_bytes();
+            (did_from_public_key(&public_key).unwrap(), public_key, key.sign(b"clip").to_bytes())
+        };
+        let (active, active_key, active_sig) = sign(1);
+        let (revoked, revoked_key, revoked_sig) = sign(2);
+        let (unlisted, unlisted_key, unlisted_sig) = sign(3);
+        let (misfiled, misfiled_key, misfiled_sig) = sign(4);
+
+        let verifier = SignatureVerifier::new();
+        assert!(verifier.verify_signature(b"clip", &revoked_sig, &revoked_key).valid);
+        verifier.set_status_provider(Some(Box::new(Issuer(std::collections::HashMap::from([
+            (active.clone(), 4),
+            (revoked.clone(), 5),
+            (misfiled.clone(), 8),
+        ])))));
+
+        let result = verifier.verify_signature(b"clip", &active_sig, &active_key);
+        assert!(result.valid && !result.revoked);
+        let result = verifier.verify_signature(b"clip", &revoked_sig, &revoked_key);
+        assert!(!result.valid && result.revoked);
+        assert_eq!(result.signer_did.as_deref(), Some(revoked.as_str()));
+        assert!(matches!(
+            verifier.verify(b"clip", &revoked_sig, &revoked_key),
+            Err(SonicError::SignerRevoked(d)) if d == revoked
+        ));
+        assert_eq!(verifier.verify(b"clip", &unlisted_sig, &unlisted_key).unwrap(), unlisted);
+        // An index past the list is not taken as "not revoked".
+        let result = verifier.verify_signature(b"clip", &misfiled_sig, &misfiled_key);
+        assert!(!result.valid && !result.revoked);
+
+        verifier.set_status_provider(None);
+        assert!(verifier.verify_signature(b"clip", &revoked_sig, &revoked_key).valid);
+    }
+
+    #[test]
+    fn test_ecdsa_payload_verifies_with_its_algorithm() {
+        use k256::ecdsa::signature::Signer;
+
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let public_key = signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        let did = did::did_key_for(SignatureAlgorithm::Es256k, &public_key).unwrap();
+        assert!(did.starts_with("did:key:zQ3s"));
+        let mut result = WatermarkResult {
+            detected: true,
+            signer_did: Some(did.clone()),
+            timestamp: Some(1_700_000_000),
+            payload_hash: Some("a1b2c3d4".into()),
+            signature_algorithm: SignatureAlgorithm::Es256k,
+            ..Default::default()
+        };
+        let signature: k256::ecdsa::Signature = signing_key.sign(&result.signing_input().unwrap());
+        result.signature = Some(signature.to_bytes().to_vec());
+
+        let verifier = SignatureVerifier::new();
+        let verified = verifier.verify_watermark_payload(result.clone());
+        assert!(verified.valid, "{:?}", verified.error_message);
+        assert_eq!(verified.algorithm, SignatureAlgorithm::Es256k);
+        assert_eq!(verified.signer_did.as_deref(), Some(did.as_str()));
+        assert_eq!(verifier.did_to_public_key(&did), Some(public_key.clone()));
+
+        // The payload's algorithm must match its signer's key type.
+        let mislabelled = verifier.verify_watermark_payload(WatermarkResult {
+            signature_algorithm: SignatureAlgorithm::Es256,
+            ..result.clone()
+        });
+        assert!(!mislabelled.valid);
+        assert!(mislabelled.error_message.unwrap().contains("key type"));
+
+        let message = result.signing_input().unwrap();
+        let raw = verifier.verify_signature_with_algorithm(
+            &message,
+            result.signature.as_deref().unwrap(),
+            signing_key.verifying_key().to_encoded_point(false).as_bytes(),
+            SignatureAlgorithm::Es256k,
+        );
+        assert!(raw.valid);
+        assert_eq!(raw.signer_did.as_deref(), Some(did.as_str()));
+        assert!(!verifier.verify_signature(&message, result.signature.as_deref().unwrap(), &public_key).valid);
+    }
+
+    #[test]
+    fn test_payload_verifies_across_key_rotation() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let old = SigningKey::from_bytes(&[11u8; 32]);
+        let new = SigningKey::from_bytes(&[12u8; 32]);
+        let keys = vec![
+            SignerKey {
+                key_id: "did:web:news.example#key-2".into(),
+                public_key: new.verifying_key().to_bytes().to_vec(),
+                algorithm: SignatureAlgorithm::Ed25519,
+                valid_from: Some(1_700_000_000),
+                valid_until: None,
+            },
+            SignerKey {
+                key_id: "did:web:news.example#key-1".into(),
+                public_key: old.verifying_key().to_bytes().to_vec(),
+                algorithm: SignatureAlgorithm::Ed25519,
+                valid_from: None,
+                valid_until: Some(1_700_000_000),
+            },
+        ];
+        let signed_by = |key: &SigningKey, timestamp: u64| {
+            let mut result = WatermarkResult {
+                detected: true,
+                signer_did: Some("did:web:news.example".into()),
+                timestamp: Some(timestamp),
+                payload_hash: Some("a1b2c3d4".into()),
+                ..Default::default()
+            };
+            result.signature = Some(key.sign(&result.signing_input().unwrap()).to_bytes().to_vec());
+            result
+        };
+        let verifier = SignatureVerifier::new();
+
+        // A watermark from before the rotation verifies under the old key.
+        let before = verifier.verify_watermark_payload_with_keys(signed_by(&old, 1_600_000_000), keys.clone());
+        assert!(before.valid, "{:?}", before.error_message);
+        assert_eq!(before.key_id.as_deref(), Some("did:web:news.example#key-1"));
+        let after = verifier.verify_watermark_payload_with_keys(signed_by(&new, 1_800_000_000), keys.clone());
+        assert_eq!(after.key_id.as_deref(), Some("did:web:news.example#key-2"));
+        // The old key signing after it was rotated out does not verify.
+        let stale = verifier.verify_watermark_payload_with_keys(signed_by(&old, 1_800_000_000), keys.clone());
+        assert!(!stale.valid && stale.key_id.is_none());
+        assert!(!verifier.verify_watermark_payload_with_keys(signed_by(&old, 1_600_000_000), vec![]).valid);
+
+        // A did:key signer reports its own key.
+        let did = did_from_public_key(&old.verifying_key().to_bytes()).unwrap();
+        let mut result = WatermarkResult {
+            signer_did: Some(did.clone()),
+            ..signed_by(&old, 1_600_000_000)
+        };
+        result.signature = Some(old.sign(&result.signing_input().unwrap()).to_bytes().to_vec());
+        let verified = verifier.verify_watermark_payload(result);
+        assert_eq!(verified.key_id, Some(format!("{did}#{}", &did["did:key:".len()..])));
+    }
+
+    #[test]
+    fn test_replayed_and_stale_payloads_are_flagged() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[13u8; 32]);
+        let did = did_from_public_key(&signing_key.verifying_key().to_bytes()).unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let signed_at = |timestamp: u64, payload_hash: &str| {
+            let mut result = WatermarkResult {
+                detected: true,
+                signer_did: Some(did.clone()),
+                timestamp: Some(timestamp),
+                payload_hash: Some(payload_hash.into()),
+                ..Default::default()
+            };
+            result.signature = Some(signing_key.sign(&result.signing_input().unwrap()).to_bytes().to_vec());
+            result
+        };
+        let verifier = SignatureVerifier::new();
+        let live = verifier.verify_watermark_payload(signed_at(now, "live"));
+        assert_eq!(live.timestamp_status, Some(TimestampStatus::Fresh));
+        assert!(!live.replayed);
+        // Replay detection is opt in.
+        assert!(!verifier.verify_watermark_payload(signed_at(now, "live")).replayed);
+
+        verifier.set_config(VerifierConfig {
+            max_timestamp_age_secs: Some(3_600),
+            detect_replays: true,
+            ..Default::default()
+        });
+        let years_old = verifier.verify_watermark_payload(signed_at(now - 3 * 365 * 86_400, "archive"));
+        assert!(years_old.valid);
+        assert_eq!(years_old.timestamp_status, Some(TimestampStatus::Stale));
+        let ahead = verifier.verify_watermark_payload(signed_at(now + 86_400, "ahead"));
+        assert_eq!(ahead.timestamp_status, Some(TimestampStatus::Future));
+
+        assert!(!verifier.verify_watermark_payload(signed_at(now, "loop")).replayed);
+        let looped = verifier.verify_watermark_payload(signed_at(now, "loop"));
+        assert!(looped.valid && looped.replayed);
+        // A forgery is not remembered, so it cannot mask the real payload.
+        let mut forged = signed_at(now, "fresh");
+        forged.signature = Some(vec![0; 64]);
+        assert!(!verifier.verify_watermark_payload(forged).replayed);
+        assert!(!verifier.verify_watermark_payload(signed_at(now, "fresh")).replayed);
+        verifier.clear_replay_cache();
+        assert!(!verifier.verify_watermark_payload(signed_at(now, "loop")).replayed);
+    }
+
     #[test]
     fn test_signature_verifier() {
         let verifier = SignatureVerifier::new();
@@ -775,5 +5749,9 @@ mod tests {
             &[0u8; 32],
         );
         assert!(!result.valid);
+        assert!(matches!(
+            verifier.verify(b"test message", &[0u8; 64], &[0u8; 32]),
+            Err(SonicError::SignatureInvalid)
+        ));
     }
 }
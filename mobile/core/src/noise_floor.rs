@@ -0,0 +1,86 @@
+//! Noise-adaptive detection threshold.
+//!
+//! How strongly unmarked audio correlates with the sync chirp depends on
+//! the room: a quiet one barely does, music or chatter with energy in the
+//! chirp's band does more. With `adaptive_threshold_sigmas` set, the
+//! listener measures that correlation for every buffer that decodes
+//! nothing, keeps the most recent measurements as the noise floor, and
+//! holds detections to a threshold that many standard deviations above it.
+
+use std::collections::VecDeque;
+
+/// Buffers without a detection whose sync correlation makes up the floor
+pub(crate) const NOISE_FLOOR_WINDOW: usize = 64;
+
+/// Measurements needed before the floor sets a threshold
+pub(crate) const NOISE_FLOOR_MIN_BUFFERS: usize = 8;
+
+/// The sync correlation peaks of the most recent buffers without a
+/// detection, oldest first.
+pub(crate) struct NoiseFloor {
+    recent: VecDeque<f32>,
+}
+
+impl Default for NoiseFloor {
+    /// Allocated up front, so recording never allocates on the audio path.
+    fn default() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(NOISE_FLOOR_WINDOW),
+        }
+    }
+}
+
+impl NoiseFloor {
+    pub(crate) fn record(&mut self, peak: f32) {
+        if self.recent.len() == NOISE_FLOOR_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(peak);
+    }
+
+    /// The floor's mean plus `sigmas` standard deviations, or `None` until
+    /// `NOISE_FLOOR_MIN_BUFFERS` buffers have been measured.
+    pub(crate) fn threshold(&self, sigmas: f32) -> Option<f32> {
+        if self.recent.len() < NOISE_FLOOR_MIN_BUFFERS {
+            return None;
+        }
+        let n = self.recent.len() as f32;
+        let mean = self.recent.iter().sum::<f32>() / n;
+        let variance = self.recent.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / n;
+        Some(mean + sigmas * variance.sqrt())
+    }
+
+    /// Forget the measurements, e.g. for a new room or sample rate.
+    pub(crate) fn clear(&mut self) {
+        self.recent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_follows_the_recent_floor() {
+        let mut floor = NoiseFloor::default();
+        for _ in 0..NOISE_FLOOR_MIN_BUFFERS - 1 {
+            floor.record(0.02);
+        }
+        assert_eq!(floor.threshold(3.0), None);
+
+        // Alternating 0.01 and 0.03: mean 0.02, standard deviation 0.01.
+        for i in 0..NOISE_FLOOR_WINDOW {
+            floor.record(if i % 2 == 0 { 0.01 } else { 0.03 });
+        }
+        assert!((floor.threshold(3.0).unwrap() - 0.05).abs() < 1e-6);
+
+        // A louder room raises it as the quiet measurements age out.
+        for _ in 0..NOISE_FLOOR_WINDOW {
+            floor.record(0.06);
+        }
+        assert!((floor.threshold(3.0).unwrap() - 0.06).abs() < 1e-6);
+
+        floor.clear();
+        assert_eq!(floor.threshold(3.0), None);
+    }
+}
@@ -0,0 +1,275 @@
+//! Ogg Opus decoding for compressed evidence (feature `opus`).
+//!
+//! Decodes with libopus (via `audiopus`) and hands back normalized mono f32
+//! at the rate the listener runs at, ready for
+//! `SonicListener::process_samples`. Covers channel mapping family 0 (mono and
+//! stereo) streams, which is what voice recorders and messaging apps produce;
+//! surround (multistream) files are rejected with `UnsupportedFormat` carrying
+//! the mapping family.
+//!
+//! Calls and WebRTC recordings often hold bare Opus packets, with no Ogg
+//! container; `OpusPacketDecoder` decodes those one at a time, concealing
+//! lost packets, for `SonicListener::push_samples`.
+
+use std::io::Cursor;
+
+use audiopus::{
+    coder::{Decoder, GenericCtl},
+    packet::Packet,
+    Channels, MutSignals, SampleRate,
+};
+use ogg::reading::PacketReader;
+use crate::{
+    resample::{resample, StreamResampler},
+    SonicError,
+};
+
+/// Rate libopus decodes at; the header's "input sample rate" is informational.
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+
+/// Longest Opus frame (120 ms at 48 kHz), per channel.
+const MAX_FRAME_SAMPLES: usize = 5760;
+
+/// The fields of the `OpusHead` identification header that affect decoding.
+struct OpusHead {
+    channels: usize,
+    /// Encoder lookahead samples (at 48 kHz) to drop from the start
+    pre_skip: usize,
+    /// Gain to apply to the decoded output
+    output_gain_db: f32,
+}
+
+/// Decode an Ogg Opus file to mono f32 samples at `target_rate`.
+///
+/// Stereo is downmixed by averaging the channels. The encoder's pre-skip is
+/// dropped, the stream is trimmed to its final granule position, and the
+/// header's output gain is applied. Only the first logical stream is decoded.
+pub fn decode_opus_to_samples(data: &[u8], target_rate: u32) -> Result<Vec<f32>, SonicError> {
+    if target_rate == 0 {
+        return Err(SonicError::InvalidSampleRate(target_rate));
+    }
+    let mut reader = PacketReader::new(Cursor::new(data));
+    let mut next_packet = || {
+        reader
+            .read_packet()
+            .map_err(|e| decode_error(format!("invalid Ogg stream: {e}")))
+    };
+
+    let head = match next_packet()? {
+        Some(packet) => parse_head(&packet.data)?,
+        None => return Err(decode_error("empty stream")),
+    };
+    // The second header packet (OpusTags) only carries comments.
+    if next_packet()?.is_none() {
+        return Err(decode_error("missing OpusTags header"));
+    }
+
+    let layout = if head.channels == 2 { Channels::Stereo } else { Channels::Mono };
+    let mut decoder = Decoder::new(SampleRate::Hz48000, layout).map_err(opus_error)?;
+    let mut frame = vec![0.0f32; MAX_FRAME_SAMPLES * head.channels];
+    let mut mono = Vec::new();
+    let mut end_granule = None;
+    while let Some(packet) = next_packet()? {
+        let input = Packet::try_from(packet.data.as_slice()).map_err(opus_error)?;
+        let output = MutSignals::try_from(frame.as_mut_slice()).map_err(opus_error)?;
+        let decoded = decoder.decode_float(Some(input), output, false).map_err(opus_error)?;
+        mono.extend(
+            frame[..decoded * head.channels]
+                .chunks_exact(head.channels)
+                .map(|c| c.iter().sum::<f32>() / head.channels as f32),
+        );
+        if packet.last_in_stream() {
+            end_granule = Some(packet.absgp_page() as usize);
+            break;
+        }
+    }
+
+    // Granule positions count from the start of the pre-skip.
+    if let Some(end) = end_granule {
+        mono.truncate(end);
+    }
+    mono.drain(..head.pre_skip.min(mono.len()));
+    if head.output_gain_db != 0.0 {
+        let gain = 10.0_f32.powf(head.output_gain_db / 20.0);
+        mono.iter_mut().for_each(|s| *s *= gain);
+    }
+
+    resample(&mono, OPUS_SAMPLE_RATE, target_rate)
+}
+
+/// Streaming decoder for raw Opus packets (RTP payloads, WebRTC frames)
+/// from a mono or stereo stream.
+///
+/// Each packet decodes to mono f32 at the target rate, stereo downmixed by
+/// averaging, ready for `SonicListener::push_samples`. A lost packet passed
+/// as `None` is concealed by libopus with audio of the previous packet's
+/// duration, so the stream keeps its timing.
+pub struct OpusPacketDecoder {
+    decoder: Decoder,
+    channels: usize,
+    /// Converts from 48 kHz when the target rate differs
+    resampler: Option<StreamResampler>,
+    frame: Vec<f32>,
+    /// Samples per channel in the last decoded packet, for concealment
+    last_frame_samples: usize,
+}
+
+impl OpusPacketDecoder {
+    /// Decoder for a stream with `channels` (1 or 2) channels, producing
+    /// mono at `target_rate`.
+    pub fn new(channels: u32, target_rate: u32) -> Result<Self, SonicError> {
+        if target_rate == 0 {
+            return Err(SonicError::InvalidSampleRate(target_rate));
+        }
+        let layout = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return Err(decode_error(format!("unsupported channel count {channels}"))),
+        };
+        let resampler = (target_rate != OPUS_SAMPLE_RATE)
+            .then(|| StreamResampler::new(OPUS_SAMPLE_RATE, target_rate))
+            .transpose()?;
+        Ok(Self {
+            decoder: Decoder::new(SampleRate::Hz48000, layout).map_err(opus_error)?,
+            channels: channels as usize,
+            resampler,
+            frame: vec![0.0; MAX_FRAME_SAMPLES * channels as usize],
+            last_frame_samples: OPUS_SAMPLE_RATE as usize / 50,
+        })
+    }
+
+    /// Decode the next packet, or conceal a lost one (`None`). Returns the
+    /// new mono samples; with resampling, a few are held back until the next
+    /// call or `finish`.
+    pub fn decode_packet(&mut self, packet: Option<&[u8]>) -> Result<Vec<f32>, SonicError> {
+        let decoded = match packet {
+            Some(data) => {
+                let input = Packet::try_from(data).map_err(opus_error)?;
+                let output = MutSignals::try_from(self.frame.as_mut_slice()).map_err(opus_error)?;
+                self.decoder.decode_float(Some(input), output, false).map_err(opus_error)?
+            }
+            None => {
+                let len = self.last_frame_samples * self.channels;
+                let output = MutSignals::try_from(&mut self.frame[..len]).map_err(opus_error)?;
+                self.decoder.decode_float(None, output, false).map_err(opus_error)?
+            }
+        };
+        self.last_frame_samples = decoded;
+        let mono: Vec<f32> = self.frame[..decoded * self.channels]
+            .chunks_exact(self.channels)
+            .map(|c| c.iter().sum::<f32>() / self.channels as f32)
+            .collect();
+        match &mut self.resampler {
+            Some(resampler) => resampler.process(&mono),
+            None => Ok(mono),
+        }
+    }
+
+    /// Samples still held back by the resampler, at end of stream. The
+    /// decoder is then ready for a new stream's packets.
+    pub fn finish(&mut self) -> Result<Vec<f32>, SonicError> {
+        self.decoder.reset_state().map_err(opus_error)?;
+        match &mut self.resampler {
+            Some(resampler) => resampler.finish(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+fn parse_head(packet: &[u8]) -> Result<OpusHead, SonicError> {
+    if packet.len() < 19 || &packet[..8] != b"OpusHead" {
+        return Err(decode_error("missing OpusHead header"));
+    }
+    let channels = packet[9] as usize;
+    let mapping_family = packet[18];
+    if mapping_family != 0 {
+        return Err(SonicError::UnsupportedFormat(mapping_family));
+    }
+    if !(1..=2).contains(&channels) {
+        return Err(decode_error(format!("unsupported channel count {channels}")));
+    }
+    Ok(OpusHead {
+        channels,
+        pre_skip: u16::from_le_bytes([packet[10], packet[11]]) as usize,
+        output_gain_db: i16::from_le_bytes([packet[16], packet[17]]) as f32 / 256.0,
+    })
+}
+
+fn decode_error(message: impl std::fmt::Display) -> SonicError {
+    SonicError::ProcessingFailed(format!("Opus decode failed: {message}"))
+}
+
+fn opus_error(e: audiopus::Error) -> SonicError {
+    decode_error(e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SonicConfig, SonicListener};
+
+    // 1.5 s of 48 kHz stereo (440 Hz left, 1 kHz right, light noise) at 32 kbps.
+    const STEREO_FIXTURE: &[u8] = include_bytes!("../tests/fixtures/stereo_tone.opus");
+
+    #[test]
+    fn test_decode_fixture_to_detector_rate() {
+        let samples = decode_opus_to_samples(STEREO_FIXTURE, 16_000).unwrap();
+        assert_eq!(samples.len(), 24_000);
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.1 && peak <= 1.0, "peak {peak}");
+
+        let listener = SonicListener::new(SonicConfig::default()).unwrap();
+        let result = listener.process_samples(&samples).unwrap();
+        assert!(!result.detected);
+        assert_eq!(result.rejection_reason, None);
+    }
+
+    #[test]
+    fn test_decode_keeps_native_rate() {
+        let samples = decode_opus_to_samples(STEREO_FIXTURE, OPUS_SAMPLE_RATE).unwrap();
+        assert_eq!(samples.len(), 72_000);
+    }
+
+    #[test]
+    fn test_packet_decoder_matches_file_decode() {
+        // The fixture's audio packets, as a call stack would hand them over.
+        let mut reader = PacketReader::new(Cursor::new(STEREO_FIXTURE));
+        let mut packets = Vec::new();
+        while let Some(packet) = reader.read_packet().unwrap() {
+            packets.push(packet.data);
+        }
+        let head = parse_head(&packets[0]).unwrap();
+
+        let mut decoder = OpusPacketDecoder::new(2, OPUS_SAMPLE_RATE).unwrap();
+        let mut streamed = Vec::new();
+        for packet in &packets[2..] {
+            streamed.extend(decoder.decode_packet(Some(packet)).unwrap());
+        }
+        streamed.extend(decoder.finish().unwrap());
+        let file = decode_opus_to_samples(STEREO_FIXTURE, OPUS_SAMPLE_RATE).unwrap();
+        assert_eq!(&streamed[head.pre_skip..head.pre_skip + file.len()], file.as_slice());
+
+        // A lost packet is concealed with a packet's worth of audio.
+        let mut decoder = OpusPacketDecoder::new(2, 16_000).unwrap();
+        let first = decoder.decode_packet(Some(&packets[2])).unwrap();
+        let concealed = decoder.decode_packet(None).unwrap();
+        assert!(concealed.len().abs_diff(first.len()) <= 320, "{} vs {}", concealed.len(), first.len());
+
+        assert!(OpusPacketDecoder::new(3, 16_000).is_err());
+        assert!(OpusPacketDecoder::new(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_rejects_non_opus_input() {
+        assert!(decode_opus_to_samples(b"RIFF\0\0\0\0WAVEfmt ", 16_000).is_err());
+        assert!(decode_opus_to_samples(STEREO_FIXTURE, 0).is_err());
+    }
+
+    #[test]
+    fn test_rejects_surround_mapping_family() {
+        let at = STEREO_FIXTURE.windows(8).position(|w| w == b"OpusHead").unwrap();
+        let mut head = STEREO_FIXTURE[at..at + 19].to_vec();
+        head[18] = 1;
+        assert!(matches!(parse_head(&head), Err(SonicError::UnsupportedFormat(1))));
+    }
+}
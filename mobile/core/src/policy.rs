@@ -0,0 +1,216 @@
+//! Covenant policy evaluation.
+//!
+//! A covenant states how a creator allows their audio to be used, and every
+//! app that acts on one must read it the same way. [`is_permitted`] is that
+//! reading, matching the Python SDK's `VouchCovenant`. Covenants of older
+//! schema versions, such as its C2PA assertion form
+//! (`{"policies": {"ai_training": "DENY"}, "restrictions": {...}}`), are
+//! upgraded to the current flat form first.
+//!
+//! Terms the engine cannot interpret fail closed: an unrecognized policy
+//! value or an unparseable expiry denies the use rather than ignoring the
+//! term.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::{Map, Value};
+
+use crate::{covenant_schema, is_encrypted_covenant, SonicError};
+
+/// A use of covenant-bearing audio an app asks permission for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsageKind {
+    AiTraining,
+    AiInference,
+    VoiceCloning,
+    DerivativeWorks,
+    CommercialUse,
+}
+
+impl UsageKind {
+    /// The covenant key of this use, and whether it is allowed when the
+    /// covenant does not mention it (the Python SDK's defaults).
+    fn term(self) -> (&'static str, bool) {
+        match self {
+            UsageKind::AiTraining => ("ai_training", true),
+            UsageKind::AiInference => ("ai_inference", true),
+            UsageKind::VoiceCloning => ("voice_cloning", false),
+            UsageKind::DerivativeWorks => ("derivative_works", true),
+            UsageKind::CommercialUse => ("commercial_use", true),
+        }
+    }
+}
+
+/// Which covenant term denied a use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DenialReason {
+    /// The covenant denies this use (or states it in a form the engine does
+    /// not recognize)
+    Policy,
+    /// The covenant's permissions have expired (or its expiry is unreadable)
+    Expired,
+    /// The use happens outside the covenant's jurisdictions, or in an
+    /// unknown one
+    Jurisdiction,
+}
+
+/// Outcome of [`is_permitted`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    pub permitted: bool,
+    /// The term that denied the use; `None` when permitted
+    pub denied_by: Option<DenialReason>,
+    /// Human-readable explanation, for logs and UI
+    pub reason: String,
+    /// The covenant requires crediting the creator for permitted uses
+    pub attribution_required: bool,
+}
+
+/// Whether `covenant_json` permits `usage` now, in `jurisdiction` (an ISO
+/// 3166-1 alpha-2 code, when the app knows where the use happens).
+///
+/// Audio without a covenant permits every use. Expiry ends every
+/// permission the covenant grants; a covenant limited to `jurisdictions`
+/// denies uses elsewhere, and uses whose jurisdiction is unknown. An
+/// encrypted covenant must be decrypted first.
+pub fn is_permitted(
+    covenant_json: Option<String>,
+    usage: UsageKind,
+    jurisdiction: Option<String>,
+) -> Result<PolicyDecision, SonicError> {
+    evaluate(covenant_json.as_deref(), usage, jurisdiction.as_deref(), Utc::now())
+}
+
+fn evaluate(
+    covenant_json: Option<&str>,
+    usage: UsageKind,
+    jurisdiction: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<PolicyDecision, SonicError> {
+    let (term, default) = usage.term();
+    let Some(covenant_json) = covenant_json else {
+        return Ok(PolicyDecision {
+            permitted: true,
+            denied_by: None,
+            reason: "No covenant: every use is permitted".into(),
+            attribution_required: false,
+        });
+    };
+    if is_encrypted_covenant(covenant_json) {
+        return Err(SonicError::InvalidConfig("covenant is encrypted; decrypt it first".into()));
+    }
+    let covenant = covenant_schema::upgrade(covenant_json)?;
+
+    let attribution_required = covenant
+        .get("attribution_required")
+        .is_none_or(|v| v.as_bool() != Some(false));
+    let deny = |denied_by: DenialReason, reason: String| PolicyDecision {
+        permitted: false,
+        denied_by: Some(denied_by),
+        reason,
+        attribution_required,
+    };
+
+    match covenant.get("expiration_date") {
+        None | Some(Value::Null) => {}
+        Some(value) => match value.as_str().and_then(parse_expiration) {
+            Some(expires) if now >= expires => {
+                return Ok(deny(DenialReason::Expired, format!("Covenant permissions expired at {}", expires.to_rfc3339())))
+            }
+            Some(_) => {}
+            None => return Ok(deny(DenialReason::Expired, format!("Unreadable covenant expiration {value}"))),
+        },
+    }
+
+    if let Some(allowed) = jurisdictions(&covenant) {
+        let inside = jurisdiction.is_some_and(|j| allowed.iter().any(|a| a.eq_ignore_ascii_case(j)));
+        if !inside {
+            let reason = match jurisdiction {
+                Some(j) => format!("Covenant does not extend to jurisdiction {j}"),
+                None => "Covenant is limited to specific jurisdictions and the use's is unknown".into(),
+            };
+            return Ok(deny(DenialReason::Jurisdiction, reason));
+        }
+    }
+
+    let allowed = match covenant.get(term) {
+        None | Some(Value::Null) => Some(default),
+        Some(Value::Bool(allowed)) => Some(*allowed),
+        Some(Value::String(s)) if s.eq_ignore_ascii_case("allow") => Some(true),
+        Some(Value::String(s)) if s.eq_ignore_ascii_case("deny") => Some(false),
+        Some(_) => None,
+    };
+    Ok(match allowed {
+        Some(true) => PolicyDecision {
+            permitted: true,
+            denied_by: None,
+            reason: format!("Covenant allows {term}"),
+            attribution_required,
+        },
+        Some(false) => deny(DenialReason::Policy, format!("Covenant denies {term}")),
+        None => deny(DenialReason::Policy, format!("Unrecognized covenant value for {term}")),
+    })
+}
+
+/// The jurisdiction allow-list, if the covenant has a non-empty one.
+fn jurisdictions(covenant: &Map<String, Value>) -> Option<Vec<&str>> {
+    let list = covenant.get("jurisdictions")?.as_array()?;
+    let codes: Vec<&str> = list.iter().filter_map(Value::as_str).collect();
+    (!codes.is_empty()).then_some(codes)
+}
+
+/// An RFC 3339 instant, or a date whose permissions last through its end
+/// (UTC).
+fn parse_expiration(expiration: &str) -> Option<DateTime<Utc>> {
+    if let Ok(instant) = DateTime::parse_from_rfc3339(expiration) {
+        return Some(instant.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(expiration, "%Y-%m-%d").ok()?;
+    Some(day.succ_opt()?.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(instant: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(instant).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_flat_and_assertion_forms_agree() {
+        let now = at("2026-01-01T00:00:00Z");
+        let flat = r#"{"ai_training": false, "voice_cloning": true}"#;
+        let assertion = r#"{"@type": "VouchCovenant", "policies": {"ai_training": "DENY", "voice_cloning": "ALLOW"},
+                            "requirements": {"attribution": false}}"#;
+        for covenant in [flat, assertion] {
+            let decide = |usage| evaluate(Some(covenant), usage, None, now).unwrap();
+            assert_eq!(decide(UsageKind::AiTraining).denied_by, Some(DenialReason::Policy));
+            assert!(decide(UsageKind::VoiceCloning).permitted);
+            assert!(decide(UsageKind::CommercialUse).permitted);
+        }
+        assert!(evaluate(Some(flat), UsageKind::AiInference, None, now).unwrap().attribution_required);
+        assert!(!evaluate(Some(assertion), UsageKind::AiInference, None, now).unwrap().attribution_required);
+        // Unmentioned voice cloning is denied, as in the Python SDK.
+        assert!(!evaluate(Some("{}"), UsageKind::VoiceCloning, None, now).unwrap().permitted);
+        assert!(evaluate(None, UsageKind::VoiceCloning, None, now).unwrap().permitted);
+        let odd = evaluate(Some(r#"{"ai_training": "maybe"}"#), UsageKind::AiTraining, None, now).unwrap();
+        assert!(!odd.permitted);
+        assert!(evaluate(Some("[]"), UsageKind::AiTraining, None, now).is_err());
+    }
+
+    #[test]
+    fn test_expiry_and_jurisdiction() {
+        let covenant = r#"{"restrictions": {"expiration": "2025-12-31", "jurisdictions": ["DE", "FR"]}}"#;
+        let decide = |jurisdiction, now| evaluate(Some(covenant), UsageKind::AiInference, jurisdiction, at(now)).unwrap();
+        assert!(decide(Some("de"), "2025-12-31T23:59:59Z").permitted);
+        assert_eq!(decide(Some("DE"), "2026-01-01T00:00:00Z").denied_by, Some(DenialReason::Expired));
+        assert_eq!(decide(Some("US"), "2025-06-01T00:00:00Z").denied_by, Some(DenialReason::Jurisdiction));
+        assert_eq!(decide(None, "2025-06-01T00:00:00Z").denied_by, Some(DenialReason::Jurisdiction));
+
+        let instant = r#"{"expiration_date": "2025-06-01T12:00:00+02:00"}"#;
+        assert!(!evaluate(Some(instant), UsageKind::AiInference, None, at("2025-06-01T10:00:00Z")).unwrap().permitted);
+        let garbled = r#"{"expiration_date": "next spring"}"#;
+        let decision = evaluate(Some(garbled), UsageKind::AiInference, None, at("2025-01-01T00:00:00Z")).unwrap();
+        assert_eq!(decision.denied_by, Some(DenialReason::Expired));
+    }
+}
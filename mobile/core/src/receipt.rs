@@ -0,0 +1,315 @@
+//! Signed detection receipts.
+//!
+//! A verification result says what a watermark claims; a receipt records
+//! that this device heard it. [`ReceiptSigner`] has the listening device
+//! sign a canonical (RFC 8785) statement of the detection, where in the
+//! stream it was heard and the device's clock at the time, so the device
+//! can later prove the detection to a third party with
+//! [`verify_receipt`], which also checks that the receipt is consistent
+//! with itself: a payload hash that the signer and timestamp derive, and a
+//! watermark not signed after it was heard.
+//!
+//! The device key stays with the host app: it signs through
+//! [`DeviceKeyProvider`], so a key in the Secure Enclave or Android
+//! Keystore never leaves the hardware. The device is identified by the
+//! `did:key` of its public key, which is all a verifier needs.
+
+use serde::{Deserialize, Serialize};
+use vouch_sonic_dsp as dsp;
+
+use crate::{canonicalize_covenant, did, signature, SignatureAlgorithm, SonicError, WatermarkResult};
+
+/// `type` of a detection receipt.
+const RECEIPT_TYPE: &str = "VouchDetectionReceipt";
+
+/// How far a watermark's signing time may run ahead of the device clock
+/// that heard it, as `VerifierConfig::max_clock_skew_secs` defaults to.
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// The signed statement of a receipt.
+#[derive(Serialize, Deserialize)]
+struct Receipt {
+    #[serde(rename = "type")]
+    kind: String,
+    /// `did:key` of the device
+    device: String,
+    detection: Detection,
+    stream_position_ms: u64,
+    device_time: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Detection {
+    payload_hash: Option<String>,
+    signer_did: Option<String>,
+    timestamp: Option<u64>,
+    confidence: f32,
+    detection_method: String,
+}
+
+/// Signs with the listening device's key. Implemented by the host app.
+pub trait DeviceKeyProvider: Send + Sync {
+    /// The device's public key: 32 bytes for Ed25519, a SEC1 point for
+    /// ECDSA.
+    fn public_key(&self) -> Vec<u8>;
+    /// The algorithm `sign` signs with.
+    fn algorithm(&self) -> SignatureAlgorithm;
+    /// `message` signed with the device key (ECDSA as 64 bytes of
+    /// `r || s`, not DER), or `None` if the key is unavailable, e.g. the
+    /// user declined the biometric prompt that guards it.
+    fn sign(&self, message: Vec<u8>) -> Option<Vec<u8>>;
+}
+
+/// A detection receipt and the device's signature over its exact bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectionReceipt {
+    /// The receipt's canonical JSON, as signed
+    pub receipt_json: String,
+    /// Device signature over `receipt_json`
+    pub signature: Vec<u8>,
+}
+
+/// Issues detection receipts signed by the device key.
+pub struct ReceiptSigner {
+    provider: Box<dyn DeviceKeyProvider>,
+}
+
+impl ReceiptSigner {
+    pub fn new(provider: Box<dyn DeviceKeyProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// The `did:key` receipts name as the device.
+    pub fn device_did(&self) -> Result<String, SonicError> {
+        did::did_key_for(self.provider.algorithm(), &self.provider.public_key())
+    }
+
+    /// Sign a receipt for `result`, heard `stream_position_ms` into the
+    /// stream at `device_time` (Unix seconds, by the device clock).
+    ///
+    /// Only detections are receipted. The signature is checked against the
+    /// device's public key before it is returned, so a provider that signs
+    /// with another key or encoding fails here rather than at verification.
+    pub fn sign_receipt(
+        &self,
+        result: WatermarkResult,
+        stream_position_ms: u64,
+        device_time: u64,
+    ) -> Result<DetectionReceipt, SonicError> {
+        if !result.detected {
+            return Err(SonicError::InvalidConfig("no watermark was detected".into()));
+        }
+        let algorithm = self.provider.algorithm();
+        let public_key = self.provider.public_key();
+        let receipt = Receipt {
+            kind: RECEIPT_TYPE.into(),
+            device: did::did_key_for(algorithm, &public_key)?,
+            detection: Detection {
+                payload_hash: result.payload_hash,
+                signer_did: result.signer_did,
+                timestamp: result.timestamp,
+                confidence: result.confidence,
+                detection_method: result.detection_method,
+            },
+            stream_position_ms,
+            device_time,
+        };
+        let receipt_json = serde_json::to_string(&receipt).map_err(|e| SonicError::InternalError(e.to_string()))?;
+        let receipt_json = String::from_utf8(canonicalize_covenant(&receipt_json)?)
+            .map_err(|e| SonicError::InternalError(e.to_string()))?;
+
+        let signature = self
+            .provider
+            .sign(receipt_json.clone().into_bytes())
+            .ok_or_else(|| SonicError::InvalidKey("device key is unavailable".into()))?;
+        signature::verify(algorithm, receipt_json.as_bytes(), &signature, &public_key)
+            .map_err(|e| SonicError::InvalidKey(format!("device key provider: {e}")))?;
+        Ok(DetectionReceipt {
+            receipt_json,
+            signature,
+        })
+    }
+}
+
+/// Outcome of [`verify_receipt`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReceiptVerification {
+    /// Signed by the device it names, consistent, and from a trusted device
+    pub valid: bool,
+    /// `did:key` of the device the receipt names
+    pub device_did: Option<String>,
+    /// The device's key is one of the trusted device keys
+    pub trusted_device: bool,
+    /// Payload hash of the detection
+    pub payload_hash: Option<String>,
+    /// Signer DID of the detection, when it was resolved
+    pub signer_did: Option<String>,
+    /// Where in the stream the watermark was heard (ms)
+    pub stream_position_ms: Option<u64>,
+    /// Device clock when it was heard (Unix seconds)
+    pub device_time: Option<u64>,
+    /// Why the receipt is not valid
+    pub error_message: Option<String>,
+}
+
+/// Check `receipt`: that the device it names signed its exact bytes, that
+/// its contents agree with each other, and that the device's public key is
+/// one of `trusted_device_keys` (any device, when the list is empty).
+///
+/// Content checks: a detection with a signer and timestamp must carry the
+/// payload hash they derive, otherwise a SHA-256 hex payload hash; and the
+/// watermark cannot have been signed after the device heard it (beyond
+/// 300 s of clock skew).
+pub fn verify_receipt(receipt: DetectionReceipt, trusted_device_keys: Vec<Vec<u8>>) -> ReceiptVerification {
+    let receipt_json = receipt.receipt_json.as_str();
+    let statement: Receipt = match serde_json::from_str(receipt_json) {
+        Ok(statement) => statement,
+        Err(e) => {
+            return ReceiptVerification {
+                error_message: Some(format!("Receipt is not valid: {e}")),
+                ..Default::default()
+            }
+        }
+    };
+    let mut verification = ReceiptVerification {
+        device_did: Some(statement.device.clone()),
+        payload_hash: statement.detection.payload_hash.clone(),
+        signer_did: statement.detection.signer_did.clone(),
+        stream_position_ms: Some(statement.stream_position_ms),
+        device_time: Some(statement.device_time),
+        ..Default::default()
+    };
+    let (algorithm, public_key) = match did::decode_did_key(&statement.device) {
+        Ok(key) => key,
+        Err(e) => return ReceiptVerification { error_message: Some(e.to_string()), ..verification },
+    };
+    verification.trusted_device = trusted_device_keys
+        .iter()
+        .any(|key| signature::compress_public_key(algorithm, key).is_some_and(|key| key == public_key));
+
+    let error = if statement.kind != RECEIPT_TYPE {
+        Some(format!("Not a detection receipt: {}", statement.kind))
+    } else if let Err(e) = signature::verify(algorithm, receipt_json.as_bytes(), &receipt.signature, &public_key) {
+        Some(e)
+    } else if let Err(e) = check_consistency(&statement) {
+        Some(e)
+    } else if !trusted_device_keys.is_empty() && !verification.trusted_device {
+        Some(format!("Device is not trusted: {}", statement.device))
+    } else {
+        None
+    };
+    verification.valid = error.is_none();
+    verification.error_message = error;
+    verification
+}
+
+/// Why the receipt's detection contradicts itself, if it does.
+fn check_consistency(receipt: &Receipt) -> Result<(), String> {
+    let detection = &receipt.detection;
+    let payload_hash = detection.payload_hash.as_deref().ok_or("Receipt has no payload hash")?;
+    match (&detection.signer_did, detection.timestamp) {
+        (Some(signer), Some(timestamp)) => {
+            if dsp::payload_hash_for(signer, timestamp.saturating_mul(1000)) != payload_hash {
+                return Err("Payload hash does not match the signer and timestamp".into());
+            }
+        }
+        _ => {
+            if payload_hash.len() != 64 || !payload_hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+                return Err(format!("Malformed payload hash {payload_hash}"));
+            }
+        }
+    }
+    if detection.timestamp.is_some_and(|t| t > receipt.device_time.saturating_add(MAX_CLOCK_SKEW_SECS)) {
+        return Err("Watermark was signed after the device heard it".into());
+    }
+    if !(0.0..=1.0).contains(&detection.confidence) {
+        return Err(format!("Confidence {} is out of range", detection.confidence));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+
+    struct EnclaveKey(Option<SigningKey>);
+
+    impl DeviceKeyProvider for EnclaveKey {
+        fn public_key(&self) -> Vec<u8> {
+            let key = SigningKey::from_slice(&[5u8; 32]).unwrap();
+            key.verifying_key().to_encoded_point(false).as_bytes().to_vec()
+        }
+        fn algorithm(&self) -> SignatureAlgorithm {
+            SignatureAlgorithm::Es256
+        }
+        fn sign(&self, message: Vec<u8>) -> Option<Vec<u8>> {
+            let signature: Signature = self.0.as_ref()?.sign(&message);
+            Some(signature.to_bytes().to_vec())
+        }
+    }
+
+    fn device(seed: u8) -> ReceiptSigner {
+        ReceiptSigner::new(Box::new(EnclaveKey(Some(SigningKey::from_slice(&[seed; 32]).unwrap()))))
+    }
+
+    fn heard(signer_did: &str, timestamp: u64) -> WatermarkResult {
+        WatermarkResult {
+            detected: true,
+            confidence: 0.5,
+            signer_did: Some(signer_did.into()),
+            timestamp: Some(timestamp),
+            payload_hash: Some(dsp::payload_hash_for(signer_did, timestamp * 1000)),
+            detection_method: "v3".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_receipt_round_trip() {
+        let signer = device(5);
+        let result = heard("did:web:news.example", 1_800_000_000);
+        let receipt = signer.sign_receipt(result.clone(), 12_500, 1_800_000_060).unwrap();
+        let verification = verify_receipt(receipt.clone(), vec![]);
+        assert!(verification.valid, "{:?}", verification.error_message);
+        assert!(!verification.trusted_device);
+        assert_eq!(verification.device_did, Some(signer.device_did().unwrap()));
+        assert!(signer.device_did().unwrap().starts_with("did:key:zDn"));
+        assert_eq!(verification.stream_position_ms, Some(12_500));
+        assert_eq!(verification.payload_hash, result.payload_hash);
+
+        let edited = DetectionReceipt {
+            receipt_json: receipt.receipt_json.replace("12500", "13500"),
+            ..receipt
+        };
+        assert!(!verify_receipt(edited, vec![]).valid);
+        assert!(!verify_receipt(DetectionReceipt { receipt_json: "{}".into(), signature: vec![] }, vec![]).valid);
+
+        assert!(signer.sign_receipt(WatermarkResult::default(), 0, 0).is_err());
+        let wrong_key = ReceiptSigner::new(Box::new(EnclaveKey(Some(SigningKey::from_slice(&[6u8; 32]).unwrap()))));
+        assert!(wrong_key.sign_receipt(result.clone(), 0, 0).is_err());
+        let locked = ReceiptSigner::new(Box::new(EnclaveKey(None)));
+        assert!(matches!(locked.sign_receipt(result, 0, 0), Err(SonicError::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_receipt_consistency_and_trust() {
+        let signer = device(5);
+        let device_key = EnclaveKey(None).public_key();
+        let receipt = signer.sign_receipt(heard("did:web:news.example", 1_800_000_000), 0, 1_800_000_060).unwrap();
+        let trusted = verify_receipt(receipt.clone(), vec![device_key.clone()]);
+        assert!(trusted.valid && trusted.trusted_device);
+        let other_device = SigningKey::from_slice(&[6u8; 32]).unwrap();
+        let untrusted = verify_receipt(receipt, vec![other_device.verifying_key().to_sec1_bytes().to_vec()]);
+        assert!(!untrusted.valid && !untrusted.trusted_device);
+
+        let mut mismatched = heard("did:web:news.example", 1_800_000_000);
+        mismatched.payload_hash = heard("did:web:other.example", 1_800_000_000).payload_hash;
+        let receipt = signer.sign_receipt(mismatched, 0, 1_800_000_060).unwrap();
+        assert!(!verify_receipt(receipt, vec![]).valid);
+
+        // Heard an hour before it was signed.
+        let receipt = signer.sign_receipt(heard("did:web:news.example", 1_800_000_000), 0, 1_799_996_400).unwrap();
+        assert!(!verify_receipt(receipt, vec![device_key]).valid);
+    }
+}
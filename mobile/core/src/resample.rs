@@ -0,0 +1,154 @@
+//! Sample-rate conversion to the detection rate.
+//!
+//! Microphones deliver 44.1 or 48 kHz while a listener may detect at another
+//! rate; `SonicConfig::input_sample_rate` names the capture rate and the
+//! listener converts every buffer on the way in. Conversion is band-limited
+//! (FFT-based synchronous resampling), so nothing above the target Nyquist
+//! aliases down into the watermark bands.
+
+use rubato::{FftFixedIn, Resampler};
+
+use crate::SonicError;
+
+/// Input chunk length the resampler consumes at a time.
+const RESAMPLE_CHUNK: usize = 1024;
+
+/// Resampler that accepts mono input in pieces of any length, for streamed
+/// audio. The concatenated output of `process` calls and a final `finish` is
+/// the same as resampling the concatenated input in one go.
+pub(crate) struct StreamResampler {
+    resampler: FftFixedIn<f32>,
+    from: u32,
+    to: u32,
+    /// Input not yet making up a whole chunk
+    input: Vec<f32>,
+    /// Input samples taken in since the last `finish`
+    consumed: u64,
+    /// Output samples produced since the last `finish`, delay included
+    produced: u64,
+}
+
+impl StreamResampler {
+    pub(crate) fn new(from: u32, to: u32) -> Result<Self, SonicError> {
+        let resampler = FftFixedIn::<f32>::new(from as usize, to as usize, RESAMPLE_CHUNK, 2, 1)
+            .map_err(|e| resample_error(&e))?;
+        Ok(Self {
+            resampler,
+            from,
+            to,
+            input: Vec::with_capacity(RESAMPLE_CHUNK),
+            consumed: 0,
+            produced: 0,
+        })
+    }
+
+    /// Input and output rates.
+    pub(crate) fn rates(&self) -> (u32, u32) {
+        (self.from, self.to)
+    }
+
+    /// Resample the next piece of input. Up to a chunk of it is held back
+    /// until more arrives (or `finish`).
+    pub(crate) fn process(&mut self, samples: &[f32]) -> Result<Vec<f32>, SonicError> {
+        self.consumed += samples.len() as u64;
+        self.input.extend_from_slice(samples);
+        let mut out = Vec::new();
+        let mut chunks = self.input.chunks_exact(RESAMPLE_CHUNK);
+        for chunk in &mut chunks {
+            let block = self.resampler.process(&[chunk], None).map_err(|e| resample_error(&e))?;
+            out.extend_from_slice(&block[0]);
+        }
+        let held = chunks.remainder().len();
+        self.input.drain(..self.input.len() - held);
+        Ok(self.trim(out))
+    }
+
+    /// Resample the input still held back, flush the delay line, and start
+    /// over for a new stream.
+    pub(crate) fn finish(&mut self) -> Result<Vec<f32>, SonicError> {
+        let delay = self.resampler.output_delay() as u64;
+        let wanted = self.consumed * self.to as u64 / self.from as u64 + delay;
+        let mut out = Vec::new();
+        if self.consumed > 0 {
+            let block = self
+                .resampler
+                .process_partial(Some(&[self.input.as_slice()]), None)
+                .map_err(|e| resample_error(&e))?;
+            out.extend_from_slice(&block[0]);
+            while self.produced + (out.len() as u64) < wanted {
+                let block = self
+                    .resampler
+                    .process_partial(None::<&[&[f32]]>, None)
+                    .map_err(|e| resample_error(&e))?;
+                if block[0].is_empty() {
+                    break;
+                }
+                out.extend_from_slice(&block[0]);
+            }
+            out.truncate(wanted.saturating_sub(self.produced) as usize);
+        }
+        let out = self.trim(out);
+        self.resampler.reset();
+        self.input.clear();
+        self.consumed = 0;
+        self.produced = 0;
+        Ok(out)
+    }
+
+    /// Count `out` as produced and drop what of it falls in the resampler's
+    /// initial delay.
+    fn trim(&mut self, mut out: Vec<f32>) -> Vec<f32> {
+        let delay = self.resampler.output_delay() as u64;
+        let skip = delay.saturating_sub(self.produced).min(out.len() as u64) as usize;
+        self.produced += out.len() as u64;
+        out.drain(..skip);
+        out
+    }
+}
+
+/// Band-limited resampling of a mono signal, trimmed to the nominal output
+/// length (`samples.len() * to / from`) with the resampler delay removed.
+pub(crate) fn resample(samples: &[f32], from: u32, to: u32) -> Result<Vec<f32>, SonicError> {
+    if from == to || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+    let mut resampler = StreamResampler::new(from, to)?;
+    let mut out = resampler.process(samples)?;
+    out.extend(resampler.finish()?);
+    Ok(out)
+}
+
+fn resample_error(e: &dyn std::fmt::Display) -> SonicError {
+    SonicError::ProcessingFailed(format!("resampling failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streamed_pieces_match_one_shot() {
+        let tone: Vec<f32> = (0..10_000)
+            .map(|n| (std::f32::consts::TAU * 1_000.0 * n as f32 / 48_000.0).sin() * 0.5)
+            .collect();
+        let whole = resample(&tone, 48_000, 16_000).unwrap();
+        assert_eq!(whole.len(), 10_000 / 3);
+
+        let mut stream = StreamResampler::new(48_000, 16_000).unwrap();
+        let mut pieces = Vec::new();
+        for piece in tone.chunks(777) {
+            pieces.extend(stream.process(piece).unwrap());
+        }
+        pieces.extend(stream.finish().unwrap());
+        assert_eq!(pieces.len(), whole.len());
+        assert!(pieces.iter().zip(&whole).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        // The 1 kHz tone survives at its amplitude, away from the edges.
+        let peak = whole[500..3000].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.01, "peak {peak}");
+
+        // `finish` leaves the resampler ready for the next stream.
+        let again = stream.process(&tone).unwrap().into_iter().chain(stream.finish().unwrap());
+        assert!(again.zip(&whole).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,118 @@
+//! Listening sessions.
+//!
+//! Each `start_listening` opens a session, named by a random ID that every
+//! result the listener produces until `stop_listening` carries, so logged
+//! and stored detections can be grouped by the listening run they came
+//! from. Stopping closes the session with a [`SessionSummary`].
+//!
+//! A session also counts the samples the listener takes in, as one stream:
+//! buffers passed to the `process_*` methods follow each other, and pushed
+//! samples follow on as they arrive. Each result is stamped with its index
+//! and where its buffer or window starts in that stream, so detections can
+//! be lined up with a recording of the session.
+
+use std::time::Instant;
+
+use rand::{rngs::OsRng, RngCore};
+
+use crate::WatermarkResult;
+
+/// What happened during a listening session, returned by `stop_listening`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SessionSummary {
+    pub session_id: String,
+    /// From `start_listening` to `stop_listening`, in ms
+    pub duration_ms: u64,
+    /// Buffers and streamed windows the listener processed
+    pub buffers_processed: u64,
+    /// Signers detected, in first-detected order: the DID, or the payload
+    /// hash for a watermark whose signer is resolved server-side
+    pub unique_signers: Vec<String>,
+    /// Highest confidence of any buffer processed (0.0 if none)
+    pub peak_confidence: f32,
+}
+
+/// The open session of a listening listener.
+pub(crate) struct Session {
+    id: String,
+    started: Instant,
+    buffers_processed: u64,
+    /// Stream time before the last sample rate change, in ms
+    base_ms: u64,
+    /// Samples taken in since then, at the detection rate
+    samples_consumed: u64,
+    unique_signers: Vec<String>,
+    peak_confidence: f32,
+}
+
+impl Session {
+    pub(crate) fn start() -> Self {
+        let mut id = [0u8; 16];
+        OsRng.fill_bytes(&mut id);
+        Self {
+            id: id.iter().map(|b| format!("{b:02x}")).collect(),
+            started: Instant::now(),
+            buffers_processed: 0,
+            base_ms: 0,
+            samples_consumed: 0,
+            unique_signers: Vec::new(),
+            peak_confidence: 0.0,
+        }
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Add `samples` streamed samples to the stream, returning its length.
+    pub(crate) fn consume(&mut self, samples: usize) -> u64 {
+        self.samples_consumed += samples as u64;
+        self.samples_consumed
+    }
+
+    /// Samples taken in so far (since the last sample rate change).
+    pub(crate) fn position(&self) -> u64 {
+        self.samples_consumed
+    }
+
+    /// Restart sample counting for a new detection rate, keeping the
+    /// stream time the samples at `old_sample_rate` covered.
+    pub(crate) fn rebase(&mut self, old_sample_rate: u32) {
+        self.base_ms += self.samples_consumed * 1000 / u64::from(old_sample_rate.max(1));
+        self.samples_consumed = 0;
+    }
+
+    /// Count a processed buffer of `len` samples and stamp its result. A
+    /// streamed window starts at sample `at`, already consumed; a buffer
+    /// passed directly (`at` of `None`) is appended to the stream.
+    pub(crate) fn record(&mut self, result: &mut WatermarkResult, len: usize, at: Option<u64>, sample_rate: u32) {
+        let start = at.unwrap_or_else(|| {
+            let start = self.samples_consumed;
+            self.consume(len);
+            start
+        });
+        result.session_id = Some(self.id.clone());
+        result.frame_index = Some(self.buffers_processed);
+        result.stream_offset_ms = Some(self.base_ms + start * 1000 / u64::from(sample_rate.max(1)));
+        self.buffers_processed += 1;
+        self.peak_confidence = self.peak_confidence.max(result.confidence);
+        if !result.detected {
+            return;
+        }
+        if let Some(signer) = result.signer_did.as_ref().or(result.payload_hash.as_ref()) {
+            if !self.unique_signers.contains(signer) {
+                self.unique_signers.push(signer.clone());
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> SessionSummary {
+        SessionSummary {
+            session_id: self.id,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            buffers_processed: self.buffers_processed,
+            unique_signers: self.unique_signers,
+            peak_confidence: self.peak_confidence,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Signature algorithms the verifier accepts.
+//!
+//! Vouch signers sign with Ed25519, but some signer ecosystems (Ethereum-style
+//! wallets, hardware security modules, WebAuthn) only offer ECDSA on
+//! secp256k1 or P-256. A payload names its algorithm, and the verifier
+//! dispatches on it here. ECDSA signatures use the JOSE encoding: SHA-256 of
+//! the message, the signature as 64 bytes of `r || s`, and the public key as
+//! a SEC1 point, compressed (33 bytes) or not (65 bytes).
+
+use ed25519_dalek::Verifier;
+
+/// Algorithm of a payload signature, by its JOSE name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SignatureAlgorithm {
+    /// EdDSA on Curve25519 (`EdDSA`); 32-byte public keys
+    #[default]
+    Ed25519,
+    /// ECDSA on secp256k1 with SHA-256 (`ES256K`)
+    Es256k,
+    /// ECDSA on P-256 with SHA-256 (`ES256`)
+    Es256,
+}
+
+/// Check `signature` over `message` under `public_key` with `algorithm`,
+/// or say why it does not verify.
+pub(crate) fn verify(
+    algorithm: SignatureAlgorithm,
+    message: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<(), String> {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => {
+            let key = public_key
+                .try_into()
+                .map_err(|_| "Public key must be 32 bytes".to_string())?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&key).map_err(|e| format!("Invalid public key: {}", e))?;
+            let signature =
+                ed25519_dalek::Signature::from_slice(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+            key.verify(message, &signature)
+                .map_err(|e| format!("Signature verification failed: {}", e))
+        }
+        SignatureAlgorithm::Es256k => {
+            let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|e| format!("Invalid public key: {}", e))?;
+            let signature =
+                k256::ecdsa::Signature::from_slice(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+            key.verify(message, &signature)
+                .map_err(|e| format!("Signature verification failed: {}", e))
+        }
+        SignatureAlgorithm::Es256 => {
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|e| format!("Invalid public key: {}", e))?;
+            let signature =
+                p256::ecdsa::Signature::from_slice(signature).map_err(|e| format!("Invalid signature: {}", e))?;
+            key.verify(message, &signature)
+                .map_err(|e| format!("Signature verification failed: {}", e))
+        }
+    }
+}
+
+/// `public_key` as the compressed SEC1 point `did:key` encodes, for the
+/// ECDSA algorithms; Ed25519 keys are returned as they are.
+pub(crate) fn compress_public_key(algorithm: SignatureAlgorithm, public_key: &[u8]) -> Option<Vec<u8>> {
+    match algorithm {
+        SignatureAlgorithm::Ed25519 => Some(public_key.to_vec()),
+        SignatureAlgorithm::Es256k => k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+            .ok()
+            .map(|key| key.to_encoded_point(true).as_bytes().to_vec()),
+        SignatureAlgorithm::Es256 => p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+            .ok()
+            .map(|key| key.to_encoded_point(true).as_bytes().to_vec()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::Signer;
+
+    #[test]
+    fn test_dispatches_on_algorithm() {
+        let k1 = k256::ecdsa::SigningKey::from_slice(&[3u8; 32]).unwrap();
+        let k1_signature: k256::ecdsa::Signature = k1.sign(b"clip");
+        let k1_key = k1.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        let r1 = p256::ecdsa::SigningKey::from_slice(&[3u8; 32]).unwrap();
+        let r1_signature: p256::ecdsa::Signature = r1.sign(b"clip");
+        let r1_key = r1.verifying_key().to_encoded_point(false).as_bytes().to_vec();
+
+        assert_eq!(verify(SignatureAlgorithm::Es256k, b"clip", &k1_signature.to_bytes(), &k1_key), Ok(()));
+        assert_eq!(verify(SignatureAlgorithm::Es256, b"clip", &r1_signature.to_bytes(), &r1_key), Ok(()));
+        assert!(verify(SignatureAlgorithm::Es256k, b"clap", &k1_signature.to_bytes(), &k1_key).is_err());
+        // The same bytes under the other curve do not verify.
+        assert!(verify(SignatureAlgorithm::Es256, b"clip", &k1_signature.to_bytes(), &k1_key).is_err());
+        assert!(verify(SignatureAlgorithm::Ed25519, b"clip", &k1_signature.to_bytes(), &k1_key)
+            .unwrap_err()
+            .contains("32 bytes"));
+
+        assert_eq!(compress_public_key(SignatureAlgorithm::Es256, &r1_key).unwrap().len(), 33);
+        assert!(compress_public_key(SignatureAlgorithm::Es256k, &[0u8; 33]).is_none());
+    }
+}
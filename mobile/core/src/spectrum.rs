@@ -0,0 +1,643 @@
+//! Spectral analysis helpers for the listener.
+//!
+//! The v3 detector itself lives in `vouch-sonic-dsp`; this module holds the
+//! listener-side FFT plumbing used for diagnostics and UI overlays, built on
+//! the same v3 codec parameters so the bins line up with what the detector
+//! actually correlates against.
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use rustfft::num_complex::Complex;
+use vouch_sonic_dsp as dsp;
+
+use crate::{kernels, SonicConfig, SonicError, WindowFunction};
+
+/// Largest FFT the listener will plan. Zero-length or larger transforms are
+/// refused up front rather than handed to the planner.
+pub(crate) const MAX_FFT_SIZE: usize = 1 << 20;
+
+/// Default FFT length of the audio quality estimate (same as the DSP
+/// crate's); `SonicConfig::stft_fft_size` sets it per listener.
+pub(crate) const QUALITY_FFT_SIZE: usize = 512;
+
+/// Share of the spectral energy below the rolloff frequency.
+const ROLLOFF_FRACTION: f64 = 0.85;
+
+/// Samples per v3 chip at `sample_rate`.
+pub(crate) fn chip_len(sample_rate: u32) -> usize {
+    (dsp::V3_CHIP_DURATION_MS / 1000.0 * sample_rate as f32) as usize
+}
+
+/// Hann window of `size` taps.
+pub(crate) fn hann_window(size: usize) -> Vec<f32> {
+    window(WindowFunction::Hann, size)
+}
+
+/// `function` sampled at `size` taps (symmetric, peak 1.0).
+pub(crate) fn window(function: WindowFunction, size: usize) -> Vec<f32> {
+    if size < 2 || function == WindowFunction::Rectangular {
+        return vec![1.0; size];
+    }
+    let (a0, a1, a2) = match function {
+        WindowFunction::Rectangular | WindowFunction::Hann => (0.5, 0.5, 0.0),
+        WindowFunction::Hamming => (0.54, 0.46, 0.0),
+        WindowFunction::Blackman => (0.42, 0.5, 0.08),
+    };
+    (0..size)
+        .map(|n| {
+            let phase = std::f32::consts::TAU * n as f32 / (size - 1) as f32;
+            a0 - a1 * phase.cos() + a2 * (2.0 * phase).cos()
+        })
+        .collect()
+}
+
+/// Real-input FFT plans kept across calls, so each size is planned once.
+///
+/// Every signal the listener analyzes is real, so transforms are real-to-
+/// complex: half the work and memory of a complex FFT, returning only the
+/// `fft_size / 2 + 1` non-negative-frequency bins (the rest mirror them).
+pub(crate) struct FftPlans {
+    planner: Mutex<RealFftPlanner<f32>>,
+}
+
+impl Default for FftPlans {
+    fn default() -> Self {
+        Self {
+            planner: Mutex::new(RealFftPlanner::new()),
+        }
+    }
+}
+
+impl FftPlans {
+    /// Plan (or fetch the cached plan for) a forward FFT of `fft_size`.
+    pub(crate) fn forward(&self, fft_size: usize) -> Result<Arc<dyn RealToComplex<f32>>, SonicError> {
+        check_fft_size(fft_size)?;
+        Ok(self.planner.lock().plan_fft_forward(fft_size))
+    }
+
+    /// Plan (or fetch the cached plan for) an inverse FFT of `fft_size`.
+    pub(crate) fn inverse(&self, fft_size: usize) -> Result<Arc<dyn ComplexToReal<f32>>, SonicError> {
+        check_fft_size(fft_size)?;
+        Ok(self.planner.lock().plan_fft_inverse(fft_size))
+    }
+
+    /// Forward FFT of a real frame, zero-padded (or truncated) to `fft_size`:
+    /// its `fft_size / 2 + 1` non-negative-frequency bins.
+    pub(crate) fn fft(&self, frame: &[f32], fft_size: usize) -> Result<Vec<Complex<f32>>, SonicError> {
+        let plan = self.forward(fft_size)?;
+        let mut input: Vec<f32> = (0..fft_size).map(|i| frame.get(i).copied().unwrap_or(0.0)).collect();
+        let mut spectrum = plan.make_output_vec();
+        plan.process(&mut input, &mut spectrum).map_err(fft_error)?;
+        Ok(spectrum)
+    }
+}
+
+fn check_fft_size(fft_size: usize) -> Result<(), SonicError> {
+    if fft_size == 0 || fft_size > MAX_FFT_SIZE {
+        return Err(SonicError::ProcessingFailed(format!(
+            "unsupported FFT size {fft_size} (must be 1..={MAX_FFT_SIZE})"
+        )));
+    }
+    Ok(())
+}
+
+fn fft_error(e: realfft::FftError) -> SonicError {
+    SonicError::ProcessingFailed(format!("FFT failed: {e}"))
+}
+
+/// Forward FFT of a real frame, zero-padded (or truncated) to `fft_size`:
+/// its `fft_size / 2 + 1` non-negative-frequency bins.
+///
+/// Fails with `ProcessingFailed` for an unsupported length instead of
+/// panicking, so callers can skip the FFT-dependent stage and carry on.
+pub(crate) fn compute_fft(frame: &[f32], fft_size: usize) -> Result<Vec<Complex<f32>>, SonicError> {
+    FftPlans::default().fft(frame, fft_size)
+}
+
+/// Weight of one-sided bin `k` of an `fft_size` transform in two-sided sums:
+/// every bin but DC and Nyquist stands for itself and its mirror image.
+fn fold(k: usize, fft_size: usize) -> f64 {
+    if k == 0 || 2 * k == fft_size {
+        1.0
+    } else {
+        2.0
+    }
+}
+
+/// Sliding FFT correlation against a fixed reference, planned once and reused
+/// across segments. A segment longer than the `segment_len` it was planned
+/// for is correlated block by block (overlap-save).
+pub(crate) struct ReferenceCorrelator {
+    reference_len: usize,
+    reference_norm: f64,
+    spectrum: Vec<Complex<f32>>,
+    fft_size: usize,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+}
+
+impl ReferenceCorrelator {
+    pub(crate) fn new(reference: &[f32], segment_len: usize) -> Result<Self, SonicError> {
+        let n = (segment_len + reference.len()).next_power_of_two();
+        let plans = FftPlans::default();
+        Ok(Self {
+            reference_len: reference.len(),
+            reference_norm: kernels::sum_squares(reference).sqrt(),
+            spectrum: plans.fft(reference, n)?,
+            fft_size: n,
+            forward: plans.forward(n)?,
+            inverse: plans.inverse(n)?,
+        })
+    }
+
+    /// Samples in the reference.
+    pub(crate) fn reference_len(&self) -> usize {
+        self.reference_len
+    }
+
+    /// `out[l]` is `sum_i segment[l + i] * reference[i]` for every full-overlap
+    /// lag (`segment.len() - reference.len() + 1` of them).
+    pub(crate) fn correlate(&self, segment: &[f32]) -> Vec<f32> {
+        let n = self.fft_size;
+        if self.reference_len == 0 || segment.len() < self.reference_len {
+            return Vec::new();
+        }
+        let lags = segment.len() - self.reference_len + 1;
+        // Each block yields the lags whose window lies wholly inside it.
+        let step = n - self.reference_len + 1;
+        let mut out = Vec::with_capacity(lags);
+        let mut input = vec![0.0f32; n];
+        let mut buf = self.forward.make_output_vec();
+        for start in (0..lags).step_by(step) {
+            let block = &segment[start..segment.len().min(start + n)];
+            input[..block.len()].copy_from_slice(block);
+            input[block.len()..].fill(0.0);
+            if self.forward.process(&mut input, &mut buf).is_err() {
+                return Vec::new();
+            }
+            for (x, r) in buf.iter_mut().zip(&self.spectrum) {
+                *x *= r.conj();
+            }
+            // DC and Nyquist of a real signal's spectrum are real; drop the
+            // rounding residue the inverse would otherwise reject.
+            buf[0].im = 0.0;
+            buf[n / 2].im = 0.0;
+            if self.inverse.process(&mut buf, &mut input).is_err() {
+                return Vec::new();
+            }
+            let block_lags = block.len() - self.reference_len + 1;
+            out.extend(input[..block_lags].iter().map(|x| x / n as f32));
+        }
+        out
+    }
+
+    /// `correlate`, normalized per lag by the energy of the segment window
+    /// and of the reference: 1.0 where the segment matches the reference up
+    /// to scale, near 0.0 for unrelated audio. Silent windows score 0.0.
+    pub(crate) fn correlate_normalized(&self, segment: &[f32]) -> Vec<f64> {
+        let corr = self.correlate(segment);
+        let mut prefix = vec![0.0f64; segment.len() + 1];
+        for (i, &s) in segment.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + s as f64 * s as f64;
+        }
+        corr.iter()
+            .enumerate()
+            .map(|(l, &c)| {
+                let window = (prefix[l + self.reference_len] - prefix[l]).max(0.0).sqrt();
+                if window > 0.0 {
+                    c as f64 / (window * self.reference_norm)
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// Audio quality metric behind `WatermarkResult::audio_quality`.
+///
+/// The listener hands the estimator the buffer's STFT magnitude spectrum
+/// (see [`estimate_quality`]; phase is discarded) together with the raw
+/// samples. With `SonicConfig::exclude_dc_and_nyquist` the DC and Nyquist
+/// bins arrive zeroed. Implementations return a score in 0.0 - 1.0; swap in a
+/// perceptual or learned metric with `SonicListener::set_quality_estimator`.
+pub trait QualityEstimator: Send + Sync {
+    fn estimate(&self, spectrum: &[Complex<f32>], samples: &[f32]) -> f32;
+}
+
+/// Default estimator: spectral balance (0.5 - 1.0), the ratio of upper to
+/// lower half-band energy, the same heuristic the DSP crate reports. Buffers
+/// shorter than one FFT frame get a neutral 0.5.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpectralBalanceEstimator;
+
+impl QualityEstimator for SpectralBalanceEstimator {
+    fn estimate(&self, spectrum: &[Complex<f32>], samples: &[f32]) -> f32 {
+        let fft_size = spectrum.len();
+        if samples.len() < fft_size || fft_size < 4 {
+            return 0.5;
+        }
+        // Energy sums in f64 so large FFTs don't round away the quieter band.
+        let band_energy =
+            |bins: &[Complex<f32>]| -> f64 { bins.iter().map(|c| c.norm_sqr() as f64).sum() };
+        let low_energy = band_energy(&spectrum[..fft_size / 4]);
+        let high_energy = band_energy(&spectrum[fft_size / 4..fft_size / 2]);
+        let ratio = (high_energy / (low_energy + 1e-10)) as f32;
+        (ratio.min(1.0) * 0.5 + 0.5).min(1.0)
+    }
+}
+
+/// Working buffers of the quality STFT.
+///
+/// The listener keeps one across buffers: once it has been sized for the
+/// configured FFT and window, estimating the quality of another buffer
+/// allocates nothing.
+#[derive(Default)]
+pub(crate) struct StftScratch {
+    /// Window taps, and the window and length they were computed for
+    taps: Vec<f32>,
+    taps_for: Option<(WindowFunction, usize)>,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    fft_scratch: Vec<Complex<f32>>,
+    power: Vec<f64>,
+    /// Two-sided magnitude spectrum handed to the estimator
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl StftScratch {
+    /// Size every buffer for `fft_size` frames tapered by `function`, if
+    /// they are not already.
+    pub(crate) fn prepare(
+        &mut self,
+        plan: &dyn RealToComplex<f32>,
+        fft_size: usize,
+        function: WindowFunction,
+    ) {
+        if self.taps_for != Some((function, fft_size)) {
+            self.taps = window(function, fft_size);
+            self.taps_for = Some((function, fft_size));
+        }
+        self.input.resize(fft_size, 0.0);
+        self.output.resize(plan.complex_len(), Complex::default());
+        self.fft_scratch.resize(plan.get_scratch_len(), Complex::default());
+        self.power.resize(plan.complex_len(), 0.0);
+        self.spectrum.resize(fft_size, Complex::default());
+    }
+
+    /// One-sided power spectrum computed by the last [`stft_power`] call.
+    pub(crate) fn power(&self) -> &[f64] {
+        &self.power
+    }
+}
+
+/// One-sided power spectrum (`fft_size / 2 + 1` bins) of `samples` averaged
+/// over STFT frames of `fft_size` samples, each tapered by `function` and
+/// advancing by `hop` (at least 1). Computed in `scratch`.
+///
+/// A buffer shorter than one frame is analyzed as a single zero-padded
+/// frame. With `remove_mean` each frame's mean is subtracted before the
+/// taper, so a DC offset cannot leak past bin 0 into its neighbours.
+pub(crate) fn stft_power<'a>(
+    plans: &FftPlans,
+    scratch: &'a mut StftScratch,
+    samples: &[f32],
+    fft_size: usize,
+    function: WindowFunction,
+    hop: usize,
+    remove_mean: bool,
+) -> Result<&'a [f64], SonicError> {
+    let plan = plans.forward(fft_size)?;
+    scratch.prepare(plan.as_ref(), fft_size, function);
+    let StftScratch { taps, input, output, fft_scratch, power, .. } = scratch;
+    power.fill(0.0);
+    let hop = hop.max(1);
+    let frames = samples.len().saturating_sub(fft_size) / hop + 1;
+    for f in 0..frames {
+        let frame = &samples[f * hop..(f * hop + fft_size).min(samples.len())];
+        let mean = if remove_mean && !frame.is_empty() {
+            frame.iter().sum::<f32>() / frame.len() as f32
+        } else {
+            0.0
+        };
+        for (i, (x, w)) in input.iter_mut().zip(taps.iter()).enumerate() {
+            *x = frame.get(i).map_or(0.0, |s| (s - mean) * w);
+        }
+        plan.process_with_scratch(input, output, fft_scratch).map_err(fft_error)?;
+        for (p, c) in power.iter_mut().zip(output.iter()) {
+            *p += c.norm_sqr() as f64;
+        }
+    }
+    power.iter_mut().for_each(|p| *p /= frames as f64);
+    Ok(power)
+}
+
+/// Run `estimator` over the STFT of `samples`: `fft_size` frames tapered by
+/// `config.stft_window`, overlapping by `config.stft_overlap` of a frame,
+/// their power averaged into one magnitude spectrum, mirrored out to all
+/// `fft_size` bins. With `config.exclude_dc_and_nyquist` the frames are
+/// mean-removed and the DC and Nyquist bins zeroed: a microphone's DC offset
+/// lands in bin 0 and would otherwise outweigh the band energies. Fails only
+/// when the FFT itself cannot run.
+pub(crate) fn estimate_quality(
+    plans: &FftPlans,
+    scratch: &mut StftScratch,
+    samples: &[f32],
+    fft_size: usize,
+    config: &SonicConfig,
+    estimator: &dyn QualityEstimator,
+) -> Result<f32, SonicError> {
+    let hop = (fft_size as f32 * (1.0 - config.stft_overlap)).round() as usize;
+    let exclude_dc_and_nyquist = config.exclude_dc_and_nyquist;
+    stft_power(plans, scratch, samples, fft_size, config.stft_window, hop, exclude_dc_and_nyquist)?;
+    let StftScratch { power, spectrum, .. } = scratch;
+    for (k, bin) in spectrum.iter_mut().enumerate() {
+        *bin = Complex::new(power[k.min(fft_size - k)].sqrt() as f32, 0.0);
+    }
+    if exclude_dc_and_nyquist {
+        spectrum[0] = Complex::default();
+        spectrum[fft_size / 2] = Complex::default();
+    }
+    Ok(estimator.estimate(spectrum, samples))
+}
+
+/// Frequency below which `fraction` of the energy of the one-sided power
+/// spectrum `power` lies, at `sample_rate`. The DC bin is left out, so an
+/// offset cannot drag the rolloff down; `None` when nothing else remains.
+pub(crate) fn spectral_rolloff(power: &[f64], sample_rate: u32, fraction: f64) -> Option<f32> {
+    let bins = power.get(1..).filter(|b| !b.is_empty())?;
+    let total: f64 = bins.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let fft_size = (power.len() - 1) * 2;
+    let mut below = 0.0;
+    let k = bins
+        .iter()
+        .position(|p| {
+            below += p;
+            below >= fraction * total
+        })
+        .unwrap_or(bins.len() - 1)
+        + 1;
+    Some(k as f32 * sample_rate as f32 / fft_size as f32)
+}
+
+/// The spectral rolloff of `power`, when it suggests the audio was captured
+/// at a higher rate than the `sample_rate` it is read at.
+///
+/// Audio read below its true rate is slowed down: its whole spectrum is
+/// compressed toward DC by the ratio of the rates, and the watermark bands
+/// with it. Speech and music normally keep more than 15% of their energy
+/// above the lowest watermark tone (800 Hz), so a rolloff below it means
+/// either a host the watermark could not hide in or, far more often, 48 kHz
+/// capture handed to a listener configured for 16 kHz or less. The reverse
+/// mistake stretches the spectrum upward, which broadband audio already
+/// fills, so it is not flagged.
+pub(crate) fn suspect_sample_rate(power: &[f64], sample_rate: u32) -> Option<f32> {
+    spectral_rolloff(power, sample_rate, ROLLOFF_FRACTION).filter(|&rolloff| rolloff < dsp::V3_LAYER_BANDS[0].0)
+}
+
+/// One chip of the v3 reference watermark: every FSK tone that fits below
+/// Nyquist, phase-reset at the chip start exactly as the embedder does.
+fn reference_chip(sample_rate: u32, len: usize) -> Vec<f32> {
+    let sr = sample_rate as f32;
+    let tones: Vec<f32> = dsp::V3_LAYER_BANDS
+        .iter()
+        .filter(|&&(_, _, _, high1)| high1 <= sr / 2.0)
+        .flat_map(|&(l0, l1, h0, h1)| [l0, l1, h0, h1])
+        .collect();
+    (0..len)
+        .map(|s| {
+            let t = s as f32 / sr;
+            tones
+                .iter()
+                .map(|f| (std::f32::consts::TAU * f * t).sin())
+                .sum()
+        })
+        .collect()
+}
+
+/// Per-bin watermark contribution map (one-sided, `chip_len / 2 + 1` bins).
+///
+/// For each chip-aligned frame this computes the cross-spectral density
+/// `Re{X(k) R*(k)}` between the frame and the v3 reference tone comb, averages
+/// it over frames, and normalizes by the frame and reference energies. By
+/// Parseval the bins sum to the normalized correlation the FSK correlators
+/// see, so the map shows *where in the spectrum* that correlation comes from:
+/// an in-band watermark piles up on the layer tone bins, while noise averages
+/// toward zero everywhere. Bin `k` sits at `k * sample_rate / chip_len` Hz.
+///
+/// Returns an empty map when the buffer is shorter than one chip.
+pub fn watermark_energy_map(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let n = chip_len(sample_rate);
+    let frames = samples.len().checked_div(n).unwrap_or(0);
+    if frames == 0 {
+        return Vec::new();
+    }
+    let window = hann_window(n);
+    let windowed = |frame: &[f32]| -> Vec<f32> {
+        frame.iter().zip(&window).map(|(x, w)| x * w).collect()
+    };
+
+    let Ok(reference) = compute_fft(&windowed(&reference_chip(sample_rate, n)), n) else {
+        return Vec::new();
+    };
+    let ref_energy: f64 = reference.iter().enumerate().map(|(k, c)| fold(k, n) * c.norm_sqr() as f64).sum();
+
+    let bins = n / 2 + 1;
+    let mut csd = vec![0.0f64; bins];
+    let mut frame_energy = 0.0f64;
+    for frame in samples.chunks_exact(n) {
+        let Ok(spectrum) = compute_fft(&windowed(frame), n) else {
+            return Vec::new();
+        };
+        frame_energy += spectrum.iter().enumerate().map(|(k, c)| fold(k, n) * c.norm_sqr() as f64).sum::<f64>();
+        for (k, acc) in csd.iter_mut().enumerate() {
+            *acc += (spectrum[k] * reference[k].conj()).re as f64;
+        }
+    }
+
+    let norm = ((frame_energy / frames as f64) * ref_energy).sqrt();
+    if norm <= 1e-20 {
+        return vec![0.0; bins];
+    }
+    csd.iter()
+        .enumerate()
+        // Fold the mirrored negative-frequency half into the one-sided map.
+        .map(|(k, &v)| (fold(k, n) * v / frames as f64 / norm) as f32)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: u32 = 44_100;
+
+    fn xorshift(seed: u64) -> impl FnMut() -> f32 {
+        let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+        }
+    }
+
+    // A v3-style in-band watermark: per chip and layer, the high or low FSK
+    // tone pair (pseudo-random bit), phase-reset at each chip boundary.
+    fn in_band_watermark(chips: usize) -> Vec<f32> {
+        let n = chip_len(SR);
+        let mut bit = xorshift(3);
+        let mut out = Vec::with_capacity(chips * n);
+        for _ in 0..chips {
+            let pairs: Vec<(f32, f32)> = dsp::V3_LAYER_BANDS
+                .iter()
+                .map(|&(l0, l1, h0, h1)| if bit() > 0.0 { (h0, h1) } else { (l0, l1) })
+                .collect();
+            for s in 0..n {
+                let t = s as f32 / SR as f32;
+                let v: f32 = pairs
+                    .iter()
+                    .map(|(a, b)| {
+                        (std::f32::consts::TAU * a * t).sin() + (std::f32::consts::TAU * b * t).sin()
+                    })
+                    .sum();
+                out.push(v * 0.05);
+            }
+        }
+        out
+    }
+
+    fn tone_bins() -> Vec<usize> {
+        let n = chip_len(SR);
+        dsp::V3_LAYER_BANDS
+            .iter()
+            .flat_map(|&(l0, l1, h0, h1)| [l0, l1, h0, h1])
+            .map(|f| (f * n as f32 / SR as f32).round() as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_energy_map_concentrates_on_watermark_bins() {
+        let map = watermark_energy_map(&in_band_watermark(40), SR);
+        assert_eq!(map.len(), chip_len(SR) / 2 + 1);
+
+        let bins = tone_bins();
+        let in_band: f32 = bins.iter().flat_map(|&b| b - 1..=b + 1).map(|b| map[b]).sum();
+        let total: f32 = map.iter().map(|v| v.abs()).sum();
+        assert!(in_band > 0.5, "in-band contribution {in_band}");
+        assert!(in_band / total > 0.9, "energy must sit on the tone bins");
+
+        let peak = (0..map.len())
+            .max_by(|&a, &b| map[a].total_cmp(&map[b]))
+            .unwrap();
+        assert!(bins.iter().any(|&b| peak.abs_diff(b) <= 1), "peak bin {peak}");
+    }
+
+    #[test]
+    fn test_energy_map_flat_for_noise() {
+        let mut noise = xorshift(11);
+        let samples: Vec<f32> = (0..chip_len(SR) * 40).map(|_| noise() * 0.3).collect();
+        let map = watermark_energy_map(&samples, SR);
+        let peak = map.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        assert!(peak < 0.05, "noise map peak {peak}");
+    }
+
+    #[test]
+    fn test_compute_fft_rejects_unsupported_sizes() {
+        assert!(compute_fft(&[0.0; 16], 0).is_err());
+        assert!(compute_fft(&[0.0; 16], MAX_FFT_SIZE + 1).is_err());
+        assert_eq!(compute_fft(&[0.0; 16], 16).unwrap().len(), 9);
+    }
+
+    #[test]
+    fn test_estimate_quality_bounds() {
+        let mut noise = xorshift(5);
+        let samples: Vec<f32> = (0..2048).map(|_| noise() * 0.5).collect();
+        let plans = FftPlans::default();
+        let mut scratch = StftScratch::default();
+        let mut quality = |samples: &[f32], stft_window| {
+            let config = SonicConfig { stft_window, ..Default::default() };
+            estimate_quality(&plans, &mut scratch, samples, QUALITY_FFT_SIZE, &config, &SpectralBalanceEstimator).unwrap()
+        };
+        for function in [WindowFunction::Rectangular, WindowFunction::Hann, WindowFunction::Blackman] {
+            assert!((0.5..=1.0).contains(&quality(&samples, function)));
+            assert_eq!(quality(&samples[..100], function), 0.5);
+        }
+    }
+
+    #[test]
+    fn test_excluding_dc_bin_ignores_mic_offset() {
+        let mut noise = xorshift(8);
+        let samples: Vec<f32> = (0..2048).map(|_| noise() * 0.1).collect();
+        let offset: Vec<f32> = samples.iter().map(|s| s + 0.4).collect();
+        let plans = FftPlans::default();
+        let mut scratch = StftScratch::default();
+        let mut quality = |samples: &[f32], exclude_dc_and_nyquist| {
+            let config = SonicConfig { exclude_dc_and_nyquist, ..Default::default() };
+            estimate_quality(&plans, &mut scratch, samples, QUALITY_FFT_SIZE, &config, &SpectralBalanceEstimator).unwrap()
+        };
+
+        // The offset swamps the low band and drags quality to the floor...
+        let clean = quality(&samples, false);
+        assert!(clean > 0.8, "clean {clean}");
+        assert!(quality(&offset, false) < 0.6);
+        // ...unless DC is left out, when the offset makes no difference.
+        assert!((quality(&offset, true) - quality(&samples, true)).abs() < 1e-4);
+        assert!((quality(&offset, true) - clean).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_window_contains_off_bin_tone_leakage() {
+        // A tone halfway between bins 20 and 21: the worst case for leakage.
+        let samples: Vec<f32> =
+            (0..4096).map(|n| (std::f32::consts::TAU * 20.5 * n as f32 / QUALITY_FFT_SIZE as f32).sin()).collect();
+        let plans = FftPlans::default();
+        let mut scratch = StftScratch::default();
+        let mut leakage = |function| {
+            let power =
+                stft_power(&plans, &mut scratch, &samples, QUALITY_FFT_SIZE, function, QUALITY_FFT_SIZE / 2, false)
+                    .unwrap();
+            let total: f64 = power[..QUALITY_FFT_SIZE / 2].iter().sum();
+            power[QUALITY_FFT_SIZE / 4..QUALITY_FFT_SIZE / 2].iter().sum::<f64>() / total
+        };
+
+        let rectangular = leakage(WindowFunction::Rectangular);
+        assert!(rectangular > 1e-4, "rectangular {rectangular}");
+        for function in [WindowFunction::Hann, WindowFunction::Hamming, WindowFunction::Blackman] {
+            let tapered = leakage(function);
+            assert!(tapered < rectangular * 0.1, "{function:?} {tapered}");
+        }
+    }
+
+    #[test]
+    fn test_reference_correlator_matches_direct_sum() {
+        let mut noise = xorshift(17);
+        let samples: Vec<f32> = (0..300).map(|_| noise()).collect();
+        let reference = &samples[120..180];
+        let corr = ReferenceCorrelator::new(reference, samples.len()).unwrap().correlate(&samples);
+        assert_eq!(corr.len(), 241);
+        for lag in [0, 57, 120, 240] {
+            let direct: f32 = reference.iter().zip(&samples[lag..]).map(|(r, x)| r * x).sum();
+            assert!((corr[lag] - direct).abs() < 1e-3, "lag {lag}");
+        }
+        let peak = (0..corr.len()).max_by(|&a, &b| corr[a].total_cmp(&corr[b])).unwrap();
+        assert_eq!(peak, 120);
+
+        // Planned for a shorter segment, it correlates the whole one in blocks.
+        let blocked = ReferenceCorrelator::new(reference, reference.len()).unwrap().correlate(&samples);
+        assert_eq!(blocked.len(), corr.len());
+        for (lag, (a, b)) in blocked.iter().zip(&corr).enumerate() {
+            assert!((a - b).abs() < 1e-3, "lag {lag}");
+        }
+    }
+
+    #[test]
+    fn test_energy_map_short_buffer_is_empty() {
+        assert!(watermark_energy_map(&[0.1; 100], SR).is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+//! Durable detection history in SQLite (feature `sqlite`).
+//!
+//! [`SqliteDetectionStore`] keeps a listener's detections in a database
+//! file, so they survive app restarts without platform persistence code.
+//! The schema is versioned with `PRAGMA user_version`: opening a database
+//! applies the migrations it has not seen yet, each in its own transaction,
+//! and refuses one written by a newer schema than this build knows.
+//!
+//! Each detection is stored with the result fields a history needs; the
+//! config snapshot and rejection reason are not kept.
+
+use std::path::Path;
+
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, Row};
+
+use crate::{DetectionRecord, DetectionStore, SignatureAlgorithm, SonicError, WatermarkResult};
+
+/// Schema migrations; the database's `user_version` is how many have run.
+const MIGRATIONS: &[&str] = &[
+    // 1: detections, indexed for queries by time and by signer
+    "CREATE TABLE detections (
+        id INTEGER PRIMARY KEY,
+        detected_at INTEGER NOT NULL,
+        detected INTEGER NOT NULL,
+        confidence REAL NOT NULL,
+        signer_did TEXT,
+        timestamp INTEGER,
+        payload_hash TEXT,
+        covenant_json TEXT,
+        signature BLOB,
+        audio_quality REAL NOT NULL,
+        detection_method TEXT NOT NULL,
+        payload_integrity INTEGER NOT NULL,
+        corrected_symbols INTEGER NOT NULL,
+        degraded INTEGER NOT NULL,
+        channel_index INTEGER,
+        sample_rate_mismatch INTEGER NOT NULL,
+        signature_algorithm TEXT NOT NULL
+    );
+    CREATE INDEX detections_by_time ON detections (detected_at);
+    CREATE INDEX detections_by_signer ON detections (signer_did, detected_at);",
+    // 2: the listening session of each detection
+    "ALTER TABLE detections ADD COLUMN session_id TEXT;",
+    // 3: where in the session's stream each detection was heard
+    "ALTER TABLE detections ADD COLUMN stream_offset_ms INTEGER;
+    ALTER TABLE detections ADD COLUMN frame_index INTEGER;",
+];
+
+const COLUMNS: &str = "detected_at, detected, confidence, signer_did, timestamp, payload_hash, covenant_json, \
+                       signature, audio_quality, detection_method, payload_integrity, corrected_symbols, degraded, \
+                       channel_index, sample_rate_mismatch, signature_algorithm, session_id, stream_offset_ms, frame_index";
+
+/// A [`DetectionStore`] in an SQLite database.
+///
+/// The trait cannot report failures, so a write or query the database
+/// rejects (a full disk, say) is logged and skipped: the insert is lost, a
+/// query returns nothing, a purge removes nothing.
+pub struct SqliteDetectionStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteDetectionStore {
+    /// Open (or create) the history database at `path`, migrating it to the
+    /// current schema.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SonicError> {
+        Self::migrated(Connection::open(path).map_err(sql_error)?)
+    }
+
+    /// A history that lives only as long as the store, e.g. for tests.
+    pub fn open_in_memory() -> Result<Self, SonicError> {
+        Self::migrated(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn migrated(mut connection: Connection) -> Result<Self, SonicError> {
+        let version: usize = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(sql_error)?;
+        if version > MIGRATIONS.len() {
+            return Err(SonicError::InvalidConfig(format!(
+                "detection history schema {version} is newer than this build's {}",
+                MIGRATIONS.len()
+            )));
+        }
+        for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction().map_err(sql_error)?;
+            transaction.execute_batch(migration).map_err(sql_error)?;
+            transaction
+                .pragma_update(None, "user_version", applied + 1)
+                .map_err(sql_error)?;
+            transaction.commit().map_err(sql_error)?;
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn try_insert(&self, record: &DetectionRecord) -> rusqlite::Result<()> {
+        let r = &record.result;
+        self.connection.lock().execute(
+            &format!("INSERT INTO detections ({COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)"),
+            params![
+                sql_time(record.detected_at),
+                r.detected,
+                r.confidence,
+                r.signer_did,
+                r.timestamp.map(sql_time),
+                r.payload_hash,
+                r.covenant_json,
+                r.signature,
+                r.audio_quality,
+                r.detection_method,
+                r.payload_integrity,
+                r.corrected_symbols,
+                r.degraded,
+                r.channel_index,
+                r.sample_rate_mismatch,
+                format!("{:?}", r.signature_algorithm),
+                r.session_id,
+                r.stream_offset_ms.map(|ms| ms as i64),
+                r.frame_index.map(|i| i as i64),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn try_query(
+        &self,
+        since: Option<u64>,
+        until: Option<u64>,
+        signer_did: Option<String>,
+    ) -> rusqlite::Result<Vec<DetectionRecord>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(&format!(
+            "SELECT {COLUMNS} FROM detections
+             WHERE (?1 IS NULL OR detected_at >= ?1) AND (?2 IS NULL OR detected_at <= ?2)
+               AND (?3 IS NULL OR signer_did = ?3)
+             ORDER BY detected_at, id"
+        ))?;
+        let records = statement.query_map(params![since.map(sql_time), until.map(sql_time), signer_did], record)?;
+        records.collect()
+    }
+}
+
+impl DetectionStore for SqliteDetectionStore {
+    fn insert(&self, record: DetectionRecord) {
+        if let Err(e) = self.try_insert(&record) {
+            log::warn!("detection history insert failed: {e}");
+        }
+    }
+
+    fn query(&self, since: Option<u64>, until: Option<u64>, signer_did: Option<String>) -> Vec<DetectionRecord> {
+        self.try_query(since, until, signer_did).unwrap_or_else(|e| {
+            log::warn!("detection history query failed: {e}");
+            Vec::new()
+        })
+    }
+
+    fn purge(&self, before: u64) -> u32 {
+        let purged = self
+            .connection
+            .lock()
+            .execute("DELETE FROM detections WHERE detected_at < ?1", [sql_time(before)]);
+        purged.map_or_else(
+            |e| {
+                log::warn!("detection history purge failed: {e}");
+                0
+            },
+            |count| count as u32,
+        )
+    }
+}
+
+/// Unix seconds as an SQLite integer (which is signed).
+fn sql_time(secs: u64) -> i64 {
+    i64::try_from(secs).unwrap_or(i64::MAX)
+}
+
+fn record(row: &Row) -> rusqlite::Result<DetectionRecord> {
+    let signature_algorithm = match row.get::<_, String>(15)?.as_str() {
+        "Es256k" => SignatureAlgorithm::Es256k,
+        "Es256" => SignatureAlgorithm::Es256,
+        _ => SignatureAlgorithm::Ed25519,
+    };
+    Ok(DetectionRecord {
+        detected_at: row.get::<_, i64>(0)? as u64,
+        result: WatermarkResult {
+            detected: row.get(1)?,
+            confidence: row.get(2)?,
+            signer_did: row.get(3)?,
+            timestamp: row.get::<_, Option<i64>>(4)?.map(|t| t as u64),
+            payload_hash: row.get(5)?,
+            covenant_json: row.get(6)?,
+            signature: row.get(7)?,
+            audio_quality: row.get(8)?,
+            detection_method: row.get(9)?,
+            payload_integrity: row.get(10)?,
+            corrected_symbols: row.get(11)?,
+            degraded: row.get(12)?,
+            channel_index: row.get(13)?,
+            sample_rate_mismatch: row.get(14)?,
+            signature_algorithm,
+            session_id: row.get(16)?,
+            stream_offset_ms: row.get::<_, Option<i64>>(17)?.map(|ms| ms as u64),
+            frame_index: row.get::<_, Option<i64>>(18)?.map(|i| i as u64),
+            ..Default::default()
+        },
+    })
+}
+
+fn sql_error(e: rusqlite::Error) -> SonicError {
+    SonicError::InternalError(format!("detection history: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.sqlite");
+        {
+            let store = SqliteDetectionStore::open(&path).unwrap();
+            for (detected_at, signer) in [(300, "did:web:a.example"), (100, "did:web:a.example"), (200, "did:web:b.example")] {
+                store.insert(DetectionRecord {
+                    detected_at,
+                    result: WatermarkResult {
+                        detected: true,
+                        confidence: 0.75,
+                        signer_did: Some(signer.into()),
+                        timestamp: Some(1_700_000_000),
+                        signature: Some(vec![1, 2, 3]),
+                        signature_algorithm: SignatureAlgorithm::Es256,
+                        detection_method: "v3".into(),
+                        session_id: Some("5e55".into()),
+                        stream_offset_ms: Some(90_500),
+                        frame_index: Some(27),
+                        ..Default::default()
+                    },
+                });
+            }
+        }
+
+        let store = SqliteDetectionStore::open(&path).unwrap();
+        let times = |records: Vec<DetectionRecord>| records.iter().map(|r| r.detected_at).collect::<Vec<_>>();
+        let all = store.query(None, None, None);
+        assert_eq!(times(all.clone()), [100, 200, 300]);
+        assert_eq!(all[0].result.confidence, 0.75);
+        assert_eq!(all[0].result.signature.as_deref(), Some(&[1u8, 2, 3][..]));
+        assert_eq!(all[0].result.signature_algorithm, SignatureAlgorithm::Es256);
+        assert_eq!(all[0].result.session_id.as_deref(), Some("5e55"));
+        assert_eq!((all[0].result.stream_offset_ms, all[0].result.frame_index), (Some(90_500), Some(27)));
+        assert_eq!(times(store.query(Some(150), None, Some("did:web:a.example".into()))), [300]);
+        assert_eq!(store.purge(200), 1);
+        assert_eq!(times(store.query(None, None, None)), [200, 300]);
+
+        let version: usize = store
+            .connection
+            .lock()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+    }
+
+    #[test]
+    fn test_migrates_first_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.sqlite");
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection.execute_batch(MIGRATIONS[0]).unwrap();
+            connection.pragma_update(None, "user_version", 1).unwrap();
+            connection
+                .execute(
+                    "INSERT INTO detections (detected_at, detected, confidence, signer_did, audio_quality,
+                     detection_method, payload_integrity, corrected_symbols, degraded, sample_rate_mismatch,
+                     signature_algorithm) VALUES (100, 1, 0.9, 'did:web:a.example', 1.0, 'v3', 1, 0, 0, 0, 'Ed25519')",
+                    [],
+                )
+                .unwrap();
+        }
+
+        let store = SqliteDetectionStore::open(&path).unwrap();
+        let all = store.query(None, None, None);
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].result.signer_did.as_deref(), Some("did:web:a.example"));
+        assert!(all[0].result.session_id.is_none());
+    }
+
+    #[test]
+    fn test_refuses_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        assert!(matches!(SqliteDetectionStore::open(&path), Err(SonicError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,61 @@
+//! Signer revocation through status lists.
+//!
+//! A signer credential that is revoked (a leaked key, a departed employee)
+//! still produces valid signatures, so verification has to ask whether it
+//! was revoked. Issuers publish that as a status list: one bit per issued
+//! credential, set once it is revoked, as in the W3C Bitstring Status List.
+//! The host app fetches and decompresses the list and tells the verifier,
+//! through a [`StatusListProvider`], which list and index belong to a
+//! signer; the core only reads the bit.
+
+use crate::SonicError;
+
+/// Where a signer's credential sits in a status list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerStatus {
+    /// The decompressed status bitstring: bit `i` is bit `7 - i % 8` of byte
+    /// `i / 8`, the first index being the most significant bit
+    pub status_list: Vec<u8>,
+    /// The signer credential's index in `status_list`
+    pub status_index: u64,
+}
+
+/// Supplies signers' status list entries. Implemented by the host app, which
+/// fetches and caches the lists over its own network stack.
+pub trait StatusListProvider: Send + Sync {
+    /// The status entry for `signer_did`, or `None` when the signer has no
+    /// status list (and so cannot be revoked).
+    fn signer_status(&self, signer_did: String) -> Option<SignerStatus>;
+}
+
+/// Whether bit `index` of the status bitstring `status_list` is set, i.e.
+/// whether the credential at `index` is revoked. An index past the end of
+/// the list is an error, not "not revoked".
+pub fn status_list_revoked(status_list: &[u8], index: u64) -> Result<bool, SonicError> {
+    let byte = usize::try_from(index / 8)
+        .ok()
+        .and_then(|i| status_list.get(i))
+        .ok_or_else(|| {
+            SonicError::InvalidConfig(format!(
+                "status index {index} is outside a {}-entry status list",
+                status_list.len() * 8
+            ))
+        })?;
+    Ok(byte & (0x80 >> (index % 8)) != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_list_bit_order() {
+        // Indexes 0, 9 and 23 revoked.
+        let list = [0b1000_0000, 0b0100_0000, 0b0000_0001];
+        let revoked: Vec<u64> = (0..24).filter(|&i| status_list_revoked(&list, i).unwrap()).collect();
+        assert_eq!(revoked, [0, 9, 23]);
+        assert!(matches!(status_list_revoked(&list, 24), Err(SonicError::InvalidConfig(_))));
+        assert!(status_list_revoked(&[], 0).is_err());
+        assert!(status_list_revoked(&list, u64::MAX).is_err());
+    }
+}
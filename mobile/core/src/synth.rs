@@ -0,0 +1,114 @@
+//! Deterministic watermarked audio for end-to-end tests.
+//!
+//! The v3 embedder hides the watermark under the host signal's own energy, so
+//! it needs real cover audio: silence gives it nothing to mask under. The
+//! [`Synthesizer`] generates a seeded broadband cover and embeds through the
+//! same `vouch-sonic-dsp` path a browser or server embed uses, so its output
+//! exercises the real detector rather than a test-only shortcut.
+
+use vouch_sonic_dsp as dsp;
+
+use crate::{pcm_le16_to_samples, samples_to_pcm_le16, SonicConfig, SonicError};
+
+/// Lowest sample rate the v3 embedder accepts (its top layer sits near 19 kHz).
+const MIN_EMBED_SAMPLE_RATE: u32 = 44_100;
+
+/// Default cover seed.
+const DEFAULT_SEED: u64 = 1;
+
+/// Watermarked audio produced by [`Synthesizer::synthesize`].
+#[derive(Debug, Clone)]
+pub struct SynthesizedAudio {
+    /// Watermarked float samples at the synthesizer's sample rate
+    pub samples: Vec<f32>,
+    /// The same audio as 16-bit LE PCM
+    pub pcm: Vec<u8>,
+    /// Payload hash a detector reports for this audio (the server lookup key)
+    pub payload_hash: String,
+    /// Watermark ID derived from the signer DID and timestamp
+    pub watermark_id: String,
+}
+
+/// Produces watermarked audio that the listener detects, deterministically:
+/// the same configuration, seed and payload always give the same samples.
+#[derive(Debug, Clone)]
+pub struct Synthesizer {
+    sample_rate: u32,
+    seed: u64,
+}
+
+impl Synthesizer {
+    /// Synthesizer at `config.sample_rate`, which must be at least 44.1 kHz.
+    ///
+    /// The v3 codec always embeds its chirp sync preamble, so
+    /// `enable_chirp_sync` has no effect here.
+    pub fn new(config: SonicConfig) -> Result<Self, SonicError> {
+        config.validate()?;
+        if config.sample_rate < MIN_EMBED_SAMPLE_RATE {
+            return Err(SonicError::InvalidSampleRate(config.sample_rate));
+        }
+        Ok(Self {
+            sample_rate: config.sample_rate,
+            seed: DEFAULT_SEED,
+        })
+    }
+
+    /// Use a different cover signal.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Embed the watermark for `signer_did` at `timestamp_ms` into
+    /// `duration_ms` of seeded broadband cover.
+    ///
+    /// Give it at least 5 s: shorter clips do not fit one full payload
+    /// repetition after the sync preamble.
+    pub fn synthesize(
+        &self,
+        signer_did: &str,
+        timestamp_ms: u64,
+        duration_ms: u32,
+    ) -> Result<SynthesizedAudio, SonicError> {
+        let n = (self.sample_rate as u64 * duration_ms as u64 / 1000) as usize;
+        let cover = samples_to_pcm_le16(&broadband_cover(n, self.sample_rate, self.seed));
+        let embedded = dsp::embed(&cover, self.sample_rate, signer_did, timestamp_ms)
+            .map_err(|e| SonicError::ProcessingFailed(e.to_string()))?;
+        Ok(SynthesizedAudio {
+            samples: pcm_le16_to_samples(&embedded.watermarked_audio),
+            pcm: embedded.watermarked_audio,
+            payload_hash: embedded.payload_hash,
+            watermark_id: embedded.watermark_id,
+        })
+    }
+}
+
+/// Seeded broadband cover: 64 sinusoids at pseudo-random frequencies
+/// (150 Hz - 20 kHz) and phases, so every embedding band has energy.
+pub(crate) fn broadband_cover(n: usize, sample_rate: u32, seed: u64) -> Vec<f32> {
+    let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut unit = || (next() >> 40) as f32 / (1u64 << 24) as f32;
+    let parts: Vec<(f32, f32)> = (0..64)
+        .map(|_| {
+            let f = 150.0 + unit() * (20_000.0 - 150.0);
+            let p = unit() * std::f32::consts::TAU;
+            (f, p)
+        })
+        .collect();
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let mut s = 0.0_f32;
+            for (f, p) in &parts {
+                s += (std::f32::consts::TAU * f * t + p).sin();
+            }
+            (s / parts.len() as f32 * 0.6).clamp(-1.0, 1.0)
+        })
+        .collect()
+}
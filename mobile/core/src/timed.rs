@@ -0,0 +1,338 @@
+//! Timed scans over long recordings.
+//!
+//! The listener answers "is there a watermark in this buffer"; a timed scan
+//! walks a whole recording window by window, reports *where* each watermark
+//! starts, and follows the payload repetitions after each sync to estimate the
+//! clock drift between the embedder and the capture device.
+//!
+//! In a reverberant room the sync preamble arrives several times: the direct
+//! sound, then attenuated reflections. Offsets are reported for the earliest
+//! strong arrival (the direct path), not the strongest one.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use vouch_sonic_dsp as dsp;
+
+use crate::{kernels, pcm_le16_to_samples, spectrum, ProgressCallback, SonicConfig, WatermarkResult, MIN_SAMPLES};
+
+/// Normalized correlation a payload repetition must reach to count as locked
+/// while tracking drift. Noise alone stays near `1/sqrt(period)` (about 0.002
+/// for one repetition at 44.1 kHz); the end of the watermarked segment drops
+/// below this and stops the track.
+const MIN_TRACK_CORRELATION: f64 = 0.01;
+
+/// How far either side of the detector's sync lock to look for other
+/// arrivals of the preamble. Covers distinct room reflections; later
+/// reverberation is diffuse and has no peak of its own.
+const MAX_ECHO_DELAY_MS: u32 = 100;
+
+/// Two matched-filter peaks closer than this are one arrival. The chirp's
+/// main lobe is well under a millisecond wide.
+const MIN_PATH_SEPARATION_MS: u32 = 1;
+
+/// One watermark found by [`detect_watermark_timed`].
+#[derive(Debug, Clone)]
+pub struct TimedDetection {
+    /// Where the watermark's sync preamble starts on the direct path, in ms
+    /// from the recording start
+    pub offset_ms: u64,
+    /// Number of later arrivals (echoes) of the sync preamble strong enough
+    /// to count as propagation paths
+    pub echo_paths: u32,
+    /// Detection result for the scan window the watermark was found in
+    pub result: WatermarkResult,
+    /// Estimated clock drift of the recording relative to the embedder, in
+    /// parts per million. Positive means the payload repetitions arrive
+    /// stretched (the capture clock runs fast). `None` when fewer than two
+    /// repetitions could be tracked.
+    pub drift_ppm: Option<f32>,
+}
+
+/// Outcome of a cancellable timed scan.
+#[derive(Debug, Clone)]
+pub struct TimedScan {
+    /// Watermarks found, in recording order (up to the cancellation point
+    /// when `cancelled`)
+    pub detections: Vec<TimedDetection>,
+    /// Whether the scan stopped early because its cancellation flag was set
+    pub cancelled: bool,
+}
+
+/// Scan a whole recording for watermarks at `config.sample_rate`.
+///
+/// The recording is analyzed in `window_ms` windows advancing
+/// `config.correlation_search_step` samples (a quarter window by default) at a
+/// time: the detector looks for the sync preamble near the start of its
+/// buffer, so every preamble needs a window that begins shortly before it.
+/// After a detection, the payload repetitions that follow the sync are
+/// tracked through the recording to estimate clock drift, and the scan resumes
+/// where the track ends: the repetitions are periodic, so a window inside them
+/// could otherwise lock onto the same watermark again.
+///
+/// `cancel` is checked before each window and each tracked repetition; once
+/// set, the scan returns the detections completed so far.
+pub(crate) fn scan(audio_data: &[u8], config: &SonicConfig, window_ms: u32, cancel: &AtomicBool) -> TimedScan {
+    let detect = |_, pcm: &[u8]| dsp::detect(pcm, config.sample_rate).ok();
+    scan_with(audio_data, config, window_ms, cancel, detect, |_, _| true)
+}
+
+/// [`scan`] reporting to `progress` before each window and once at the
+/// end, with the share of the recording scanned, the detections so far and
+/// the position reached. The scan stops, as if cancelled, when `progress`
+/// returns false.
+pub(crate) fn scan_with_progress(
+    audio_data: &[u8],
+    config: &SonicConfig,
+    window_ms: u32,
+    progress: &dyn ProgressCallback,
+) -> TimedScan {
+    let total = (audio_data.len() / 2).max(1);
+    let sample_rate = config.sample_rate.max(1) as u64;
+    let detect = |_, pcm: &[u8]| dsp::detect(pcm, config.sample_rate).ok();
+    let report = |position: usize, detections: usize| {
+        progress.on_progress(
+            (position.min(total) as f64 * 100.0 / total as f64) as f32,
+            detections as u32,
+            position as u64 * 1000 / sample_rate,
+        )
+    };
+    let scan = scan_with(audio_data, config, window_ms, &AtomicBool::new(false), detect, report);
+    if !scan.cancelled {
+        report(total, scan.detections.len());
+    }
+    scan
+}
+
+/// [`scan`] with the windows on the regular step grid decoded in parallel
+/// up front (feature `parallel`).
+///
+/// Window decoding dominates a scan and every window is independent, so the
+/// grid is fanned out across the rayon pool; the sequential walk then
+/// tracks and skips exactly as `scan` does, decoding on the spot only the
+/// off-grid windows it resumes at after a tracked watermark. The detections
+/// are the same as `scan`'s.
+#[cfg(feature = "parallel")]
+pub(crate) fn scan_parallel(audio_data: &[u8], config: &SonicConfig, window_ms: u32) -> Vec<TimedDetection> {
+    use rayon::prelude::*;
+
+    let sample_rate = config.sample_rate;
+    let Some((window, hop)) = window_and_hop(config, window_ms) else {
+        return Vec::new();
+    };
+    let samples = audio_data.len() / 2;
+    let starts: Vec<usize> = (0..samples).step_by(hop).collect();
+    let decoded: Vec<Option<dsp::DetectResult>> = starts
+        .par_iter()
+        .map(|&start| {
+            let end = (start + window).min(samples);
+            (end - start >= MIN_SAMPLES)
+                .then(|| dsp::detect(&audio_data[start * 2..end * 2], sample_rate).ok())
+                .flatten()
+        })
+        .collect();
+    let detect = |start: usize, pcm: &[u8]| match starts.binary_search(&start) {
+        Ok(i) => decoded[i].clone(),
+        Err(_) => dsp::detect(pcm, sample_rate).ok(),
+    };
+    scan_with(audio_data, config, window_ms, &AtomicBool::new(false), detect, |_, _| true).detections
+}
+
+/// Samples per scan window and per step, or `None` if the window is too
+/// short to decode.
+fn window_and_hop(config: &SonicConfig, window_ms: u32) -> Option<(usize, usize)> {
+    let window = (config.sample_rate as u64 * window_ms as u64 / 1000) as usize;
+    if config.sample_rate == 0 || window < MIN_SAMPLES {
+        return None;
+    }
+    Some((window, config.correlation_search_step.map_or(window / 4, |step| step as usize)))
+}
+
+/// The scan loop, with `detect` decoding the window of 16-bit PCM that
+/// starts at sample `start`. `proceed` is told the start of each window and
+/// the detections so far before it is decoded; returning false cancels.
+fn scan_with(
+    audio_data: &[u8],
+    config: &SonicConfig,
+    window_ms: u32,
+    cancel: &AtomicBool,
+    detect: impl Fn(usize, &[u8]) -> Option<dsp::DetectResult>,
+    proceed: impl Fn(usize, usize) -> bool,
+) -> TimedScan {
+    let sample_rate = config.sample_rate;
+    let samples = pcm_le16_to_samples(audio_data);
+    let mut detections: Vec<TimedDetection> = Vec::new();
+    let Some((window, hop)) = window_and_hop(config, window_ms) else {
+        return TimedScan { detections, cancelled: false };
+    };
+
+    let mut start = 0;
+    while start < samples.len() {
+        if cancel.load(Ordering::Relaxed) || !proceed(start, detections.len()) {
+            return TimedScan { detections, cancelled: true };
+        }
+        let end = (start + window).min(samples.len());
+        if end - start < MIN_SAMPLES {
+            break;
+        }
+        let pcm = &audio_data[start * 2..end * 2];
+        let mut next = start + hop;
+        if let Some(d) = detect(start, pcm) {
+            if d.detected {
+                if let Ok(Some(sync)) = dsp::locate(pcm, sample_rate) {
+                    let paths = resolve_paths(
+                        &samples,
+                        sample_rate,
+                        start + sync.chirp_start,
+                        config.min_path_prominence,
+                    );
+                    let payload_start = paths.direct + (sync.payload_start - sync.chirp_start);
+                    let track = track_repetitions(&samples, sample_rate, payload_start, &sync.id, cancel);
+                    detections.push(TimedDetection {
+                        offset_ms: paths.direct as u64 * 1000 / sample_rate as u64,
+                        echo_paths: paths.echoes,
+                        result: WatermarkResult::from_dsp(d),
+                        drift_ppm: track.drift_ppm,
+                    });
+                    next = next.max(track.end);
+                }
+            }
+        }
+        if end == samples.len() {
+            break;
+        }
+        start = next;
+    }
+    TimedScan {
+        detections,
+        cancelled: false,
+    }
+}
+
+/// Arrivals of one sync preamble.
+struct SyncPaths {
+    /// Start of the earliest strong arrival (the direct path)
+    direct: usize,
+    /// Number of strong arrivals after it
+    echoes: u32,
+}
+
+/// Find every strong arrival of the sync preamble around `locked`, the
+/// position the detector synced on.
+///
+/// The normalized matched filter is evaluated within `MAX_ECHO_DELAY_MS` of
+/// the lock. Peaks are taken strongest first, skipping any within
+/// `MIN_PATH_SEPARATION_MS` of one already taken, down to `min_prominence` of
+/// the strongest. The earliest of them is the direct path: a reflection can
+/// out-correlate an obstructed direct sound, but it always arrives later.
+fn resolve_paths(samples: &[f32], sample_rate: u32, locked: usize, min_prominence: f32) -> SyncPaths {
+    let single = SyncPaths { direct: locked, echoes: 0 };
+    let chirp = dsp::v3_sync_reference(sample_rate);
+    let reach = (sample_rate * MAX_ECHO_DELAY_MS / 1000) as usize;
+    let lo = locked.saturating_sub(reach);
+    let hi = (locked + reach + chirp.len()).min(samples.len());
+    if chirp.is_empty() || hi < lo + chirp.len() {
+        return single;
+    }
+    let segment = &samples[lo..hi];
+    let Ok(correlator) = spectrum::ReferenceCorrelator::new(&chirp, segment.len()) else {
+        return single;
+    };
+    let ncc = correlator.correlate_normalized(segment);
+
+    let mut order: Vec<usize> = (0..ncc.len()).collect();
+    order.sort_by(|&a, &b| ncc[b].total_cmp(&ncc[a]));
+    let Some(&strongest) = order.first() else {
+        return single;
+    };
+    let floor = ncc[strongest] * min_prominence as f64;
+    if floor <= 0.0 {
+        return single;
+    }
+    let separation = (sample_rate * MIN_PATH_SEPARATION_MS / 1000) as usize;
+    let mut peaks: Vec<usize> = Vec::new();
+    for lag in order {
+        if ncc[lag] < floor {
+            break;
+        }
+        if peaks.iter().all(|&p| p.abs_diff(lag) > separation) {
+            peaks.push(lag);
+        }
+    }
+    SyncPaths {
+        direct: lo + peaks.iter().copied().min().unwrap_or(strongest),
+        echoes: peaks.len().saturating_sub(1) as u32,
+    }
+}
+
+/// Payload repetitions followed after one sync.
+struct RepetitionTrack {
+    /// Least-squares drift of the repetition arrival times, in ppm
+    drift_ppm: Option<f32>,
+    /// End of the last repetition that locked (the payload start if none did)
+    end: usize,
+}
+
+/// Track the payload repetitions that start at `payload_start` and fit the
+/// drift of their arrival times.
+///
+/// Each repetition is located by correlating against the reference waveform
+/// for `id`. The search is centered on where the previous repetitions predict
+/// the next one, so steadily accumulating drift stays inside it. The search
+/// reaches half the 10 ms tone-comb period either way; beyond that the comb
+/// correlates with itself. Tracking stops at the first repetition that fails
+/// to lock, or once `cancel` is set. The least-squares slope of offset against
+/// nominal position is the drift.
+fn track_repetitions(
+    samples: &[f32],
+    sample_rate: u32,
+    payload_start: usize,
+    id: &[u8],
+    cancel: &AtomicBool,
+) -> RepetitionTrack {
+    let reference = dsp::v3_payload_reference(id, sample_rate);
+    let period = reference.len();
+    let mut end = payload_start;
+    let ref_norm = kernels::sum_squares(&reference).sqrt();
+    let search = (sample_rate / 200) as i64;
+    let Ok(correlator) = spectrum::ReferenceCorrelator::new(&reference, period + 2 * search as usize) else {
+        return RepetitionTrack { drift_ppm: None, end };
+    };
+
+    // (nominal position relative to the first repetition, measured offset)
+    let mut track: Vec<(f64, f64)> = Vec::new();
+    let (mut offset, mut step) = (0i64, 0i64);
+    for rep in 0.. {
+        let nominal = (payload_start + rep * period) as i64;
+        let lo = nominal + offset + step - search;
+        let hi = nominal + offset + step + search + period as i64;
+        if lo < 0 || hi > samples.len() as i64 || cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let segment = &samples[lo as usize..hi as usize];
+        let corr = correlator.correlate(segment);
+        let Some(best) = (0..corr.len()).max_by(|&a, &b| corr[a].total_cmp(&corr[b])) else {
+            break;
+        };
+        let seg_norm = kernels::sum_squares(&segment[best..best + period]).sqrt();
+        if seg_norm <= 0.0 || corr[best] as f64 / (seg_norm * ref_norm) < MIN_TRACK_CORRELATION {
+            break;
+        }
+        end = lo as usize + best + period;
+        let measured = lo + best as i64 - nominal;
+        if !track.is_empty() {
+            step = measured - offset;
+        }
+        offset = measured;
+        track.push(((rep * period) as f64, measured as f64));
+    }
+
+    let drift_ppm = (track.len() >= 2).then(|| {
+        let n = track.len() as f64;
+        let mean_x = track.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = track.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxy: f64 = track.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let sxx: f64 = track.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        (sxy / sxx * 1e6) as f32
+    });
+    RepetitionTrack { drift_ppm, end }
+}
@@ -0,0 +1,77 @@
+//! Per-buffer processing times, for `SonicListener::stats`.
+//!
+//! Apps watch the engine's cost on the device (is detection keeping up with
+//! the mic?) without a profiler. The listener times every buffer it decodes
+//! and keeps the most recent times, from which the mean and the 95th
+//! percentile are reported.
+
+use std::{collections::VecDeque, time::Duration};
+
+/// Buffers whose processing times the figures cover
+pub(crate) const PROCESSING_TIME_WINDOW: usize = 256;
+
+/// The processing times of the most recent buffers, oldest first.
+pub(crate) struct ProcessingTimes {
+    recent: VecDeque<Duration>,
+}
+
+impl Default for ProcessingTimes {
+    /// Allocated up front, so recording never allocates on the audio path.
+    fn default() -> Self {
+        Self {
+            recent: VecDeque::with_capacity(PROCESSING_TIME_WINDOW),
+        }
+    }
+}
+
+impl ProcessingTimes {
+    pub(crate) fn record(&mut self, elapsed: Duration) {
+        if self.recent.len() == PROCESSING_TIME_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(elapsed);
+    }
+
+    /// Mean processing time in ms (0.0 before any buffer)
+    pub(crate) fn mean_ms(&self) -> f32 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let total: Duration = self.recent.iter().sum();
+        (total.as_secs_f64() * 1000.0 / self.recent.len() as f64) as f32
+    }
+
+    /// 95th percentile processing time in ms, nearest rank (0.0 before any
+    /// buffer)
+    pub(crate) fn p95_ms(&self) -> f32 {
+        let mut sorted: Vec<_> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        rank.checked_sub(1).map_or(0.0, |i| (sorted[i].as_secs_f64() * 1000.0) as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_figures_cover_the_recent_window() {
+        let mut times = ProcessingTimes::default();
+        assert_eq!((times.mean_ms(), times.p95_ms()), (0.0, 0.0));
+
+        // 1..=100 ms: mean 50.5, p95 the 95th smallest.
+        for ms in 1..=100 {
+            times.record(Duration::from_millis(ms));
+        }
+        assert!((times.mean_ms() - 50.5).abs() < 1e-3);
+        assert!((times.p95_ms() - 95.0).abs() < 1e-3);
+
+        // Older times age out of the window.
+        for _ in 0..PROCESSING_TIME_WINDOW {
+            times.record(Duration::from_millis(2));
+        }
+        assert!((times.mean_ms() - 2.0).abs() < 1e-3);
+        assert!((times.p95_ms() - 2.0).abs() < 1e-3);
+    }
+}
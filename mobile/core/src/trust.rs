@@ -0,0 +1,92 @@
+//! Pinned signers for closed deployments.
+//!
+//! A broadcaster monitoring its own output only cares about watermarks its
+//! own signers made; a valid signature from anyone else is noise at best.
+//! A [`TrustStore`] holds the signers an app pins, by DID or by public key,
+//! and a `SignatureVerifier` with `VerifierConfig::require_pinned_signer`
+//! set rejects everyone else.
+
+use std::collections::HashSet;
+
+use parking_lot::RwLock;
+
+use crate::{did_from_public_key, SonicError};
+
+/// Signer DIDs and Ed25519 public keys an app trusts.
+#[derive(Debug, Default)]
+pub struct TrustStore {
+    dids: RwLock<HashSet<String>>,
+    public_keys: RwLock<HashSet<Vec<u8>>>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust the signer `did`, whatever key it resolves to.
+    pub fn pin_did(&self, did: String) {
+        self.dids.write().insert(did);
+    }
+
+    /// Trust the 32-byte Ed25519 `public_key`, under any DID.
+    pub fn pin_public_key(&self, public_key: Vec<u8>) -> Result<(), SonicError> {
+        did_from_public_key(&public_key)?;
+        self.public_keys.write().insert(public_key);
+        Ok(())
+    }
+
+    /// Stop trusting `did`. Returns whether it was pinned.
+    pub fn unpin_did(&self, did: &str) -> bool {
+        self.dids.write().remove(did)
+    }
+
+    /// Stop trusting `public_key`. Returns whether it was pinned.
+    pub fn unpin_public_key(&self, public_key: &[u8]) -> bool {
+        self.public_keys.write().remove(public_key)
+    }
+
+    /// Whether a signature verified under `public_key` from signer `did`
+    /// comes from a pinned signer: either is enough.
+    pub fn is_trusted(&self, did: &str, public_key: &[u8]) -> bool {
+        self.dids.read().contains(did) || self.public_keys.read().contains(public_key)
+    }
+
+    /// The pinned DIDs, sorted.
+    pub fn pinned_dids(&self) -> Vec<String> {
+        let mut dids: Vec<String> = self.dids.read().iter().cloned().collect();
+        dids.sort();
+        dids
+    }
+
+    /// Unpin every DID and key.
+    pub fn clear(&self) {
+        self.dids.write().clear();
+        self.public_keys.write().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_by_did_or_key() {
+        let store = TrustStore::new();
+        assert!(!store.is_trusted("did:web:news.example", &[1; 32]));
+
+        store.pin_did("did:web:news.example".into());
+        store.pin_public_key(vec![2; 32]).unwrap();
+        assert!(store.is_trusted("did:web:news.example", &[1; 32]));
+        assert!(store.is_trusted("did:web:elsewhere.example", &[2; 32]));
+        assert!(!store.is_trusted("did:web:elsewhere.example", &[1; 32]));
+        assert!(matches!(store.pin_public_key(vec![2; 31]), Err(SonicError::InvalidKey(_))));
+        assert_eq!(store.pinned_dids(), ["did:web:news.example"]);
+
+        assert!(store.unpin_did("did:web:news.example"));
+        assert!(!store.unpin_did("did:web:news.example"));
+        assert!(!store.is_trusted("did:web:news.example", &[1; 32]));
+        store.clear();
+        assert!(!store.is_trusted("did:web:elsewhere.example", &[2; 32]));
+    }
+}
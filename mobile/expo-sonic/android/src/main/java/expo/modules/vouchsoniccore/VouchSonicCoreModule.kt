@@ -49,7 +49,7 @@ class VouchSonicCoreModule : Module() {
   override fun definition() = ModuleDefinition {
     Name("VouchSonicCore")
 
-    Events("onWatermark", "onAudioLevel", "onError", "onStateChange")
+    Events("onWatermark", "onWatermarkStillPresent", "onAudioLevel", "onError", "onStateChange", "onSignerConfirmed", "onConfigChange")
 
     AsyncFunction("getVersion") {
       getVersion()
@@ -67,7 +67,15 @@ class VouchSonicCoreModule : Module() {
     }
 
     AsyncFunction("stopListening") { listenerId: String ->
-      requireListener(listenerId).stopListening()
+      requireListener(listenerId).stopListening().toJsMap()
+    }
+
+    AsyncFunction("pauseListening") { listenerId: String ->
+      requireListener(listenerId).pause()
+    }
+
+    AsyncFunction("resumeListening") { listenerId: String ->
+      requireListener(listenerId).resume()
     }
 
     AsyncFunction("processBuffer") { listenerId: String, pcmDataB64: String ->
@@ -93,7 +101,7 @@ class VouchSonicCoreModule : Module() {
 
     AsyncFunction("disposeListener") { listenerId: String ->
       listeners.remove(listenerId)?.let { listener ->
-        runCatching { listener.stopListening() }
+        listener.shutdown()
         listener.close()
       }
       Unit
@@ -124,6 +132,12 @@ class VouchSonicCoreModule : Module() {
       override fun onWatermarkDetected(result: WatermarkResult) {
         sendEvent("onWatermark", mapOf("listenerId" to listenerId, "result" to result.toJsMap()))
       }
+      override fun onWatermarkStillPresent(result: WatermarkResult, presentForMs: ULong) {
+        sendEvent(
+          "onWatermarkStillPresent",
+          mapOf("listenerId" to listenerId, "result" to result.toJsMap(), "presentForMs" to presentForMs.toLong())
+        )
+      }
       override fun onAudioLevelChanged(levelDb: Float) {
         sendEvent("onAudioLevel", mapOf("listenerId" to listenerId, "levelDb" to levelDb))
       }
@@ -133,6 +147,15 @@ class VouchSonicCoreModule : Module() {
       override fun onStateChanged(state: ListenerState) {
         sendEvent("onStateChange", mapOf("listenerId" to listenerId, "state" to state.toJs()))
       }
+      override fun onSignerConfirmed(signer: String, accumulatedConfidence: Float) {
+        sendEvent(
+          "onSignerConfirmed",
+          mapOf("listenerId" to listenerId, "signer" to signer, "accumulatedConfidence" to accumulatedConfidence)
+        )
+      }
+      override fun onConfigChanged(old: SonicConfig, new: SonicConfig) {
+        sendEvent("onConfigChange", mapOf("listenerId" to listenerId, "old" to old.toJsMap(), "new" to new.toJsMap()))
+      }
     }
 }
 
@@ -143,8 +166,11 @@ private fun ByteArray.toUByteList(): List<UByte> = map { it.toUByte() }
 private fun ListenerState.toJs(): String = when (this) {
   ListenerState.IDLE -> "Idle"
   ListenerState.LISTENING -> "Listening"
+  ListenerState.ACQUIRING -> "Acquiring"
   ListenerState.PROCESSING -> "Processing"
+  ListenerState.PAUSED -> "Paused"
   ListenerState.ERROR -> "Error"
+  ListenerState.CLOSED -> "Closed"
 }
 
 private fun WatermarkResult.toJsMap(): Map<String, Any?> = mapOf(
@@ -155,7 +181,26 @@ private fun WatermarkResult.toJsMap(): Map<String, Any?> = mapOf(
   "payloadHash" to payloadHash,
   "covenantJson" to covenantJson,
   "audioQuality" to audioQuality,
-  "detectionMethod" to detectionMethod
+  "detectionMethod" to detectionMethod,
+  "sessionId" to sessionId,
+  "streamOffsetMs" to streamOffsetMs?.toLong(),
+  "frameIndex" to frameIndex?.toLong()
+)
+
+private fun SessionSummary.toJsMap(): Map<String, Any?> = mapOf(
+  "sessionId" to sessionId,
+  "durationMs" to durationMs.toLong(),
+  "buffersProcessed" to buffersProcessed.toLong(),
+  "uniqueSigners" to uniqueSigners,
+  "peakConfidence" to peakConfidence
+)
+
+private fun SonicConfig.toJsMap(): Map<String, Any?> = mapOf(
+  "sampleRate" to sampleRate.toInt(),
+  "frameSizeMs" to frameSizeMs.toInt(),
+  "detectionThreshold" to detectionThreshold,
+  "spreadingFactor" to spreadingFactor.toInt(),
+  "enableChirpSync" to enableChirpSync
 )
 
 private fun uniffi.vouch_sonic_core.VerificationResult.toJsMap(): Map<String, Any?> = mapOf(
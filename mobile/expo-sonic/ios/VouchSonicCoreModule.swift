@@ -36,7 +36,7 @@ public final class VouchSonicCoreModule: Module {
   public func definition() -> ModuleDefinition {
     Name("VouchSonicCore")
 
-    Events("onWatermark", "onAudioLevel", "onError", "onStateChange")
+    Events("onWatermark", "onWatermarkStillPresent", "onAudioLevel", "onError", "onStateChange", "onSignerConfirmed", "onConfigChange")
 
     AsyncFunction("getVersion") { () -> String in
       getVersion()
@@ -49,13 +49,21 @@ public final class VouchSonicCoreModule: Module {
       return id
     }
 
-    AsyncFunction("startListening") { (listenerId: String) throws in
+    AsyncFunction("startListening") { (listenerId: String) throws -> String in
       let listener = try self.requireListener(listenerId)
-      try listener.startListening(callback: SonicCallback(module: self, listenerId: listenerId))
+      return try listener.startListening(callback: SonicCallback(module: self, listenerId: listenerId))
     }
 
-    AsyncFunction("stopListening") { (listenerId: String) throws in
-      try self.requireListener(listenerId).stopListening()
+    AsyncFunction("stopListening") { (listenerId: String) throws -> [String: Any?] in
+      try self.requireListener(listenerId).stopListening().toDict()
+    }
+
+    AsyncFunction("pauseListening") { (listenerId: String) throws in
+      try self.requireListener(listenerId).pause()
+    }
+
+    AsyncFunction("resumeListening") { (listenerId: String) throws in
+      try self.requireListener(listenerId).resume()
     }
 
     AsyncFunction("processBuffer") { (listenerId: String, pcmDataB64: String) throws -> [String: Any?] in
@@ -82,7 +90,8 @@ public final class VouchSonicCoreModule: Module {
     }
 
     AsyncFunction("disposeListener") { (listenerId: String) in
-      self.lock.lock(); self.listeners.removeValue(forKey: listenerId); self.lock.unlock()
+      self.lock.lock(); let listener = self.listeners.removeValue(forKey: listenerId); self.lock.unlock()
+      listener?.shutdown()
     }
 
     AsyncFunction("verifySignature") {
@@ -125,6 +134,11 @@ private final class SonicCallback: WatermarkCallback {
   func onWatermarkDetected(result: WatermarkResult) {
     module?.emit("onWatermark", ["listenerId": listenerId, "result": result.toDict()])
   }
+  func onWatermarkStillPresent(result: WatermarkResult, presentForMs: UInt64) {
+    module?.emit("onWatermarkStillPresent", [
+      "listenerId": listenerId, "result": result.toDict(), "presentForMs": presentForMs,
+    ])
+  }
   func onAudioLevelChanged(levelDb: Float) {
     module?.emit("onAudioLevel", ["listenerId": listenerId, "levelDb": levelDb])
   }
@@ -134,6 +148,14 @@ private final class SonicCallback: WatermarkCallback {
   func onStateChanged(state: ListenerState) {
     module?.emit("onStateChange", ["listenerId": listenerId, "state": state.toJs()])
   }
+  func onSignerConfirmed(signer: String, accumulatedConfidence: Float) {
+    module?.emit("onSignerConfirmed", [
+      "listenerId": listenerId, "signer": signer, "accumulatedConfidence": accumulatedConfidence,
+    ])
+  }
+  func onConfigChanged(old: SonicConfig, new: SonicConfig) {
+    module?.emit("onConfigChange", ["listenerId": listenerId, "old": old.toDict(), "new": new.toDict()])
+  }
 }
 
 // MARK: - conversion helpers
@@ -143,8 +165,11 @@ private extension ListenerState {
     switch self {
     case .idle: return "Idle"
     case .listening: return "Listening"
+    case .acquiring: return "Acquiring"
     case .processing: return "Processing"
+    case .paused: return "Paused"
     case .error: return "Error"
+    case .closed: return "Closed"
     }
   }
 }
@@ -160,6 +185,33 @@ private extension WatermarkResult {
       "covenantJson": covenantJson as Any?,
       "audioQuality": audioQuality,
       "detectionMethod": detectionMethod,
+      "sessionId": sessionId as Any?,
+      "streamOffsetMs": streamOffsetMs.map { Double($0) } as Any?,
+      "frameIndex": frameIndex.map { Double($0) } as Any?,
+    ]
+  }
+}
+
+private extension SessionSummary {
+  func toDict() -> [String: Any?] {
+    return [
+      "sessionId": sessionId,
+      "durationMs": Double(durationMs),
+      "buffersProcessed": Double(buffersProcessed),
+      "uniqueSigners": uniqueSigners,
+      "peakConfidence": peakConfidence,
+    ]
+  }
+}
+
+private extension SonicConfig {
+  func toDict() -> [String: Any?] {
+    return [
+      "sampleRate": Int(sampleRate),
+      "frameSizeMs": Int(frameSizeMs),
+      "detectionThreshold": detectionThreshold,
+      "spreadingFactor": Int(spreadingFactor),
+      "enableChirpSync": enableChirpSync,
     ]
   }
 }
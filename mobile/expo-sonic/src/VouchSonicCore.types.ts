@@ -27,8 +27,24 @@ export interface WatermarkResult {
   payloadHash: string | null;
   covenantJson: string | null;
   audioQuality: number;
-  /** "spread_spectrum" | "chirp" | "mock" | ... */
+  /** "chirp_v3" | "none" | "clipped" */
   detectionMethod: string;
+  /** Listening session the result was produced in; null when not listening. */
+  sessionId: string | null;
+  /** Where the buffer or window starts in the session's audio, in ms. */
+  streamOffsetMs: number | null;
+  /** Index of the buffer or window within the session. */
+  frameIndex: number | null;
+}
+
+/** Returned by `stopListening`: what happened during the session. */
+export interface SessionSummary {
+  sessionId: string;
+  durationMs: number;
+  buffersProcessed: number;
+  /** Signer DIDs, or payload hashes for signers resolved server-side. */
+  uniqueSigners: string[];
+  peakConfidence: number;
 }
 
 export interface VerificationResult {
@@ -37,13 +53,17 @@ export interface VerificationResult {
   errorMessage: string | null;
 }
 
-export type ListenerState = 'Idle' | 'Listening' | 'Processing' | 'Error';
+export type ListenerState = 'Idle' | 'Listening' | 'Acquiring' | 'Processing' | 'Paused' | 'Error' | 'Closed';
 
 export interface SonicEventHandlers {
   onWatermarkDetected?: (result: WatermarkResult) => void;
+  /** A watermark whose repeated detections are collapsed is still being detected. */
+  onWatermarkStillPresent?: (result: WatermarkResult, presentForMs: number) => void;
   onAudioLevelChanged?: (levelDb: number) => void;
   onError?: (message: string) => void;
   onStateChanged?: (state: ListenerState) => void;
+  onSignerConfirmed?: (signer: string, accumulatedConfidence: number) => void;
+  onConfigChanged?: (oldConfig: SonicConfig, newConfig: SonicConfig) => void;
 }
 
 // ---- Native event payloads (carry the listenerId so the JS layer can route) -
@@ -52,6 +72,11 @@ export interface WatermarkEventPayload {
   listenerId: string;
   result: WatermarkResult;
 }
+export interface WatermarkStillPresentEventPayload {
+  listenerId: string;
+  result: WatermarkResult;
+  presentForMs: number;
+}
 export interface AudioLevelEventPayload {
   listenerId: string;
   levelDb: number;
@@ -64,12 +89,25 @@ export interface StateEventPayload {
   listenerId: string;
   state: ListenerState;
 }
+export interface SignerConfirmedEventPayload {
+  listenerId: string;
+  signer: string;
+  accumulatedConfidence: number;
+}
+export interface ConfigChangeEventPayload {
+  listenerId: string;
+  old: SonicConfig;
+  new: SonicConfig;
+}
 
 export type VouchSonicCoreModuleEvents = {
   onWatermark: (payload: WatermarkEventPayload) => void;
+  onWatermarkStillPresent: (payload: WatermarkStillPresentEventPayload) => void;
   onAudioLevel: (payload: AudioLevelEventPayload) => void;
   onError: (payload: ErrorEventPayload) => void;
   onStateChange: (payload: StateEventPayload) => void;
+  onSignerConfirmed: (payload: SignerConfirmedEventPayload) => void;
+  onConfigChange: (payload: ConfigChangeEventPayload) => void;
 };
 
 /**
@@ -80,8 +118,12 @@ export type VouchSonicCoreModuleEvents = {
 export interface VouchSonicCoreNativeModule {
   getVersion(): Promise<string>;
   createListener(config: SonicConfig): Promise<string>;
-  startListening(listenerId: string): Promise<void>;
-  stopListening(listenerId: string): Promise<void>;
+  /** Resolves to the new listening session's ID. */
+  startListening(listenerId: string): Promise<string>;
+  stopListening(listenerId: string): Promise<SessionSummary>;
+  /** Pause without losing streamed state; resume where it left off. */
+  pauseListening(listenerId: string): Promise<void>;
+  resumeListening(listenerId: string): Promise<void>;
   /** pcmData is base64-encoded 16-bit LE mono PCM. */
   processBuffer(listenerId: string, pcmData: string): Promise<WatermarkResult>;
   processSamples(listenerId: string, samples: number[]): Promise<WatermarkResult>;
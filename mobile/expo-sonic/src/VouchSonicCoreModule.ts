@@ -11,8 +11,10 @@ declare class VouchSonicCoreModule
 {
   getVersion(): Promise<string>;
   createListener(config: import('./VouchSonicCore.types').SonicConfig): Promise<string>;
-  startListening(listenerId: string): Promise<void>;
-  stopListening(listenerId: string): Promise<void>;
+  startListening(listenerId: string): Promise<string>;
+  stopListening(listenerId: string): Promise<import('./VouchSonicCore.types').SessionSummary>;
+  pauseListening(listenerId: string): Promise<void>;
+  resumeListening(listenerId: string): Promise<void>;
   processBuffer(
     listenerId: string,
     pcmData: string
@@ -21,6 +21,7 @@ import type { EventSubscription } from 'expo-modules-core';
 import VouchSonicCore from './VouchSonicCoreModule';
 import type {
   ListenerState,
+  SessionSummary,
   SonicConfig,
   SonicEventHandlers,
   VerificationResult,
@@ -108,6 +109,9 @@ export class SonicListener {
       VouchSonicCore.addListener('onWatermark', (p) => {
         if (p.listenerId === id) this.handlers.onWatermarkDetected?.(p.result);
       }),
+      VouchSonicCore.addListener('onWatermarkStillPresent', (p) => {
+        if (p.listenerId === id) this.handlers.onWatermarkStillPresent?.(p.result, p.presentForMs);
+      }),
       VouchSonicCore.addListener('onAudioLevel', (p) => {
         if (p.listenerId === id) this.handlers.onAudioLevelChanged?.(p.levelDb);
       }),
@@ -117,22 +121,46 @@ export class SonicListener {
       VouchSonicCore.addListener('onStateChange', (p) => {
         if (p.listenerId === id) this.handlers.onStateChanged?.(p.state);
       }),
+      VouchSonicCore.addListener('onSignerConfirmed', (p) => {
+        if (p.listenerId === id) this.handlers.onSignerConfirmed?.(p.signer, p.accumulatedConfidence);
+      }),
+      VouchSonicCore.addListener('onConfigChange', (p) => {
+        if (p.listenerId === id) this.handlers.onConfigChanged?.(p.old, p.new);
+      }),
     ];
   }
 
-  async start(handlers?: SonicEventHandlers): Promise<void> {
+  /** Start listening; resolves to the session ID every result carries. */
+  async start(handlers?: SonicEventHandlers): Promise<string> {
     await this.ensureInitialized();
     if (handlers) this.handlers = handlers;
-    await VouchSonicCore!.startListening(this.listenerId!);
+    const sessionId = await VouchSonicCore!.startListening(this.listenerId!);
     this._isListening = true;
     this.handlers.onStateChanged?.('Listening');
+    return sessionId;
   }
 
-  async stop(): Promise<void> {
-    if (!VouchSonicCore || !this.listenerId) return;
-    await VouchSonicCore.stopListening(this.listenerId);
+  /** Stop listening; resolves to the session's summary (null if never started). */
+  async stop(): Promise<SessionSummary | null> {
+    if (!VouchSonicCore || !this.listenerId) return null;
+    const summary = await VouchSonicCore.stopListening(this.listenerId);
     this._isListening = false;
     this.handlers.onStateChanged?.('Idle');
+    return summary;
+  }
+
+  /**
+   * Pause while the app is briefly backgrounded, keeping the native stream
+   * state and handlers; `resume()` carries on where it left off.
+   */
+  async pause(): Promise<void> {
+    if (!VouchSonicCore || !this.listenerId) return;
+    await VouchSonicCore.pauseListening(this.listenerId);
+  }
+
+  async resume(): Promise<void> {
+    if (!VouchSonicCore || !this.listenerId) return;
+    await VouchSonicCore.resumeListening(this.listenerId);
   }
 
   async processBuffer(pcmDataB64: string): Promise<WatermarkResult> {